@@ -0,0 +1,62 @@
+//! Plays a program that shows the last key held with a controller pressing every key in turn,
+//! and prints the display at the end
+//!
+//! `cargo run --example bot`
+
+use rc_8::backend::{self, controller, interfaces, CoreKind};
+use rc_8::defaults;
+
+/// Ticks a key is held for, and then released for before the next one
+const PRESS_TICKS: u64 = 15;
+const TICKS: u64 = PRESS_TICKS * 2 * 11;
+
+#[rustfmt::skip]
+const PROGRAM: [u8; 20] = [
+    0x60, 0x00, // V0 = 0, the key checked
+    0xE0, 0x9E, // skip the next instruction if the key in V0 is held
+    0x12, 0x0C, // jump to 0x20C
+    0x00, 0xE0, // clear the display
+    0xF0, 0x29, // I = the character of V0
+    0xD1, 0x25, // draw the character at V1, V2
+    0x70, 0x01, // V0 += 1
+    0x30, 0x10, // skip the next instruction if V0 = 16
+    0x12, 0x02, // jump to 0x202
+    0x12, 0x00, // jump to 0x200
+];
+
+fn main() {
+    let mut core = CoreKind::Interpreter.create(backend::Backend::new());
+    let mut tick = 0;
+
+    // the controller could as well look at the display, e.g. to feed it to an agent
+    let mut player = |_: &interfaces::DisplayBuffer, _: &backend::Backend| {
+        let key = tick / (PRESS_TICKS * 2) % backend::KEY_COUNT as u64;
+        let held = tick % (PRESS_TICKS * 2) < PRESS_TICKS;
+        tick += 1;
+
+        (held as u16) << key
+    };
+
+    core.load(None, &PROGRAM).unwrap();
+
+    let display_buffer = controller::play(
+        core.as_mut(),
+        &mut player,
+        defaults::INSTRUCTIONS_PER_TICK,
+        TICKS,
+    )
+    .unwrap();
+
+    for row in display_buffer.buffer.iter().take(backend::CHARACTER_SIZE) {
+        let line: String = row
+            .iter()
+            .take(8)
+            .map(|pixel| match *pixel {
+                true => '#',
+                false => ' ',
+            })
+            .collect();
+
+        println!("{}", line);
+    }
+}
@@ -0,0 +1,39 @@
+//! Decodes every possible instruction a number of times and prints how long it took, along with how
+//! many of them are recognized
+//!
+//! `cargo run --release --example decode_benchmark`
+
+use std::hint;
+use std::time;
+
+use rc_8::backend::{self, Instruction, Op};
+
+const ROUNDS: usize = 1_000;
+
+fn main() {
+    let quirks = backend::Quirks::default();
+    let instructions: Vec<Instruction> = (0..=u16::MAX)
+        .map(|word| Instruction::new(word.to_be_bytes()))
+        .collect();
+
+    let recognized = instructions
+        .iter()
+        .filter(|instruction| Op::decode(**instruction, &quirks).is_some())
+        .count();
+
+    let start = time::Instant::now();
+
+    for _ in 0..ROUNDS {
+        for instruction in instructions.iter() {
+            hint::black_box(Op::decode(hint::black_box(*instruction), &quirks));
+        }
+    }
+
+    println!(
+        "{} rounds of {} instructions, {} of them recognized, decoded in {:?}",
+        ROUNDS,
+        instructions.len(),
+        recognized,
+        start.elapsed()
+    );
+}
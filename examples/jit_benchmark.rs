@@ -0,0 +1,71 @@
+//! Runs an arithmetic heavy program through the interpreter and the JIT core and prints how long
+//! each of them took
+//!
+//! `cargo run --release --features jit --example jit_benchmark`
+
+use std::num;
+use std::time;
+
+use rc_8::backend::{self, interfaces, CoreKind};
+
+/// Instructions per tick, far more than the default speed on purpose for the time to go into the
+/// instructions rather than into the timers and the work done once per tick
+const INSTRUCTIONS_PER_TICK: u16 = 700;
+const TICKS: usize = 10_000;
+
+#[rustfmt::skip]
+const PROGRAM: [u8; 28] = [
+    0x60, 0x01, // V0 = 0x01
+    0x61, 0x02, // V1 = 0x02
+    0x80, 0x14, // V0 += V1
+    0x81, 0x05, // V1 -= V0
+    0x82, 0x06, // V2 = V0 >> 1
+    0x83, 0x1E, // V3 = V1 << 1
+    0x72, 0x03, // V2 += 0x03
+    0x84, 0x31, // V4 |= V3
+    0x84, 0x23, // V4 ^= V2
+    0xA3, 0x00, // I = 0x300
+    0xF4, 0x1E, // I += V4
+    0x85, 0x47, // V5 = V4 - V5
+    0x86, 0x52, // V6 &= V5
+    0x12, 0x04, // jump to 0x204
+];
+
+fn main() {
+    let n = num::NonZeroU16::new(INSTRUCTIONS_PER_TICK).unwrap();
+    let keyboard_state = interfaces::KeyboardState::default();
+
+    let mut registers = Vec::new();
+
+    for kind in CoreKind::ALL {
+        let mut core = kind.create(backend::Backend::new());
+        let mut display_buffer = interfaces::DisplayBuffer::new(false);
+
+        core.load(None, &PROGRAM).unwrap();
+
+        let start = time::Instant::now();
+
+        for _ in 0..TICKS {
+            core.tick(n, (&mut display_buffer, &keyboard_state))
+                .unwrap();
+        }
+
+        println!(
+            "{:?}: {} ticks of {} instructions in {:?}",
+            kind,
+            TICKS,
+            n,
+            start.elapsed()
+        );
+
+        registers.push((
+            core.state().registers.general,
+            core.state().registers.address,
+        ));
+    }
+
+    assert!(
+        registers.windows(2).all(|pair| pair[0] == pair[1]),
+        "the cores ended up in different states"
+    );
+}
@@ -0,0 +1,315 @@
+# Menu
+
+menu-tab-program = Program
+menu-tab-display = Display
+menu-tab-audio = Audio
+menu-tab-input = Input
+menu-tab-quirks = Quirks
+menu-tab-debug = Debug
+menu-hotkeys = Hotkeys
+
+menu-font = Font
+menu-builtin-font = Built-in Font
+menu-big-font = Big Font
+menu-font-replaced = The font is replaced in the memory of the paused program, which may still draw the characters it copied or whose addresses it kept
+menu-program = Program
+menu-none = None
+menu-load = 📂 Load { $item }
+menu-clear = Clear { $item }
+menu-author = by { $author }
+menu-recommended-settings = Its recommended quirks and speed are used
+menu-patch = Patch
+menu-sprites = Sprites
+menu-export-sprites = Export Sprites
+menu-library = Library
+menu-library-directory = Library Folder
+menu-library-search = Search the library…
+menu-playlist = Playlist
+menu-auto-advance = Auto Advance
+menu-minutes-suffix = { " " }min
+
+menu-core = Core
+menu-key-priority = FX0A Key Priority
+menu-instructions-per-tick = Instructions Per Tick
+menu-theme = Theme
+menu-active-color = Active Color
+menu-inactive-color = Inactive Color
+menu-rotation = Rotation
+menu-mirrored = Mirrored
+menu-scaling-filter = Scaling Filter
+menu-stretch = Stretch
+menu-fade-effect = Fade Effect
+menu-preview = Preview
+menu-visual-beep = Visual Beep
+menu-audio-device = Audio Device
+menu-default = Default
+menu-audio-buffer-size = Audio Buffer Size
+menu-frames = { $frames } frames
+menu-beep-sound = Beep Sound
+menu-built-in = Built-In
+menu-pitched-beep = Pitch The Beep By Its Length
+menu-controls-overlay = Controls Overlay
+menu-hash-overlay = Display Hash Overlay
+menu-log = Log
+menu-throttle-in-background = Throttle In Background
+menu-self-modification-warnings = Self-Modification Warnings
+menu-pause-on-halt = Pause Halted Programs
+menu-stall-warning = Warn About Stalls After
+menu-seconds-suffix = { " " }s
+menu-step-repeat-rate = Step Repeat Rate
+menu-per-second-suffix = { " " }/s
+menu-peripherals = Pseudo-Peripherals
+menu-input-latency = Input Latency
+menu-milliseconds = { $milliseconds } ms
+menu-audio-latency = Audio Latency
+menu-language = Language
+menu-test-pattern = Test Pattern
+menu-ui-scale = UI Scale
+menu-always-on-top = Always On Top
+menu-remember-window-geometry = Remember The Window Size And Position
+menu-press-key = Press a key…
+
+menu-start = ▶ Start
+menu-resume = ⟲ Resume Last Session
+menu-loading = Loading…
+menu-diagnostics = ▶ Diagnostics
+menu-save-crash-report = Save Crash Report
+menu-conformance = Check The Arithmetic Of The Core
+menu-dump-display = Save The Display As It Is
+menu-load-crash-state = Load The State At The Crash
+menu-restart = ⟲ Restart Program
+menu-stop = ■ Stop
+
+# Debugger
+
+debugger-add = Add
+debugger-address = Address
+debugger-break-on-write = Break On Write
+debugger-breakpoints = Breakpoints
+debugger-references = Cross-References
+debugger-references-to = References to { $address }
+debugger-references-none = No instruction jumps to, calls or points I to it
+debugger-event-frame = Tick { $frame } at { $address }: { $events }
+debugger-rewound = Rewound to tick { $frame }
+debugger-call-graph = Call Graph
+debugger-subroutine = Subroutine at { $address }, called from
+debugger-subroutine-entry = Nowhere, it's the entry of the program
+debugger-subroutine-none = No subroutine starts at { $address }
+debugger-rename = Rename
+debugger-stack = Stack
+debugger-stack-empty = The stack is empty
+debugger-stack-frame = { $depth }: { $subroutine }, returning to { $address }
+debugger-labels = Labels
+debugger-watches = Watches
+debugger-input-timeline = Input Timeline
+debugger-timeline-frame = Tick { $frame }, last instruction at { $address }, keys held: { $keys }
+debugger-timers = Timers
+debugger-timer-delay = Delay
+debugger-timer-sound = Sound
+debugger-timer-period = { $timer } timer reloaded every { $ticks } ticks ({ $frequency } Hz)
+debugger-timer-unreloaded = { $timer } timer not reloaded twice yet
+debugger-collisions = Collisions
+debugger-collisions-none = No sprite has collided yet
+debugger-collision = Tick { $frame }, { $address } at { $x }, { $y }, { $pixels } pixels
+debugger-flash-collisions = Flash The Collided Pixels
+debugger-export-octo = Export State For Octo
+debugger-import-octo = Import State From Octo
+debugger-magnifier = Magnifier
+debugger-detach-display = Display In A Separate Window
+debugger-display-window = Display
+debugger-magnifier-on = ({ $x }, { $y }) on
+debugger-magnifier-off = ({ $x }, { $y }) off
+debugger-pixel-undrawn = Not drawn over since the display was last cleared
+debugger-changes = Last step changed { $registers } registers, { $bytes } bytes and { $pixels } pixels
+debugger-statistics = Statistics
+debugger-statistics-total = Instructions
+debugger-statistics-draws = Draws
+debugger-statistics-sounds = Sounds
+debugger-statistics-key-reads = Key Reads
+debugger-memory = Memory
+debugger-search-equal = Equal To
+debugger-search-changed = Changed
+debugger-search-unchanged = Unchanged
+debugger-search-increased = Increased
+debugger-search-decreased = Decreased
+debugger-search-new = New Search
+debugger-search-matches = { $count } addresses match
+debugger-watch = Watch
+debugger-disassembly = Disassembly
+debugger-export-disassembly = Export The Disassembly
+debugger-control-flow = Control Flow
+debugger-export-control-flow = Export The Graph For Graphviz
+debugger-control-flow-edge = { $kind } { $address }
+debugger-disassembly-search = DXYN, 0x300, DRW…
+debugger-disassembly-search-matches = { $count } matches
+debugger-disassembly-search-current = { $current } of { $count }
+debugger-disassembly-search-none = No match
+debugger-copy-selection = Copy The Selection
+debugger-clear-selection = Clear The Selection
+debugger-selection-hint = Shift-click bytes or instructions to select them
+debugger-registers = Registers
+debugger-freeze = Pin the register to a value, written back after every instruction
+debugger-step-hint = Step to inspect the state of the program
+debugger-paused = Paused
+debugger-program = Program
+
+# Edges
+
+edge-call = ⤷ calls
+edge-jump = → jumps to
+edge-next = ↓ goes on to
+edge-skip = ↷ skips to
+
+# Events
+
+event-draw = drew
+event-beep = beeped
+event-key-read = read the keys
+event-error = stopped with an error
+event-none = nothing of note
+
+# Halted
+
+halted = Program Halted
+halted-message = The program was paused in a loop at { $address } that it can't leave.
+halted-keep-running = Keep Running
+halted-restart = Restart
+
+# Integrity
+
+integrity-size = Size
+integrity-bytes = { $bytes } bytes
+integrity-sha1 = SHA-1
+integrity-crc32 = CRC32
+
+# Log
+
+log = Log
+log-clear = Clear
+log-dropped-messages = { $messages } older messages were dropped
+log-empty = Programs print here through the console of the pseudo-peripherals
+
+# Conformance
+
+conformance = Conformance
+conformance-passed = All { $cases } cases match the reference
+conformance-failed = { $divergences } of { $cases } cases diverge from the reference
+conformance-register = { $register } is { $actual } instead of { $expected }
+conformance-memory = I+{ $offset } is { $actual } instead of { $expected }
+
+# Cores
+
+core-interpreter = Interpreter
+core-jit = Recompiler (experimental)
+
+# Fonts
+
+font-standard = Standard
+font-cosmac-vip = COSMAC VIP
+font-dream-6800 = DREAM 6800
+font-eti-660 = ETI-660
+
+# Key Priorities
+
+key-priority-lowest-index = Lowest Key
+key-priority-most-recent = Most Recent Key
+
+# References
+
+reference-call = Called from { $address }
+reference-jump = Jumped to from { $address }
+reference-load = Pointed I to from { $address }
+
+# Rotations
+
+rotation-none = None
+rotation-quarter = 90°
+rotation-half = 180°
+rotation-three-quarters = 270°
+
+# Themes
+
+theme-default = Default
+theme-high-contrast = High Contrast
+theme-colorblind-safe = Colorblind Safe
+
+# Scaling Filters
+
+scaling-filter-nearest = Nearest
+scaling-filter-bilinear = Bilinear
+scaling-filter-prescaled = Prescaled
+
+# Stretches
+
+stretch-fit = Fit
+stretch-fill = Fill
+stretch-four-thirds = 4:3
+
+# Test Patterns
+
+test-pattern-checkerboard = Checkerboard
+test-pattern-noise = Noise
+
+# Quirks
+
+quirk-extended-memory = XO-CHIP Memory
+quirk-jump-vx = Jump With VX
+quirk-key-checks-continue = Key Checks Continue Tick
+quirk-load-address = Load Address
+quirk-load-store-increment = Load/Store Increments I
+quirk-shift-vy = Shift VY
+quirk-vf-reset = VF Reset
+quirk-wrap-sprites = Wrap Sprites
+
+# Actions
+
+action-continue = Continue
+action-load-state = Load State
+action-log = Log
+action-next-program = Next Program
+action-pause = Pause
+action-previous-program = Previous Program
+action-reset = Reset
+action-save-state = Save State
+action-screenshot = Screenshot
+action-step = Step
+action-turbo = Turbo
+
+# Errors
+
+error-fatal = fatal error, { $error }
+error-file-not-found = file '{ $file }' does not exist
+error-beep-sound = couldn't load the beep sound, { $error }
+error-font = couldn't load the font, { $error }
+error-language = couldn't load the language, { $error }
+error-metadata = couldn't load the program metadata, { $error }
+error-program = couldn't load the program, { $error }
+error-program-config = couldn't apply the program configuration, { $error }
+error-program-exited = the program exited
+error-audio-device-gone = the audio device '{ $device }' is gone, the default one is used
+error-patch = couldn't apply the patch '{ $patch }', { $error }
+
+load-error-font-size = the font is { $size } bytes long instead of { $expected }
+load-error-not-a-rom = not a program
+load-error-odd-length = the program is { $size } bytes long, an odd number, its last instruction is cut
+load-error-program-size = the program is { $size } bytes long, more than the { $space } bytes after its load address
+
+frontend-error-divergence = diverged from the reference after instruction { $instruction } at { $address }, { $differences }
+frontend-error-self-modification = the program overwrote its code at { $address }
+frontend-error-halted = the program halted in a loop at { $address }
+frontend-error-stalled = the program changed nothing for { $seconds } seconds at { $address }, it may be stuck
+
+backend-error-instruction = instruction { $instruction } at { $address }, { $kind }
+backend-error-address = at { $address }, { $kind }
+backend-error-context = { $error }
+    { $context }
+
+backend-error-debug-break = debug break
+backend-error-memory-overflow = attempt to access invalid memory address
+backend-error-program-exited = the program exited
+backend-error-program-invalid = attempt to load invalid program
+backend-error-program-not-loaded = attempt to run without loading any program
+backend-error-stack-overflow = attempt to call a coroutine when the stack is full
+backend-error-stack-underflow = attempt to return when the stack is empty
+backend-error-unrecognized-instruction = unrecognized instruction
+backend-error-unrecognized-sprite = attempt to load unrecognized sprite
@@ -0,0 +1,227 @@
+use std::collections;
+use std::error;
+use std::fmt;
+
+use crate::backend;
+
+#[derive(Debug)]
+pub struct AssemblerError {
+    /// Number of the line the error is on, starting from 1
+    pub line: usize,
+    pub kind: AssemblerErrorKind,
+}
+
+#[derive(Debug)]
+pub enum AssemblerErrorKind {
+    DuplicateLabel(String),
+    InvalidOperands,
+    UnknownLabel(String),
+    UnknownMnemonic(String),
+    ValueOutOfRange,
+}
+
+/// An operand of an instruction, as written in the source
+enum Operand {
+    Bcd,
+    BigFont,
+    Delay,
+    Font,
+    Index,
+    Indirect,
+    Key,
+    Long,
+    Register(u16),
+    Sound,
+    Value(u16),
+}
+
+/// Assembles the source written with the mnemonics of the disassembler into a program, with labels
+/// (`name:`), `db` directives and comments starting with `;`
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssemblerError> {
+    let mut labels = collections::HashMap::new();
+    let mut statements = Vec::new();
+    let mut address = backend::MEMORY_PADDING;
+
+    // the labels are gathered first for instructions to refer to the ones after them
+    for (number, line) in source.lines().enumerate() {
+        let mut line = line.split(';').next().unwrap_or_default().trim();
+
+        if let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+
+            if labels.insert(label.to_owned(), address).is_some() {
+                return Err(AssemblerError {
+                    line: number + 1,
+                    kind: AssemblerErrorKind::DuplicateLabel(label.to_owned()),
+                });
+            }
+
+            line = rest.trim();
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, operands) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let operands: Vec<&str> = match operands.trim() {
+            "" => Vec::new(),
+            operands => operands.split(',').map(str::trim).collect(),
+        };
+
+        address += match mnemonic.eq_ignore_ascii_case("db") {
+            true => operands.len(),
+            false => 2,
+        };
+
+        statements.push((number + 1, mnemonic, operands));
+    }
+
+    let mut program = Vec::new();
+
+    for (line, mnemonic, operands) in statements {
+        let error = |kind| AssemblerError { line, kind };
+
+        let operands = operands
+            .iter()
+            .map(|operand| parse(operand, &labels))
+            .collect::<Result<Vec<Operand>, AssemblerErrorKind>>()
+            .map_err(error)?;
+
+        if mnemonic.eq_ignore_ascii_case("db") {
+            for operand in operands {
+                match operand {
+                    Operand::Value(value) if value <= 0xFF => program.push(value as u8),
+                    Operand::Value(_) => return Err(error(AssemblerErrorKind::ValueOutOfRange)),
+                    _ => return Err(error(AssemblerErrorKind::InvalidOperands)),
+                }
+            }
+
+            continue;
+        }
+
+        let word = encode(mnemonic, &operands).map_err(error)?;
+        program.extend(word.to_be_bytes());
+    }
+
+    Ok(program)
+}
+
+/// Returns the instruction of `mnemonic` with `operands`
+fn encode(mnemonic: &str, operands: &[Operand]) -> Result<u16, AssemblerErrorKind> {
+    use Operand::*;
+
+    let address = |value: u16| match value <= 0xFFF {
+        true => Ok(value),
+        false => Err(AssemblerErrorKind::ValueOutOfRange),
+    };
+    let byte = |value: u16| match value <= 0xFF {
+        true => Ok(value),
+        false => Err(AssemblerErrorKind::ValueOutOfRange),
+    };
+
+    Ok(match (mnemonic.to_ascii_uppercase().as_str(), operands) {
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("EXIT", []) => 0x00FD,
+        ("SYS", [Value(nnn)]) => address(*nnn)?,
+        ("JP", [Value(nnn)]) => 0x1000 | address(*nnn)?,
+        ("CALL", [Value(nnn)]) => 0x2000 | address(*nnn)?,
+        ("SE", [Register(x), Value(nn)]) => 0x3000 | x << 8 | byte(*nn)?,
+        ("SNE", [Register(x), Value(nn)]) => 0x4000 | x << 8 | byte(*nn)?,
+        ("SE", [Register(x), Register(y)]) => 0x5000 | x << 8 | y << 4,
+        ("LD", [Register(x), Value(nn)]) => 0x6000 | x << 8 | byte(*nn)?,
+        ("ADD", [Register(x), Value(nn)]) => 0x7000 | x << 8 | byte(*nn)?,
+        ("LD", [Register(x), Register(y)]) => 0x8000 | x << 8 | y << 4,
+        ("OR", [Register(x), Register(y)]) => 0x8001 | x << 8 | y << 4,
+        ("AND", [Register(x), Register(y)]) => 0x8002 | x << 8 | y << 4,
+        ("XOR", [Register(x), Register(y)]) => 0x8003 | x << 8 | y << 4,
+        ("ADD", [Register(x), Register(y)]) => 0x8004 | x << 8 | y << 4,
+        ("SUB", [Register(x), Register(y)]) => 0x8005 | x << 8 | y << 4,
+        ("SHR", [Register(x), Register(y)]) => 0x8006 | x << 8 | y << 4,
+        ("SUBN", [Register(x), Register(y)]) => 0x8007 | x << 8 | y << 4,
+        ("SHL", [Register(x), Register(y)]) => 0x800E | x << 8 | y << 4,
+        ("SNE", [Register(x), Register(y)]) => 0x9000 | x << 8 | y << 4,
+        ("LD", [Index, Value(nnn)]) => 0xA000 | address(*nnn)?,
+        ("JP", [Register(0), Value(nnn)]) => 0xB000 | address(*nnn)?,
+        ("RND", [Register(x), Value(nn)]) => 0xC000 | x << 8 | byte(*nn)?,
+        ("DRW", [Register(x), Register(y), Value(n)]) if *n <= 0xF => 0xD000 | x << 8 | y << 4 | n,
+        ("SKP", [Register(x)]) => 0xE09E | x << 8,
+        ("SKNP", [Register(x)]) => 0xE0A1 | x << 8,
+        ("LD", [Index, Long]) => 0xF000,
+        ("LD", [Register(x), Delay]) => 0xF007 | x << 8,
+        ("LD", [Register(x), Key]) => 0xF00A | x << 8,
+        ("LD", [Delay, Register(x)]) => 0xF015 | x << 8,
+        ("LD", [Sound, Register(x)]) => 0xF018 | x << 8,
+        ("ADD", [Index, Register(x)]) => 0xF01E | x << 8,
+        ("LD", [Font, Register(x)]) => 0xF029 | x << 8,
+        ("LD", [BigFont, Register(x)]) => 0xF030 | x << 8,
+        ("LD", [Bcd, Register(x)]) => 0xF033 | x << 8,
+        ("LD", [Indirect, Register(x)]) => 0xF055 | x << 8,
+        ("LD", [Register(x), Indirect]) => 0xF065 | x << 8,
+        (
+            "CLS" | "RET" | "EXIT" | "SYS" | "JP" | "CALL" | "SE" | "SNE" | "LD" | "ADD" | "OR"
+            | "AND" | "XOR" | "SUB" | "SHR" | "SUBN" | "SHL" | "RND" | "DRW" | "SKP" | "SKNP",
+            _,
+        ) => return Err(AssemblerErrorKind::InvalidOperands),
+        _ => return Err(AssemblerErrorKind::UnknownMnemonic(mnemonic.to_owned())),
+    })
+}
+
+fn parse(
+    operand: &str,
+    labels: &collections::HashMap<String, usize>,
+) -> Result<Operand, AssemblerErrorKind> {
+    let value = |digits: &str, radix| {
+        u16::from_str_radix(digits, radix).map_err(|_| AssemblerErrorKind::ValueOutOfRange)
+    };
+
+    Ok(match operand.to_ascii_uppercase().as_str() {
+        "B" => Operand::Bcd,
+        "DT" => Operand::Delay,
+        "F" => Operand::Font,
+        "HF" => Operand::BigFont,
+        "I" => Operand::Index,
+        "[I]" => Operand::Indirect,
+        "K" => Operand::Key,
+        "LONG" => Operand::Long,
+        "ST" => Operand::Sound,
+        register
+            if register.len() == 2
+                && register.starts_with('V')
+                && register.as_bytes()[1].is_ascii_hexdigit() =>
+        {
+            Operand::Register(value(&register[1..], 16)?)
+        }
+        number if number.starts_with("0X") => Operand::Value(value(&number[2..], 16)?),
+        number if number.starts_with(|c: char| c.is_ascii_digit()) => {
+            Operand::Value(value(number, 10)?)
+        }
+        _ => match labels.get(operand) {
+            Some(address) => Operand::Value(
+                u16::try_from(*address).map_err(|_| AssemblerErrorKind::ValueOutOfRange)?,
+            ),
+            None => return Err(AssemblerErrorKind::UnknownLabel(operand.to_owned())),
+        },
+    })
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, {}", self.line, self.kind)
+    }
+}
+
+impl fmt::Display for AssemblerErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateLabel(label) => write!(f, "label {} is defined more than once", label),
+            Self::InvalidOperands => write!(f, "invalid operands"),
+            Self::UnknownLabel(label) => write!(f, "unknown label {}", label),
+            Self::UnknownMnemonic(mnemonic) => write!(f, "unknown mnemonic {}", mnemonic),
+            Self::ValueOutOfRange => write!(f, "value out of range"),
+        }
+    }
+}
+
+impl error::Error for AssemblerError {}
@@ -0,0 +1,221 @@
+use super::{interfaces, Backend, BackendError, CoreKind, Instruction, Quirks, REGISTER_COUNT};
+
+/// Where `I` points during the cases, for `FX33` to store its digits at
+const BCD_ADDRESS: usize = 0x300;
+/// Values the operands of the cases take, around the edges of the carries, the borrows and the
+/// digits
+const VALUES: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x09, 0x0A, 0x0F, 0x10, 0x63, 0x64, 0x7F, 0x80, 0x81, 0xC7, 0xC8, 0xFE, 0xFF,
+];
+/// Registers the operands are put in, `VF` among them to check the flag is written after the
+/// result
+const OPERANDS: [(usize, usize); 4] = [(0x1, 0x2), (0x3, 0x3), (0xF, 0x1), (0x1, 0xF)];
+/// The `8XYN` instructions checked, with their operands left out
+const OPERATIONS: [u16; 8] = [
+    0x8001, 0x8002, 0x8003, 0x8004, 0x8005, 0x8006, 0x8007, 0x800E,
+];
+
+/// An instruction executed on its own from a known state of the registers
+#[derive(Clone, Copy, Debug)]
+pub struct Case {
+    pub instruction: Instruction,
+    pub registers: [u8; REGISTER_COUNT],
+}
+
+/// A case the core ended up in another state than the reference in
+#[derive(Debug)]
+pub struct Divergence {
+    pub actual: Result<Outcome, BackendError>,
+    pub case: Case,
+    pub expected: Outcome,
+}
+
+/// The state a case ends up in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outcome {
+    /// The 3 bytes `I` points to
+    pub memory: [u8; 3],
+    pub registers: [u8; REGISTER_COUNT],
+}
+
+/// The results of a conformance run
+#[derive(Debug)]
+pub struct Report {
+    /// Number of cases executed
+    pub cases: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+impl Case {
+    /// Executes the case on a new core of `kind`
+    fn execute(
+        &self,
+        kind: CoreKind,
+        quirks: Quirks,
+        display_buffer: &mut interfaces::DisplayBuffer,
+    ) -> Result<Outcome, BackendError> {
+        let mut state = Backend::new();
+        state.quirks = quirks;
+
+        let mut core = kind.create(state);
+        core.load(None, &self.instruction.word().to_be_bytes())?;
+
+        let state = core.state_mut();
+        state.registers.address = BCD_ADDRESS;
+        state.registers.general = self.registers;
+
+        core.step((display_buffer, &interfaces::KeyboardState::default()))?;
+
+        let state = core.state();
+
+        Ok(Outcome {
+            memory: state.memory[BCD_ADDRESS..BCD_ADDRESS + 3]
+                .try_into()
+                .unwrap(),
+            registers: state.registers.general,
+        })
+    }
+
+    /// Returns the state the case should end up in, worked out independently of the cores
+    fn expected(&self, quirks: Quirks) -> Outcome {
+        let (x, y) = (self.instruction.operand_x(), self.instruction.operand_y());
+        let (vx, vy) = (self.registers[x], self.registers[y]);
+        let mut outcome = Outcome {
+            memory: [0; 3],
+            registers: self.registers,
+        };
+
+        let shifted = match quirks.shift_vy {
+            true => vy,
+            false => vx,
+        };
+
+        let flagged = match (
+            self.instruction.operator_code(),
+            self.instruction.operand_n(),
+        ) {
+            (0x7, _) => {
+                outcome.registers[x] = vx.wrapping_add(self.instruction.operand_nn());
+                None
+            }
+            (0x8, code @ 0x1..=0x3) => {
+                outcome.registers[x] = match code {
+                    0x1 => vx | vy,
+                    0x2 => vx & vy,
+                    _ => vx ^ vy,
+                };
+
+                if quirks.vf_reset {
+                    outcome.registers[0xF] = 0;
+                }
+
+                None
+            }
+            (0x8, 0x4) => Some(vx.overflowing_add(vy)),
+            (0x8, 0x5) => Some((vx.wrapping_sub(vy), vx >= vy)),
+            (0x8, 0x6) => Some((shifted >> 1, shifted & 1 != 0)),
+            (0x8, 0x7) => Some((vy.wrapping_sub(vx), vy >= vx)),
+            (0x8, 0xE) => Some((shifted << 1, shifted & 0x80 != 0)),
+            (0xF, _) => {
+                outcome.memory = [vx / 100, vx / 10 % 10, vx % 10];
+                None
+            }
+            _ => unreachable!("no other instructions are generated"),
+        };
+
+        // the flag is written after the result, overwriting it when X is F
+        if let Some((result, flag)) = flagged {
+            outcome.registers[x] = result;
+            outcome.registers[0xF] = flag as u8;
+        }
+
+        outcome
+    }
+}
+
+/// Returns the cases of a run, every `8XYN` arithmetic and logic instruction and `7XNN` on the
+/// operand values, and `FX33` on every byte
+pub fn cases() -> Vec<Case> {
+    // the registers left alone are set apart from the operands, to catch stray writes
+    let registers = |operands: &[(usize, u8)]| {
+        let mut registers: [u8; REGISTER_COUNT] = std::array::from_fn(|i| 0xA0 | i as u8);
+
+        for (register, value) in operands {
+            registers[*register] = *value;
+        }
+
+        registers
+    };
+    let instruction = |word: u16| Instruction::new(word.to_be_bytes());
+    let mut cases = Vec::new();
+
+    for operation in OPERATIONS {
+        for (x, y) in OPERANDS {
+            let word = operation | (x as u16) << 8 | (y as u16) << 4;
+
+            for vx in VALUES {
+                // with both operands in the same register, there's a single value to try
+                let same = [vx];
+                let values = match x == y {
+                    true => &same[..],
+                    false => &VALUES[..],
+                };
+
+                for vy in values.iter().copied() {
+                    cases.push(Case {
+                        instruction: instruction(word),
+                        registers: registers(&[(x, vx), (y, vy)]),
+                    });
+                }
+            }
+        }
+    }
+
+    for x in [0x1, 0xF] {
+        for vx in VALUES {
+            for nn in VALUES {
+                cases.push(Case {
+                    instruction: instruction(0x7000 | (x as u16) << 8 | nn as u16),
+                    registers: registers(&[(x, vx)]),
+                });
+            }
+        }
+
+        for vx in u8::MIN..=u8::MAX {
+            cases.push(Case {
+                instruction: instruction(0xF033 | (x as u16) << 8),
+                registers: registers(&[(x, vx)]),
+            });
+        }
+    }
+
+    cases
+}
+
+/// Executes the cases on cores of `kind` with `quirks` and compares them against the reference
+pub fn run(kind: CoreKind, quirks: Quirks) -> Report {
+    let cases = cases();
+    let mut display_buffer = interfaces::DisplayBuffer::new(false);
+
+    let divergences = cases
+        .iter()
+        .filter_map(|case| {
+            let expected = case.expected(quirks);
+            let actual = case.execute(kind, quirks, &mut display_buffer);
+
+            match actual.as_ref() {
+                Ok(outcome) if *outcome == expected => None,
+                _ => Some(Divergence {
+                    actual,
+                    case: *case,
+                    expected,
+                }),
+            }
+        })
+        .collect();
+
+    Report {
+        cases: cases.len(),
+        divergences,
+    }
+}
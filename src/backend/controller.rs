@@ -0,0 +1,49 @@
+use std::num;
+
+use super::{interfaces, Backend, BackendError, Core, KEY_COUNT};
+
+/// Plays programs in place of a user, e.g. a script or an agent, shown the display before every
+/// tick and holding the keys it returns during it
+pub trait Controller: Send {
+    /// Returns the keys to hold during the next tick, as a bit mask with bit N for key N
+    fn keys(&mut self, display: &interfaces::DisplayBuffer, state: &Backend) -> u16;
+}
+
+impl<F> Controller for F
+where
+    F: FnMut(&interfaces::DisplayBuffer, &Backend) -> u16 + Send,
+{
+    #[inline]
+    fn keys(&mut self, display: &interfaces::DisplayBuffer, state: &Backend) -> u16 {
+        self(display, state)
+    }
+}
+
+/// Holds the keys set in `keys` and releases the others
+pub fn apply(keyboard_state: &mut interfaces::KeyboardState, keys: u16) {
+    for key in 0..KEY_COUNT {
+        keyboard_state.set(key, keys & 1 << key != 0);
+    }
+}
+
+/// Runs the program loaded in `core` for `ticks` ticks of `n` instructions with `controller`
+/// pressing the keys, as fast as it can and without a frontend, and returns the display at the end
+pub fn play(
+    core: &mut dyn Core,
+    controller: &mut dyn Controller,
+    n: num::NonZeroU16,
+    ticks: u64,
+) -> Result<interfaces::DisplayBuffer, BackendError> {
+    let mut display_buffer = interfaces::DisplayBuffer::new(false);
+    let mut keyboard_state = interfaces::KeyboardState::default();
+
+    for _ in 0..ticks {
+        apply(
+            &mut keyboard_state,
+            controller.keys(&display_buffer, core.state()),
+        );
+        core.tick(n, (&mut display_buffer, &keyboard_state))?;
+    }
+
+    Ok(display_buffer)
+}
@@ -0,0 +1,113 @@
+use std::num;
+
+use serde::{Deserialize, Serialize};
+
+use super::{instruction, interfaces, Backend, BackendError, FONT_SIZE};
+
+/// An implementation of the machine, so that experimental ones can be swapped in for the interpreter
+///
+/// The state of every core is kept in a `Backend`, cores which derive anything from it have to
+/// account for it being changed through `state_mut`.
+pub trait Core: Send {
+    fn load(&mut self, font: Option<&[u8; FONT_SIZE]>, program: &[u8]) -> Result<(), BackendError>;
+
+    fn reset(&mut self);
+
+    /// Replaces the state of the machine with `state`
+    fn restore(&mut self, state: Backend);
+
+    fn state(&self) -> &Backend;
+
+    fn state_mut(&mut self) -> &mut Backend;
+
+    /// Executes the next instruction, see `Backend::step`
+    fn step(
+        &mut self,
+        io: (&mut interfaces::DisplayBuffer, &interfaces::KeyboardState),
+    ) -> Result<(usize, instruction::Instruction, bool), BackendError>;
+
+    /// Executes a tick worth of instructions, see `Backend::tick`
+    fn tick(
+        &mut self,
+        n: num::NonZeroU16,
+        io: (&mut interfaces::DisplayBuffer, &interfaces::KeyboardState),
+    ) -> Result<(usize, instruction::Instruction), BackendError>;
+
+    fn tick_timers(&mut self);
+}
+
+/// The cores that can be selected to run programs with
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoreKind {
+    #[default]
+    Interpreter,
+    /// Compiles blocks of instructions to native code, see `jit::Jit`
+    #[cfg(feature = "jit")]
+    Jit,
+}
+
+impl CoreKind {
+    #[cfg(not(feature = "jit"))]
+    pub const ALL: [Self; 1] = [Self::Interpreter];
+    #[cfg(feature = "jit")]
+    pub const ALL: [Self; 2] = [Self::Interpreter, Self::Jit];
+
+    /// Returns a new core of this kind starting from `state`
+    pub fn create(&self, state: Backend) -> Box<dyn Core> {
+        match self {
+            Self::Interpreter => Box::new(state),
+            #[cfg(feature = "jit")]
+            Self::Jit => Box::new(super::jit::Jit::new(state)),
+        }
+    }
+}
+
+impl Core for Backend {
+    #[inline]
+    fn load(&mut self, font: Option<&[u8; FONT_SIZE]>, program: &[u8]) -> Result<(), BackendError> {
+        Backend::load(self, font, program)
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        Backend::reset(self)
+    }
+
+    #[inline]
+    fn restore(&mut self, state: Backend) {
+        *self = state;
+    }
+
+    #[inline]
+    fn state(&self) -> &Backend {
+        self
+    }
+
+    #[inline]
+    fn state_mut(&mut self) -> &mut Backend {
+        self
+    }
+
+    #[inline]
+    fn step(
+        &mut self,
+        io: (&mut interfaces::DisplayBuffer, &interfaces::KeyboardState),
+    ) -> Result<(usize, instruction::Instruction, bool), BackendError> {
+        Backend::step(self, io)
+    }
+
+    #[inline]
+    fn tick(
+        &mut self,
+        n: num::NonZeroU16,
+        io: (&mut interfaces::DisplayBuffer, &interfaces::KeyboardState),
+    ) -> Result<(usize, instruction::Instruction), BackendError> {
+        Backend::tick(self, n, io)
+    }
+
+    #[inline]
+    fn tick_timers(&mut self) {
+        Backend::tick_timers(self)
+    }
+}
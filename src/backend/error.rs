@@ -5,13 +5,18 @@ use super::instruction;
 
 #[derive(Debug)]
 pub struct BackendError {
+    /// The registers when the error happened, if a program was running
+    pub context: Option<ErrorContext>,
     pub instruction: Option<(usize, Option<instruction::Instruction>)>,
     pub kind: BackendErrorKind,
 }
 
 #[derive(Debug)]
 pub enum BackendErrorKind {
+    DebugBreak,
     MemoryOverflow,
+    /// The program ended itself with `00FD`, which isn't a failure
+    ProgramExited,
     ProgramInvalid,
     ProgramNotLoaded,
     StackOverflow,
@@ -20,6 +25,38 @@ pub enum BackendErrorKind {
     UnrecognizedSprite,
 }
 
+/// The registers and the top of the stack when an error happened
+#[derive(Clone, Debug)]
+pub struct ErrorContext {
+    pub registers: super::Registers,
+    pub stack_top: Option<u16>,
+}
+
+impl BackendError {
+    #[inline]
+    pub fn code(&self) -> u8 {
+        self.kind.code()
+    }
+}
+
+impl BackendErrorKind {
+    /// Returns the code of the kind of error, which stays the same across versions for scripts to
+    /// tell errors apart by
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::DebugBreak => 10,
+            Self::MemoryOverflow => 11,
+            Self::ProgramExited => 18,
+            Self::ProgramInvalid => 12,
+            Self::ProgramNotLoaded => 13,
+            Self::StackOverflow => 14,
+            Self::StackUnderflow => 15,
+            Self::UnrecognizedInstruction => 16,
+            Self::UnrecognizedSprite => 17,
+        }
+    }
+}
+
 impl fmt::Display for BackendError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.instruction {
@@ -30,6 +67,28 @@ impl fmt::Display for BackendError {
             ),
             Some((index, None)) => write!(f, "at 0x{:x}, {}", index, self.kind),
             None => write!(f, "{}", self.kind),
+        }?;
+
+        write!(f, " (error {})", self.code())?;
+
+        match self.context.as_ref() {
+            Some(context) => write!(f, " [{}]", context),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (register, value) in self.registers.general.iter().enumerate() {
+            write!(f, "V{:X}={:02X} ", register, value)?;
+        }
+
+        write!(f, "I={:03X} ", self.registers.address)?;
+
+        match self.stack_top {
+            Some(address) => write!(f, "stack top={:03X}", address),
+            None => write!(f, "stack empty"),
         }
     }
 }
@@ -40,7 +99,9 @@ impl fmt::Display for BackendErrorKind {
             f,
             "{}",
             match self {
+                Self::DebugBreak => "debug break",
                 Self::MemoryOverflow => "attempt to access invalid memory address",
+                Self::ProgramExited => "the program exited",
                 Self::ProgramInvalid => "attempt to load invalid program",
                 Self::ProgramNotLoaded => "attempt to run without loading any program",
                 Self::StackOverflow => "attempt to call a coroutine when the stack is full",
@@ -13,7 +13,7 @@ impl Instruction {
 
     #[inline]
     pub fn operator_code(&self) -> u8 {
-        (self.0 >> u16::BITS - u8::BITS / 2) as u8
+        (self.0 >> (u16::BITS - u8::BITS / 2)) as u8
     }
 
     #[inline]
@@ -38,7 +38,12 @@ impl Instruction {
 
     #[inline]
     pub fn operand_y(&self) -> usize {
-        ((self.0 & 0x00F0) >> u8::BITS / 2) as usize
+        ((self.0 & 0x00F0) >> (u8::BITS / 2)) as usize
+    }
+
+    #[inline]
+    pub fn word(&self) -> u16 {
+        self.0
     }
 }
 
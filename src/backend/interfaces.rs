@@ -1,39 +1,119 @@
+use std::cell;
+use std::cmp;
 use std::collections;
+use std::ffi;
+use std::fs;
+use std::io;
+use std::path;
 use std::time;
 
 use bitvec::view::BitViewSized;
+use serde::{Deserialize, Serialize};
 
+const FNV_OFFSET_BASIS: u64 = 0xCBF29CE484222325;
+const FNV_PRIME: u64 = 0x100000001B3;
+/// Number of the last draws over a pixel kept for it
+const PIXEL_DRAWS_KEPT: usize = 8;
+
+/// A row of the display, the leftmost pixel in the most significant bit
+pub type Row = bitvec::BitArr!(for super::DISPLAY_BUFFER_WIDTH, in u64, bitvec::order::Msb0);
+
+/// Only the pixels and the settings of a serialized display are kept, a display read back is dirty
+/// and knows nothing of the changes and the draws before
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct DisplayBuffer {
-    pub buffer: [bitvec::BitArr!(for super::DISPLAY_BUFFER_WIDTH, in u64, bitvec::order::Msb0);
-        super::DISPLAY_BUFFER_HEIGHT],
+    #[cfg_attr(feature = "serde", serde(with = "rows"))]
+    pub buffer: [Row; super::DISPLAY_BUFFER_HEIGHT],
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub changed: collections::HashMap<(usize, usize), time::Instant>,
+    /// Collisions of the sprites drawn while tracking the draws, to be taken by the frontend
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub collisions: Vec<Collision>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "dirty"))]
     pub dirty: bool,
-    pub options: Options,
+    /// The last instructions that drew over each pixel since the display was cleared, oldest first
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub draws: collections::HashMap<(usize, usize), collections::VecDeque<Draw>>,
+    pub track_changes: bool,
+    pub track_draws: bool,
+}
+
+/// A sprite drawn over pixels that were on, turning them off
+#[derive(Clone, Debug)]
+pub struct Collision {
+    /// Where the sprite was drawn
+    pub coordinates: (usize, usize),
+    /// Address of the instruction drawing the sprite
+    pub index: usize,
+    /// The pixels turned off
+    pub pixels: Vec<(usize, usize)>,
 }
 
-pub struct KeyboardState([bool; super::KEY_COUNT]);
+/// A DXYN instruction that drew over a pixel
+#[derive(Clone, Copy, Debug)]
+pub struct Draw {
+    /// Address of the sprite, where I pointed to
+    pub address: usize,
+    /// Address of the instruction
+    pub index: usize,
+}
 
-pub struct Options {
-    pub track_changes: bool,
-    pub wrap_sprites: bool,
+/// The pixels of a display as they are, without scaling, colors or effects, a byte per pixel row
+/// by row, 0 when off and 255 when on
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisplayImage {
+    pub height: usize,
+    pub pixels: Vec<u8>,
+    pub width: usize,
+}
+
+/// Which of the held keys `FX0A` takes
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyPriority {
+    #[default]
+    LowestIndex,
+    /// The key pressed last, for keyboards reporting keys next to the pressed ones as held too
+    MostRecent,
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct KeyboardState {
+    keys: [bool; super::KEY_COUNT],
+    /// Bit mask of the keys a program found held since it was last taken
+    observed: cell::Cell<u16>,
+    /// Number of the press of each key, counting from when the state was made
+    pressed_at: [u64; super::KEY_COUNT],
+    presses: u64,
+    pub priority: KeyPriority,
 }
 
 impl DisplayBuffer {
     pub fn clear(&mut self) {
+        self.draws.clear();
+
         for row in self.buffer.iter_mut() {
-            row.fill(false);
+            if row.any() {
+                row.fill(false);
+                self.dirty = true;
+            }
         }
-
-        self.dirty = true;
     }
 
-    pub fn draw(&mut self, coordinates: (usize, usize), sprite: &[u8]) -> bool {
+    pub fn draw(
+        &mut self,
+        coordinates: (usize, usize),
+        sprite: &[u8],
+        wrap: bool,
+        source: Draw,
+    ) -> bool {
         let coordinates = (
             coordinates.0 % super::DISPLAY_BUFFER_WIDTH,
             coordinates.1 % super::DISPLAY_BUFFER_HEIGHT,
         );
 
-        let mut collided = false;
+        let mut collided = Vec::new();
 
         for (y, byte) in sprite.iter().enumerate() {
             let cy = (coordinates.1 + y) % super::DISPLAY_BUFFER_HEIGHT;
@@ -46,74 +126,415 @@ impl DisplayBuffer {
                 let cx = (coordinates.0 + x) % super::DISPLAY_BUFFER_WIDTH;
 
                 if *bit {
-                    let mut pixel = self.buffer[cy]
-                        .get_mut(cx)
-                        .unwrap();
+                    if self.track_draws {
+                        let draws = self.draws.entry((cx, cy)).or_default();
+
+                        if draws.len() == PIXEL_DRAWS_KEPT {
+                            draws.pop_front();
+                        }
+
+                        draws.push_back(source);
+                    }
+
+                    let mut pixel = self.buffer[cy].get_mut(cx).unwrap();
 
                     if *pixel {
-                        collided = true;
+                        collided.push((cx, cy));
 
-                        if self.options.track_changes {
-                            self.changed
-                                .insert((cx, cy), time::Instant::now());
+                        if self.track_changes {
+                            self.changed.insert((cx, cy), time::Instant::now());
                         }
                     }
 
                     pixel.set(!*pixel);
                 };
 
-                if !self.options.wrap_sprites && cx == super::DISPLAY_BUFFER_WIDTH - 1 {
+                if !wrap && cx == super::DISPLAY_BUFFER_WIDTH - 1 {
                     break;
                 }
             }
 
-            if !self.options.wrap_sprites && cy == super::DISPLAY_BUFFER_HEIGHT - 1 {
+            if !wrap && cy == super::DISPLAY_BUFFER_HEIGHT - 1 {
                 break;
             }
         }
 
-        self.dirty = true;
+        // a blank sprite leaves the display as it was
+        self.dirty |= sprite.iter().any(|byte| *byte != 0);
+
+        if collided.is_empty() {
+            return false;
+        }
+
+        if self.track_draws {
+            self.collisions.push(Collision {
+                coordinates,
+                index: source.index,
+                pixels: collided,
+            });
+        }
 
-        collided
+        true
+    }
+
+    /// Returns the FNV-1a hash of the pixels, the same for the same display whatever the machine
+    /// or the version of the emulator
+    pub fn hash(&self) -> u64 {
+        self.buffer
+            .iter()
+            .flat_map(|row| row.as_raw_slice())
+            .flat_map(|word| word.to_be_bytes())
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
     }
 
     #[inline]
-    pub fn new(options: Options) -> Self {
+    pub fn new(track_changes: bool) -> Self {
         Self {
             buffer: [bitvec::array::BitArray::ZERO; super::DISPLAY_BUFFER_HEIGHT],
-            changed: collections::HashMap::with_capacity(match options.track_changes {
+            changed: collections::HashMap::with_capacity(match track_changes {
                 true => super::DISPLAY_BUFFER_WIDTH * super::DISPLAY_BUFFER_HEIGHT,
                 false => 0,
             }),
+            collisions: Vec::new(),
             dirty: false,
-            options,
+            draws: collections::HashMap::new(),
+            track_changes,
+            track_draws: false,
+        }
+    }
+
+    /// Returns the pixels as they are, one image pixel per display pixel
+    pub fn to_image(&self) -> DisplayImage {
+        DisplayImage {
+            height: super::DISPLAY_BUFFER_HEIGHT,
+            pixels: self
+                .buffer
+                .iter()
+                .flat_map(|row| row[..super::DISPLAY_BUFFER_WIDTH].iter().by_vals())
+                .map(|pixel| match pixel {
+                    true => u8::MAX,
+                    false => 0,
+                })
+                .collect(),
+            width: super::DISPLAY_BUFFER_WIDTH,
+        }
+    }
+}
+
+impl DisplayImage {
+    /// Saves the image at `path`, as a PPM file when its extension is ppm and as a PNG file
+    /// otherwise
+    pub fn save(&self, path: &path::Path) -> io::Result<()> {
+        let file = io::BufWriter::new(fs::File::create(path)?);
+
+        match path.extension() == Some(ffi::OsStr::new("ppm")) {
+            true => self.write_ppm(file),
+            false => self.write_png(file),
         }
     }
+
+    /// Encodes the image as a grayscale PNG into `writer`
+    pub fn write_png(&self, writer: impl io::Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        Ok(encoder.write_header()?.write_image_data(&self.pixels)?)
+    }
+
+    /// Encodes the image as a binary PPM into `writer`
+    pub fn write_ppm(&self, mut writer: impl io::Write) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+
+        for pixel in self.pixels.iter() {
+            writer.write_all(&[*pixel; 3])?;
+        }
+
+        writer.flush()
+    }
+}
+
+impl KeyPriority {
+    pub const ALL: [Self; 2] = [Self::LowestIndex, Self::MostRecent];
 }
 
 impl KeyboardState {
+    /// Returns whether `key` is held, without a program seeing it
+    #[inline]
+    pub fn held(&self, key: usize) -> bool {
+        self.keys.get(key).copied().unwrap_or(false)
+    }
+
     #[inline]
     pub fn hold(&mut self, key: usize) {
-        self.0[key] = true
+        if !self.keys[key] {
+            self.presses += 1;
+            self.pressed_at[key] = self.presses;
+        }
+
+        self.keys[key] = true
     }
 
     #[inline]
-    pub fn new() -> Self {
-        Self([false; super::KEY_COUNT])
+    pub fn new(priority: KeyPriority) -> Self {
+        Self {
+            keys: [false; super::KEY_COUNT],
+            observed: cell::Cell::new(0),
+            pressed_at: [0; super::KEY_COUNT],
+            presses: 0,
+            priority,
+        }
     }
 
+    /// Returns whether `key` is held, as seen by a program
     #[inline]
     pub fn pressed(&self, key: usize) -> bool {
-        self.0.get(key).copied().unwrap_or(false)
+        let pressed = self.held(key);
+
+        if pressed {
+            self.observed.set(self.observed.get() | 1 << key);
+        }
+
+        pressed
     }
 
+    /// Returns the held key that comes first by the priority, as seen by a program
     #[inline]
     pub fn pressed_key(&self) -> Option<usize> {
-        self.0.iter().position(|pressed| *pressed)
+        let key = self.pressed_keys().first().copied();
+
+        if let Some(key) = key {
+            self.observed.set(self.observed.get() | 1 << key);
+        }
+
+        key
+    }
+
+    /// Returns every held key in the order of the priority, without a program seeing them
+    pub fn pressed_keys(&self) -> Vec<usize> {
+        let mut keys: Vec<usize> = (0..super::KEY_COUNT)
+            .filter(|key| self.keys[*key])
+            .collect();
+
+        if self.priority == KeyPriority::MostRecent {
+            keys.sort_by_key(|key| cmp::Reverse(self.pressed_at[*key]));
+        }
+
+        keys
     }
 
     #[inline]
     pub fn release(&mut self, key: usize) {
-        self.0[key] = false
+        self.keys[key] = false
+    }
+
+    /// Holds or releases `key`, ignoring the keys past the last one, for the ones coming from
+    /// outside of the emulator, e.g. a controller
+    #[inline]
+    pub fn set(&mut self, key: usize, pressed: bool) {
+        match (key < super::KEY_COUNT, pressed) {
+            (true, true) => self.hold(key),
+            (true, false) => self.release(key),
+            (false, _) => {}
+        }
+    }
+
+    /// Returns the bit mask of the keys a program found held since the last call, and clears it
+    #[inline]
+    pub fn take_observed(&self) -> u16 {
+        self.observed.take()
+    }
+}
+
+impl Default for KeyboardState {
+    fn default() -> Self {
+        Self::new(KeyPriority::default())
+    }
+}
+
+#[cfg(feature = "serde")]
+fn dirty() -> bool {
+    true
+}
+
+/// Serializes the rows of the display as numbers, the leftmost pixel in the most significant bit
+#[cfg(feature = "serde")]
+mod rows {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::super::DISPLAY_BUFFER_HEIGHT;
+    use super::Row;
+
+    pub fn serialize<S: Serializer>(
+        rows: &[Row; DISPLAY_BUFFER_HEIGHT],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let rows: Vec<u64> = rows.iter().map(|row| row.data[0]).collect();
+
+        rows.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[Row; DISPLAY_BUFFER_HEIGHT], D::Error> {
+        let rows = Vec::<u64>::deserialize(deserializer)?;
+
+        if rows.len() != DISPLAY_BUFFER_HEIGHT {
+            return Err(de::Error::invalid_length(
+                rows.len(),
+                &"as many rows as the display has",
+            ));
+        }
+
+        let mut buffer = [Row::ZERO; DISPLAY_BUFFER_HEIGHT];
+
+        for (row, data) in buffer.iter_mut().zip(rows) {
+            *row = Row::new([data]);
+        }
+
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{DISPLAY_BUFFER_HEIGHT, DISPLAY_BUFFER_WIDTH};
+    use super::*;
+
+    /// Four pixels wide and high, the outline of a square
+    const SQUARE: [u8; 4] = [0xF0, 0x90, 0x90, 0xF0];
+    const SOURCE: Draw = Draw {
+        address: 0x300,
+        index: 0x200,
+    };
+
+    /// Returns the rows of the display, `#` for the pixels on and `.` for the ones off
+    fn bitmap(display: &DisplayBuffer) -> Vec<String> {
+        display
+            .buffer
+            .iter()
+            .map(|row| {
+                row[..DISPLAY_BUFFER_WIDTH]
+                    .iter()
+                    .map(|pixel| match *pixel {
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the bitmap of a display with only `rows` on, by their index
+    fn expected(rows: &[(usize, &str)]) -> Vec<String> {
+        let mut bitmap = vec![".".repeat(DISPLAY_BUFFER_WIDTH); DISPLAY_BUFFER_HEIGHT];
+
+        for (y, row) in rows {
+            bitmap[*y] = row.to_string();
+        }
+
+        bitmap
+    }
+
+    #[test]
+    fn blank_sprite_leaves_the_display_clean() {
+        let mut display = DisplayBuffer::new(false);
+
+        assert!(!display.draw((10, 10), &[0x00; 5], false, SOURCE));
+        assert!(!display.dirty);
+        assert_eq!(bitmap(&display), expected(&[]));
+    }
+
+    #[test]
+    fn coordinates_past_the_edges_start_over() {
+        let mut display = DisplayBuffer::new(false);
+
+        display.draw((64 + 2, 32 + 3), &SQUARE, false, SOURCE);
+
+        assert_eq!(
+            bitmap(&display),
+            expected(&[
+                (
+                    3,
+                    "..####.........................................................."
+                ),
+                (
+                    4,
+                    "..#..#.........................................................."
+                ),
+                (
+                    5,
+                    "..#..#.........................................................."
+                ),
+                (
+                    6,
+                    "..####.........................................................."
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn sprite_drawn_twice_collides_and_clears() {
+        let mut display = DisplayBuffer::new(false);
+        display.track_draws = true;
+
+        assert!(!display.draw((8, 4), &SQUARE, false, SOURCE));
+        assert!(display.draw((8, 4), &SQUARE, false, SOURCE));
+        assert_eq!(bitmap(&display), expected(&[]));
+        assert_eq!(display.collisions.len(), 1);
+        assert_eq!(display.collisions[0].pixels.len(), 12);
+    }
+
+    #[test]
+    fn sprite_clips_at_the_right_and_bottom_edges() {
+        let mut display = DisplayBuffer::new(false);
+
+        display.draw((62, 30), &SQUARE, false, SOURCE);
+
+        assert_eq!(
+            bitmap(&display),
+            expected(&[
+                (
+                    30,
+                    "..............................................................##"
+                ),
+                (
+                    31,
+                    "..............................................................#."
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn sprite_wraps_around_the_corners() {
+        let mut display = DisplayBuffer::new(false);
+
+        display.draw((62, 30), &SQUARE, true, SOURCE);
+
+        assert_eq!(
+            bitmap(&display),
+            expected(&[
+                (
+                    0,
+                    ".#............................................................#."
+                ),
+                (
+                    1,
+                    "##............................................................##"
+                ),
+                (
+                    30,
+                    "##............................................................##"
+                ),
+                (
+                    31,
+                    ".#............................................................#."
+                ),
+            ])
+        );
     }
 }
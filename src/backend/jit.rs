@@ -0,0 +1,393 @@
+use std::collections;
+use std::mem;
+use std::num;
+
+use cranelift_codegen::ir::{self, condcodes::IntCC, types, InstBuilder, MemFlags};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::Module;
+
+use super::{
+    instruction::Instruction, interfaces, Backend, BackendError, BackendErrorKind, Core, Coverage,
+    Quirks, FONT_SIZE, REGISTER_COUNT,
+};
+
+const MAX_BLOCK_LENGTH: usize = 64;
+
+/// Compiled code of a block, called with pointers to the general registers and the address register
+type BlockFunction = extern "C" fn(*mut u8, *mut usize);
+
+/// A core compiling runs of arithmetic instructions to native code, every other instruction is
+/// left to the interpreter
+pub struct Jit {
+    /// Blocks keyed by their start address, `None` for addresses the block would be empty at
+    blocks: collections::HashMap<usize, Option<Block>>,
+    /// The quirks and the debug break instruction the blocks were compiled with
+    compiled_with: (Quirks, Option<u16>),
+    context: FunctionBuilderContext,
+    module: JITModule,
+    state: Backend,
+}
+
+#[derive(Clone, Copy)]
+struct Block {
+    /// Instructions of the block by their operator code, counted into the statistics when it runs
+    families: [u64; 16],
+    function: BlockFunction,
+    last: Instruction,
+    length: usize,
+}
+
+impl Jit {
+    pub fn new(state: Backend) -> Self {
+        let mut flags = settings::builder();
+        flags.set("use_colocated_libcalls", "false").unwrap();
+        flags.set("is_pic", "false").unwrap();
+
+        let isa = cranelift_native::builder()
+            .expect("the host machine is not supported by the JIT core")
+            .finish(settings::Flags::new(flags))
+            .unwrap();
+
+        Self {
+            blocks: collections::HashMap::new(),
+            compiled_with: (state.quirks, state.debug_break),
+            context: FunctionBuilderContext::new(),
+            module: JITModule::new(JITBuilder::with_isa(
+                isa,
+                cranelift_module::default_libcall_names(),
+            )),
+            state,
+        }
+    }
+
+    /// Returns the block starting at `start`, compiling it if it hasn't been yet
+    fn block(&mut self, start: usize) -> Option<Block> {
+        if let Some(block) = self.blocks.get(&start) {
+            return *block;
+        }
+
+        let instructions: Vec<Instruction> = (start..self.state.memory.len() - 1)
+            .step_by(mem::size_of::<Instruction>())
+            .map(|index| Instruction::new([self.state.memory[index], self.state.memory[index + 1]]))
+            .take_while(|instruction| {
+                self.state.debug_break != Some(instruction.word()) && compilable(instruction)
+            })
+            .take(MAX_BLOCK_LENGTH)
+            .collect();
+
+        let mut families = [0; 16];
+
+        for instruction in instructions.iter() {
+            families[instruction.operator_code() as usize] += 1;
+        }
+
+        let block = instructions.last().copied().map(|last| Block {
+            families,
+            function: self.compile(&instructions),
+            last,
+            length: instructions.len(),
+        });
+
+        self.blocks.insert(start, block);
+
+        block
+    }
+
+    fn compile(&mut self, instructions: &[Instruction]) -> BlockFunction {
+        let pointer = self.module.target_config().pointer_type();
+
+        let mut signature = self.module.make_signature();
+        signature.params.push(ir::AbiParam::new(pointer));
+        signature.params.push(ir::AbiParam::new(pointer));
+
+        let id = self.module.declare_anonymous_function(&signature).unwrap();
+
+        let mut context = self.module.make_context();
+        context.func.signature = signature;
+
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut self.context);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+
+        let (general, address) = (
+            builder.block_params(entry)[0],
+            builder.block_params(entry)[1],
+        );
+        let flags = MemFlags::trusted();
+        let quirks = self.state.quirks;
+        let address_mask = self.state.memory.len() as i64 - 1;
+
+        for instruction in instructions {
+            let (x, y) = (
+                instruction.operand_x() as i32,
+                instruction.operand_y() as i32,
+            );
+
+            let load = |builder: &mut FunctionBuilder, register: i32| {
+                builder.ins().load(types::I8, flags, general, register)
+            };
+
+            match instruction.operator_code() {
+                0x6 => {
+                    let value = builder
+                        .ins()
+                        .iconst(types::I8, instruction.operand_nn() as i64);
+                    builder.ins().store(flags, value, general, x);
+                }
+
+                0x7 => {
+                    let vx = load(&mut builder, x);
+                    let value = builder.ins().iadd_imm(vx, instruction.operand_nn() as i64);
+                    builder.ins().store(flags, value, general, x);
+                }
+
+                0x8 => {
+                    let (vx, vy) = (load(&mut builder, x), load(&mut builder, y));
+
+                    // the flag is stored before the result, like the interpreter does
+                    let (value, flag) = match instruction.operand_n() {
+                        0x0 => (vy, None),
+                        0x1 => (builder.ins().bor(vx, vy), None),
+                        0x2 => (builder.ins().band(vx, vy), None),
+                        0x3 => (builder.ins().bxor(vx, vy), None),
+                        0x4 => {
+                            let value = builder.ins().iadd(vx, vy);
+                            (
+                                value,
+                                Some(builder.ins().icmp(IntCC::UnsignedLessThan, value, vx)),
+                            )
+                        }
+                        0x5 => (
+                            builder.ins().isub(vx, vy),
+                            Some(builder.ins().icmp(IntCC::UnsignedGreaterThan, vx, vy)),
+                        ),
+                        0x7 => (
+                            builder.ins().isub(vy, vx),
+                            Some(builder.ins().icmp(IntCC::UnsignedGreaterThan, vy, vx)),
+                        ),
+                        code @ (0x6 | 0xE) => {
+                            let source = match quirks.shift_vy {
+                                true => vy,
+                                false => vx,
+                            };
+
+                            match code {
+                                0x6 => (
+                                    builder.ins().ushr_imm(source, 1),
+                                    Some(builder.ins().band_imm(source, 1)),
+                                ),
+                                _ => (
+                                    builder.ins().ishl_imm(source, 1),
+                                    Some(builder.ins().ushr_imm(source, (u8::BITS - 1) as i64)),
+                                ),
+                            }
+                        }
+                        _ => unreachable!(),
+                    };
+
+                    if let Some(flag) = flag {
+                        builder
+                            .ins()
+                            .store(flags, flag, general, (REGISTER_COUNT - 1) as i32);
+                    }
+
+                    builder.ins().store(flags, value, general, x);
+
+                    if quirks.vf_reset && matches!(instruction.operand_n(), 0x1..=0x3) {
+                        let zero = builder.ins().iconst(types::I8, 0);
+                        builder
+                            .ins()
+                            .store(flags, zero, general, (REGISTER_COUNT - 1) as i32);
+                    }
+                }
+
+                0xA => {
+                    let value = builder
+                        .ins()
+                        .iconst(pointer, instruction.operand_nnn() as i64);
+                    builder.ins().store(flags, value, address, 0);
+                }
+
+                0xF => {
+                    let vx = load(&mut builder, x);
+                    let vx = builder.ins().uextend(pointer, vx);
+                    let value = builder.ins().load(pointer, flags, address, 0);
+                    let value = builder.ins().iadd(value, vx);
+                    let value = builder.ins().band_imm(value, address_mask);
+                    builder.ins().store(flags, value, address, 0);
+                }
+
+                _ => unreachable!(),
+            }
+        }
+
+        builder.ins().return_(&[]);
+        builder.seal_all_blocks();
+        builder.finalize();
+
+        self.module.define_function(id, &mut context).unwrap();
+        self.module.clear_context(&mut context);
+        self.module.finalize_definitions().unwrap();
+
+        // SAFETY: the function was declared with the signature of `BlockFunction`
+        unsafe {
+            mem::transmute::<*const u8, BlockFunction>(self.module.get_finalized_function(id))
+        }
+    }
+
+    /// Executes a compiled block if there's one that fits in `budget`, or a single instruction
+    /// otherwise, and returns the result like `Backend::step` along with the number of instructions
+    /// executed
+    fn execute(
+        &mut self,
+        budget: usize,
+        io: (&mut interfaces::DisplayBuffer, &interfaces::KeyboardState),
+    ) -> Result<((usize, Instruction, bool), usize), BackendError> {
+        let start = self.state.index;
+
+        if let Some(block) = self.block(start) {
+            let end = start + block.length * mem::size_of::<Instruction>();
+
+            // the frozen registers are written back after every instruction, not after every block
+            if block.length <= budget
+                && self.state.frozen.is_empty()
+                && self
+                    .state
+                    .breakpoints
+                    .range(start + 1..end)
+                    .next()
+                    .is_none()
+            {
+                (block.function)(
+                    self.state.registers.general.as_mut_ptr(),
+                    &mut self.state.registers.address,
+                );
+                self.state.index = end;
+                self.state.cover(start..end, Coverage::EXECUTED);
+                self.state.written = None;
+
+                for (count, block_count) in self
+                    .state
+                    .statistics
+                    .families
+                    .iter_mut()
+                    .zip(block.families)
+                {
+                    *count += block_count;
+                }
+
+                return Ok((
+                    (end - mem::size_of::<Instruction>(), block.last, false),
+                    block.length,
+                ));
+            }
+        }
+
+        let step = self.state.step(io)?;
+
+        // drop the blocks the instruction overwrote
+        if let Some(written) = self.state.written.clone() {
+            self.blocks.retain(|start, block| {
+                let length = block.map_or(1, |block| block.length);
+                let end = start + length * mem::size_of::<Instruction>();
+
+                end <= written.start || *start >= written.end
+            });
+        }
+
+        Ok((step, 1))
+    }
+}
+
+impl Core for Jit {
+    fn load(&mut self, font: Option<&[u8; FONT_SIZE]>, program: &[u8]) -> Result<(), BackendError> {
+        self.blocks.clear();
+        self.state.load(font, program)
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.state.reset()
+    }
+
+    fn restore(&mut self, state: Backend) {
+        self.blocks.clear();
+        self.state = state;
+    }
+
+    #[inline]
+    fn state(&self) -> &Backend {
+        &self.state
+    }
+
+    #[inline]
+    fn state_mut(&mut self) -> &mut Backend {
+        &mut self.state
+    }
+
+    fn step(
+        &mut self,
+        io: (&mut interfaces::DisplayBuffer, &interfaces::KeyboardState),
+    ) -> Result<(usize, Instruction, bool), BackendError> {
+        self.execute(1, io).map(|(step, _)| step)
+    }
+
+    fn tick(
+        &mut self,
+        n: num::NonZeroU16,
+        (display_buffer, keyboard_state): (
+            &mut interfaces::DisplayBuffer,
+            &interfaces::KeyboardState,
+        ),
+    ) -> Result<(usize, Instruction), BackendError> {
+        if !self.state.loaded {
+            return Err(BackendError {
+                context: None,
+                instruction: None,
+                kind: BackendErrorKind::ProgramNotLoaded,
+            });
+        }
+
+        // blocks depend on the quirks and the debug break instruction, which may have changed
+        if self.compiled_with != (self.state.quirks, self.state.debug_break) {
+            self.blocks.clear();
+            self.compiled_with = (self.state.quirks, self.state.debug_break);
+        }
+
+        self.state.tick_timers();
+
+        let mut remaining = n.get() as usize;
+        let (mut last, executed) = self.execute(remaining, (display_buffer, keyboard_state))?;
+        remaining -= executed;
+
+        while remaining > 0 {
+            // the first instruction always runs so that execution can go on from a breakpoint
+            if last.2 || self.state.breakpoints.contains(&self.state.index) {
+                break;
+            }
+
+            let executed;
+            (last, executed) = self.execute(remaining, (display_buffer, keyboard_state))?;
+            remaining -= executed;
+        }
+
+        Ok((last.0, last.1))
+    }
+
+    #[inline]
+    fn tick_timers(&mut self) {
+        self.state.tick_timers()
+    }
+}
+
+fn compilable(instruction: &Instruction) -> bool {
+    match instruction.operator_code() {
+        0x6 | 0x7 | 0xA => true,
+        0x8 => matches!(instruction.operand_n(), 0x0..=0x7 | 0xE),
+        0xF => instruction.operand_nn() == 0x1E,
+        _ => false,
+    }
+}
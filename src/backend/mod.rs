@@ -1,65 +1,225 @@
+use std::collections;
 use std::mem;
 use std::num;
+use std::ops;
+
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
 use crate::defaults;
+use crate::loader;
 
+pub mod conformance;
+pub mod controller;
+mod core;
 mod error;
+mod font;
 mod instruction;
 pub mod interfaces;
-
-pub use error::{BackendError, BackendErrorKind};
+#[cfg(feature = "jit")]
+mod jit;
+mod op;
+mod peripherals;
+pub mod stall;
+
+pub use self::core::{Core, CoreKind};
+pub use error::{BackendError, BackendErrorKind, ErrorContext};
+pub use font::Font;
 pub use instruction::Instruction;
+pub use interfaces::KeyPriority;
+pub use op::Op;
 
 pub const DISPLAY_BUFFER_ASPECT_RATIO: f32 = (DISPLAY_BUFFER_WIDTH / DISPLAY_BUFFER_HEIGHT) as f32;
 pub const DISPLAY_BUFFER_HEIGHT: usize = 32;
 pub const DISPLAY_BUFFER_WIDTH: usize = 64;
+/// Address of the big font `FX30` points to, right after the small one
+pub const BIG_FONT_ADDRESS: usize = FONT_SIZE;
+pub const BIG_CHARACTER_SIZE: usize = 10;
+pub const BIG_FONT_SIZE: usize = BIG_CHARACTER_SIZE * KEY_COUNT;
 pub const CHARACTER_SIZE: usize = 5;
+/// Size of the memory with the `extended_memory` quirk
+pub const EXTENDED_MEMORY_SIZE: usize = 65536;
 pub const FONT_SIZE: usize = CHARACTER_SIZE * KEY_COUNT;
-pub const INSTRUCTIONS_PER_TICK: u16 = 700;
 pub const KEY_COUNT: usize = 16;
+/// Address programs are loaded at and start from, unless the `load_address` quirk says otherwise
 pub const MEMORY_PADDING: usize = 512;
 pub const MEMORY_SIZE: usize = 4096;
 pub const REGISTER_COUNT: usize = 16;
 pub const STACK_SIZE: usize = 12;
+/// Rate the ticks are executed and the timers count down at
+pub const TICKS_PER_SECOND: u64 = 60;
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Backend {
+    /// Addresses at which a batch of instructions stops early
+    pub breakpoints: collections::BTreeSet<usize>,
+    /// Bytes the program printed to the console through the pseudo-peripherals, until they're taken
+    pub console: Vec<u8>,
+    /// How the program used each byte of the memory since it was loaded
+    #[cfg_attr(feature = "serde", serde(with = "coverage"))]
+    coverage: Vec<Coverage>,
+    /// Instruction that stops execution and reports a `DebugBreak` when executed
+    pub debug_break: Option<u16>,
+    /// Ticks executed since the program was loaded or reset
+    frames: u64,
+    pub frozen: FrozenRegisters,
     index: usize,
     loaded: bool,
-    pub memory: [u8; MEMORY_SIZE],
+    /// `MEMORY_SIZE` bytes, or `EXTENDED_MEMORY_SIZE` if the program was loaded with the
+    /// `extended_memory` quirk
+    pub memory: Vec<u8>,
+    /// Map the pseudo-peripherals to the end of the memory, an extension of RC-8
+    pub peripherals: bool,
+    pub quirks: Quirks,
     pub registers: Registers,
+    /// Generator of the numbers of `CXNN`, seeded again when the program is loaded or reset, the
+    /// one behind `StdRng` which unlike it can be serialized
+    rng: rand_chacha::ChaCha12Rng,
+    /// Seed of the numbers of `CXNN` for the runs to be reproducible, a random one is used when
+    /// it's `None`
+    pub seed: Option<u64>,
+    /// Address of the first executed byte a program overwrote since this was last taken
+    pub self_modified: Option<usize>,
     pub stack: Vec<u16>,
+    pub statistics: Statistics,
     pub timers: Timers,
+    /// Addresses written by the last instruction executed
+    written: Option<ops::Range<usize>>,
+}
+
+bitflags::bitflags! {
+    /// How a program used a byte of the memory
+    #[derive(Default)]
+    pub struct Coverage: u8 {
+        /// Fetched as part of an instruction
+        const EXECUTED = 0b01;
+        /// Drawn as a sprite or loaded into the registers
+        const READ = 0b10;
+    }
+}
+
+/// Behaviours that differ between the CHIP-8 interpreters programs were written for
+#[derive(Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Quirks {
+    /// XO-CHIP's 64K of memory, with `F000 NNNN` loading a 16-bit address into `I`
+    pub extended_memory: bool,
+    /// `BNNN` jumps to `NNN + VX` instead of `NNN + V0`
+    pub jump_vx: bool,
+    /// `EX9E` and `EXA1` let the rest of the instructions of the tick run instead of ending it,
+    /// for programs polling the keys not to be slowed down to a check per frame
+    pub key_checks_continue: bool,
+    /// Address the program is loaded at and starts from, e.g. 0x600 for ETI-660 programs
+    pub load_address: usize,
+    /// `FX55` and `FX65` leave `I` pointing past the last register stored or loaded
+    pub load_store_increment: bool,
+    /// `8XY6` and `8XYE` shift `VY` into `VX` instead of shifting `VX` in place
+    pub shift_vy: bool,
+    /// `8XY1`, `8XY2` and `8XY3` reset `VF`
+    pub vf_reset: bool,
+    /// Sprites drawn beyond the edge of the screen wrap around instead of being clipped
+    pub wrap_sprites: bool,
+}
+
+/// Registers pinned to a value, which is written back to them after every instruction
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct FrozenRegisters {
+    pub address: Option<usize>,
+    pub general: [Option<u8>; REGISTER_COUNT],
 }
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Registers {
     pub address: usize,
     pub general: [u8; REGISTER_COUNT],
 }
 
+/// Counts of what the program executed since it was loaded or reset
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Statistics {
+    /// `DXYN` instructions executed
+    pub draws: u64,
+    /// Instructions executed, indexed by their operator code
+    pub families: [u64; 16],
+    /// `EX9E`, `EXA1` and `FX0A` instructions executed
+    pub key_reads: u64,
+    /// `FX18` instructions executed
+    pub sounds: u64,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Timers {
     pub delay: u8,
     pub sound: u8,
 }
 
 impl Backend {
+    /// Returns how the program used each byte of the memory since it was loaded
+    #[inline]
+    pub fn coverage(&self) -> &[Coverage] {
+        &self.coverage
+    }
+
+    /// Marks the bytes at `addresses` as used the way `coverage` tells
+    fn cover(&mut self, addresses: ops::Range<usize>, coverage: Coverage) {
+        for byte in &mut self.coverage[addresses] {
+            byte.insert(coverage);
+        }
+    }
+
+    /// Returns the address of the next instruction to be executed
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number of ticks executed since the program was loaded or reset
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
     pub fn load(
         &mut self,
         font: Option<&[u8; FONT_SIZE]>,
         program: &[u8],
     ) -> Result<(), BackendError> {
-        if program.len() > MEMORY_SIZE - MEMORY_PADDING || program.len() % 2 != 0 {
+        if loader::check_program(program, &self.quirks).is_err() {
             return Err(BackendError {
+                context: None,
                 instruction: None,
                 kind: BackendErrorKind::ProgramInvalid,
             });
         }
 
-        if self.loaded {
-            self.memory.fill(0);
-        }
-
-        self.memory[..FONT_SIZE].copy_from_slice(font.unwrap_or(&defaults::FONT));
-
-        self.memory[MEMORY_PADDING..(MEMORY_PADDING + program.len())].copy_from_slice(program);
+        let size = match self.quirks.extended_memory {
+            true => EXTENDED_MEMORY_SIZE,
+            false => MEMORY_SIZE,
+        };
+
+        self.memory.clear();
+        self.memory.resize(size, 0);
+
+        self.statistics = Statistics::default();
+        self.coverage.clear();
+        self.coverage.resize(size, Coverage::empty());
+        self.self_modified = None;
+        self.console.clear();
+        self.frames = 0;
+
+        self.set_font(font);
+        self.memory[BIG_FONT_ADDRESS..BIG_FONT_ADDRESS + BIG_FONT_SIZE]
+            .copy_from_slice(&defaults::BIG_FONT);
+
+        let start = self.quirks.load_address;
+        self.memory[start..start + program.len()].copy_from_slice(program);
+        self.index = start;
+        self.rng = rng(self.seed);
         self.loaded = true;
 
         Ok(())
@@ -68,30 +228,58 @@ impl Backend {
     #[inline]
     pub fn new() -> Self {
         Self {
+            breakpoints: collections::BTreeSet::new(),
+            console: Vec::new(),
+            coverage: vec![Coverage::empty(); MEMORY_SIZE],
+            debug_break: None,
+            frames: 0,
+            frozen: FrozenRegisters::default(),
             index: MEMORY_PADDING,
             loaded: false,
-            memory: [0; MEMORY_SIZE],
+            memory: vec![0; MEMORY_SIZE],
+            peripherals: false,
+            quirks: Quirks::default(),
             registers: Registers {
                 address: 0,
                 general: [0; REGISTER_COUNT],
             },
+            rng: rng(None),
+            seed: None,
+            self_modified: None,
             stack: Vec::with_capacity(STACK_SIZE),
+            statistics: Statistics::default(),
             timers: Timers { delay: 0, sound: 0 },
+            written: None,
         }
     }
 
     pub fn reset(&mut self) {
-        self.index = MEMORY_PADDING;
+        self.index = self.quirks.load_address;
 
         self.registers.address = 0;
         self.registers.general.fill(0);
+        self.rng = rng(self.seed);
 
+        self.console.clear();
+        self.frames = 0;
         self.stack.clear();
+        self.statistics = Statistics::default();
 
         self.timers.delay = 0;
         self.timers.delay = 0;
     }
 
+    /// Moves the next instruction to be executed to `index`
+    #[inline]
+    pub fn set_index(&mut self, index: usize) {
+        self.index = index;
+    }
+
+    /// Replaces the font at the start of the memory, with the built-in one when it's `None`
+    pub fn set_font(&mut self, font: Option<&[u8; FONT_SIZE]>) {
+        self.memory[..FONT_SIZE].copy_from_slice(font.unwrap_or(&defaults::FONT));
+    }
+
     /// Executes `n` instructions and returns the index of the last instruction executed
     pub fn tick(
         &mut self,
@@ -103,333 +291,670 @@ impl Backend {
     ) -> Result<(usize, instruction::Instruction), BackendError> {
         if !self.loaded {
             return Err(BackendError {
+                context: None,
                 instruction: None,
                 kind: BackendErrorKind::ProgramNotLoaded,
             });
         }
 
+        self.tick_timers();
+
+        let mut last = self.step((display_buffer, keyboard_state))?;
+
+        for _ in 1..n.get() {
+            // the first instruction always runs so that execution can go on from a breakpoint
+            if last.2 || self.breakpoints.contains(&self.index) {
+                break;
+            }
+
+            last = self.step((display_buffer, keyboard_state))?;
+        }
+
+        Ok((last.0, last.1))
+    }
+
+    /// Skips the next instruction, which is twice as long if it's `F000 NNNN`
+    fn skip(&mut self) {
+        let long = self.quirks.extended_memory
+            && self.memory.get(self.index..self.index + 2) == Some(&[0xF0, 0x00]);
+
+        self.index += mem::size_of::<Instruction>() * (1 + long as usize);
+    }
+
+    /// Writes `value` to `address`, keeping track of the executed code it overwrites
+    fn store(&mut self, address: usize, value: u8) {
+        if let Some(offset) = peripherals::offset(self, address) {
+            peripherals::write(self, offset, value);
+            return;
+        }
+
+        self.memory[address] = value;
+
+        if self.coverage[address].contains(Coverage::EXECUTED) && self.self_modified.is_none() {
+            self.self_modified = Some(address);
+        }
+
+        self.written = Some(match self.written.take() {
+            Some(written) => written.start.min(address)..written.end.max(address + 1),
+            None => address..address + 1,
+        });
+    }
+
+    /// Counts the timers down by one tick, and the tick itself
+    #[inline]
+    pub fn tick_timers(&mut self) {
+        self.frames += 1;
         self.timers.delay = self.timers.delay.saturating_sub(1);
         self.timers.sound = self.timers.sound.saturating_sub(1);
+    }
+
+    /// Executes the next instruction and returns its index, the instruction and whether it ends the
+    /// batch of instructions of the tick, without updating the timers
+    pub fn step(
+        &mut self,
+        io: (&mut interfaces::DisplayBuffer, &interfaces::KeyboardState),
+    ) -> Result<(usize, instruction::Instruction, bool), BackendError> {
+        let result = self.cycle(io);
 
-        let mut last_index = self.index;
+        if self.loaded {
+            self.frozen.apply(&mut self.registers);
+        }
 
-        for _ in 0..n.get() {
-            if self.index + 1 >= self.memory.len() {
-                return Err(BackendError {
-                    instruction: Some((self.index, None)),
-                    kind: BackendErrorKind::MemoryOverflow,
+        result.map_err(|mut error| {
+            // there are no registers to speak of before the program is loaded
+            if self.loaded {
+                error.context = Some(ErrorContext {
+                    registers: self.registers.clone(),
+                    stack_top: self.stack.last().copied(),
                 });
             }
 
-            let instruction =
-                Instruction::new([self.memory[self.index], self.memory[self.index + 1]]);
-
-            last_index = self.index;
-            self.index += mem::size_of::<Instruction>();
-
-            match instruction.operator_code() {
-                0x0 => match instruction.operand_nnn() {
-                    0x0E0 => {
-                        display_buffer.clear();
-                    }
-
-                    0x0EE => {
-                        if self.stack.is_empty() {}
-
-                        match self.stack.pop() {
-                            None => {
-                                return Err(BackendError {
-                                    instruction: Some((last_index, Some(instruction))),
-                                    kind: BackendErrorKind::StackUnderflow,
-                                })
-                            }
-                            Some(address) => self.index = address as usize,
-                        };
-                    }
-                    // Not implementing 0NNN, needs a 1802 or M6800 VM.
-                    _ => {}
-                },
-
-                opcode @ (0x1 | 0x2) => {
-                    if opcode == 2 {
-                        if self.stack.len() == STACK_SIZE {
-                            return Err(BackendError {
-                                instruction: Some((last_index, Some(instruction))),
-                                kind: BackendErrorKind::StackOverflow,
-                            });
-                        }
-
-                        self.stack.push(self.index as u16);
-                    }
-
-                    self.index = instruction.operand_nnn() as usize;
+            error
+        })
+    }
+
+    /// Does what `step` does, leaving the context out of the errors
+    fn cycle(
+        &mut self,
+        io: (&mut interfaces::DisplayBuffer, &interfaces::KeyboardState),
+    ) -> Result<(usize, instruction::Instruction, bool), BackendError> {
+        if !self.loaded {
+            return Err(BackendError {
+                context: None,
+                instruction: None,
+                kind: BackendErrorKind::ProgramNotLoaded,
+            });
+        }
+
+        let (index, instruction) = self.fetch()?;
+
+        if self.debug_break == Some(instruction.word()) {
+            return Err(BackendError {
+                context: None,
+                instruction: Some((index, Some(instruction))),
+                kind: BackendErrorKind::DebugBreak,
+            });
+        }
+
+        self.statistics.count(&instruction);
+
+        let op = Op::decode(instruction, &self.quirks).ok_or(BackendError {
+            context: None,
+            instruction: Some((index, Some(instruction))),
+            kind: BackendErrorKind::UnrecognizedInstruction,
+        })?;
+
+        let ends = self.execute(op, (index, instruction), io)?;
+
+        Ok((index, instruction, ends))
+    }
+
+    /// Reads the instruction at the index and moves the index past it, returning the address it was
+    /// read from along with it
+    fn fetch(&mut self) -> Result<(usize, instruction::Instruction), BackendError> {
+        if self.index + 1 >= self.memory.len() {
+            return Err(BackendError {
+                context: None,
+                instruction: Some((self.index, None)),
+                kind: BackendErrorKind::MemoryOverflow,
+            });
+        }
+
+        let index = self.index;
+        let instruction = Instruction::new([self.memory[index], self.memory[index + 1]]);
+
+        self.cover(index..index + 2, Coverage::EXECUTED);
+        self.written = None;
+        self.index += mem::size_of::<Instruction>();
+
+        Ok((index, instruction))
+    }
+
+    /// Does what `op` says, `op` being the instruction fetched from `index`, and returns whether it
+    /// ends the batch of instructions of the tick
+    fn execute(
+        &mut self,
+        op: Op,
+        (index, instruction): (usize, instruction::Instruction),
+        (display_buffer, keyboard_state): (
+            &mut interfaces::DisplayBuffer,
+            &interfaces::KeyboardState,
+        ),
+    ) -> Result<bool, BackendError> {
+        let error = |kind| BackendError {
+            context: None,
+            instruction: Some((index, Some(instruction))),
+            kind,
+        };
+        let v = &mut self.registers.general;
+
+        match op {
+            Op::Clear => display_buffer.clear(),
+
+            Op::Return => match self.stack.pop() {
+                None => return Err(error(BackendErrorKind::StackUnderflow)),
+                Some(address) => self.index = address as usize,
+            },
+
+            // the program stays on the instruction, running it again ends it again
+            Op::Exit => {
+                self.index = index;
+                return Err(error(BackendErrorKind::ProgramExited));
+            }
+
+            // Not implementing 0NNN, needs a 1802 or M6800 VM.
+            Op::Machine => {}
+
+            Op::Jump { nnn } => self.index = nnn,
+
+            Op::Call { nnn } => {
+                if self.stack.len() == STACK_SIZE {
+                    return Err(error(BackendErrorKind::StackOverflow));
+                }
+
+                self.stack.push(self.index as u16);
+                self.index = nnn;
+            }
+
+            Op::SkipEqualImmediate { x, nn } => {
+                if v[x] == nn {
+                    self.skip();
+                }
+            }
+
+            Op::SkipNotEqualImmediate { x, nn } => {
+                if v[x] != nn {
+                    self.skip();
+                }
+            }
+
+            Op::SkipEqual { x, y } => {
+                if v[x] == v[y] {
+                    self.skip();
+                }
+            }
+
+            Op::SkipNotEqual { x, y } => {
+                if v[x] != v[y] {
+                    self.skip();
+                }
+            }
+
+            Op::LoadImmediate { x, nn } => v[x] = nn,
+
+            Op::AddImmediate { x, nn } => v[x] = v[x].wrapping_add(nn),
+
+            Op::Move { x, y } => v[x] = v[y],
+
+            Op::Or { x, y } | Op::And { x, y } | Op::Xor { x, y } => {
+                match op {
+                    Op::Or { .. } => v[x] |= v[y],
+                    Op::And { .. } => v[x] &= v[y],
+                    _ => v[x] ^= v[y],
+                }
+
+                if self.quirks.vf_reset {
+                    v[15] = 0;
+                }
+            }
+
+            Op::Add { x, y } => {
+                let (result, carry) = v[x].overflowing_add(v[y]);
+
+                v[15] = carry as u8;
+                v[x] = result;
+            }
+
+            Op::Subtract { x, y } => {
+                let result = v[x].wrapping_sub(v[y]);
+
+                v[15] = (v[x] > v[y]) as u8;
+                v[x] = result;
+            }
+
+            Op::SubtractReversed { x, y } => {
+                let result = v[y].wrapping_sub(v[x]);
+
+                v[15] = (v[y] > v[x]) as u8;
+                v[x] = result;
+            }
+
+            Op::ShiftRight { x, y } | Op::ShiftLeft { x, y } => {
+                let source = match self.quirks.shift_vy {
+                    true => v[y],
+                    false => v[x],
+                };
+
+                let (result, flag) = match op {
+                    Op::ShiftRight { .. } => (source >> 1, source & 1),
+                    _ => (source << 1, source >> (u8::BITS - 1)),
+                };
+
+                v[15] = flag;
+                v[x] = result;
+            }
+
+            Op::LoadAddress { nnn } => self.registers.address = nnn,
+
+            Op::JumpOffset { x, nnn } => {
+                let offset = match self.quirks.jump_vx {
+                    true => v[x],
+                    false => v[0],
+                };
+
+                self.index = offset as usize + nnn;
+            }
+
+            Op::Random { x, nn } => v[x] = self.rng.gen::<u8>() & nn,
+
+            Op::Draw { x, y, n } => {
+                let sprite = self.registers.address..self.registers.address + n as usize;
+
+                if sprite.end > self.memory.len() {
+                    return Err(error(BackendErrorKind::MemoryOverflow));
+                }
+
+                self.cover(sprite.clone(), Coverage::READ);
+
+                self.registers.general[15] = display_buffer.draw(
+                    (
+                        self.registers.general[x] as usize,
+                        self.registers.general[y] as usize,
+                    ),
+                    &self.memory[sprite],
+                    self.quirks.wrap_sprites,
+                    interfaces::Draw {
+                        address: self.registers.address,
+                        index,
+                    },
+                ) as u8;
+            }
+
+            Op::SkipPressed { x } => {
+                if keyboard_state.pressed(v[x] as usize) {
+                    self.skip();
+                }
+
+                return Ok(!self.quirks.key_checks_continue);
+            }
+
+            Op::SkipNotPressed { x } => {
+                if !keyboard_state.pressed(v[x] as usize) {
+                    self.skip();
+                }
+
+                return Ok(!self.quirks.key_checks_continue);
+            }
+
+            Op::LoadLongAddress => {
+                if self.index + 1 >= self.memory.len() {
+                    return Err(error(BackendErrorKind::MemoryOverflow));
+                }
+
+                self.registers.address =
+                    u16::from_be_bytes([self.memory[self.index], self.memory[self.index + 1]])
+                        as usize;
+                self.cover(self.index..self.index + 2, Coverage::EXECUTED);
+                self.index += mem::size_of::<Instruction>();
+            }
+
+            Op::LoadDelay { x } => v[x] = self.timers.delay,
+
+            Op::WaitKey { x } => {
+                if let Some(key) = keyboard_state.pressed_key() {
+                    v[x] = key as u8;
                 }
 
-                opcode @ (0x3 | 0x4 | 0x5 | 0x9) => {
-                    match opcode {
-                        0x3 if self.registers.general[instruction.operand_x()]
-                            == instruction.operand_nn() => {}
+                self.index = index;
+                return Ok(true);
+            }
+
+            Op::SetDelay { x } => self.timers.delay = v[x],
+
+            Op::SetSound { x } => self.timers.sound = v[x],
+
+            // `I` wraps around at the end of the memory, the size of which is a power of two
+            Op::AddAddress { x } => {
+                self.registers.address =
+                    (self.registers.address + v[x] as usize) & (self.memory.len() - 1)
+            }
+
+            Op::LoadCharacter { x } => {
+                let character_code = v[x] as usize;
+
+                if character_code >= KEY_COUNT {
+                    return Err(error(BackendErrorKind::UnrecognizedSprite));
+                }
 
-                        0x4 if self.registers.general[instruction.operand_x()]
-                            != instruction.operand_nn() => {}
+                self.registers.address = character_code * CHARACTER_SIZE;
+            }
 
-                        0x5 if self.registers.general[instruction.operand_x()]
-                            == self.registers.general[instruction.operand_y()] => {}
+            Op::LoadBigCharacter { x } => {
+                let character_code = v[x] as usize;
 
-                        0x9 if self.registers.general[instruction.operand_x()]
-                            != self.registers.general[instruction.operand_y()] => {}
+                if character_code >= KEY_COUNT {
+                    return Err(error(BackendErrorKind::UnrecognizedSprite));
+                }
 
-                        _ => continue,
-                    }
+                self.registers.address = BIG_FONT_ADDRESS + character_code * BIG_CHARACTER_SIZE;
+            }
 
-                    self.index += mem::size_of::<Instruction>();
+            Op::StoreDecimal { x } => {
+                if self.registers.address + 2 >= self.memory.len() {
+                    return Err(BackendError {
+                        context: None,
+                        instruction: Some((self.index, None)),
+                        kind: BackendErrorKind::MemoryOverflow,
+                    });
                 }
 
-                0x6 => self.registers.general[instruction.operand_x()] = instruction.operand_nn(),
+                let number = v[x];
+
+                self.store(self.registers.address, (number / 10) / 10);
+                self.store(self.registers.address + 1, (number / 10) % 10);
+                self.store(self.registers.address + 2, number % 10);
+            }
 
-                0x7 => {
-                    self.registers.general[instruction.operand_x()] = self.registers.general
-                        [instruction.operand_x()]
-                    .wrapping_add(instruction.operand_nn())
+            Op::Store { x } => {
+                if self.registers.address + x >= self.memory.len() {
+                    return Err(BackendError {
+                        context: None,
+                        instruction: Some((self.index, None)),
+                        kind: BackendErrorKind::MemoryOverflow,
+                    });
                 }
 
-                0x8 => match instruction.operand_n() {
-                    0x0 => {
-                        self.registers.general[instruction.operand_x()] =
-                            self.registers.general[instruction.operand_y()]
-                    }
-
-                    0x1 => {
-                        self.registers.general[instruction.operand_x()] |=
-                            self.registers.general[instruction.operand_y()]
-                    }
-
-                    0x2 => {
-                        self.registers.general[instruction.operand_x()] &=
-                            self.registers.general[instruction.operand_y()]
-                    }
-
-                    0x3 => {
-                        self.registers.general[instruction.operand_x()] ^=
-                            self.registers.general[instruction.operand_y()]
-                    }
-
-                    0x4 => {
-                        let result = self.registers.general[instruction.operand_x()] as u16
-                            + self.registers.general[instruction.operand_y()] as u16;
-
-                        self.registers.general[15] = (result > u8::MAX as u16) as u8;
-                        self.registers.general[instruction.operand_x()] =
-                            (result & u8::MAX as u16) as u8
-                    }
-
-                    code @ (0x5 | 0x7) => {
-                        let result;
-
-                        match code {
-                            0x5 => {
-                                result = self.registers.general[instruction.operand_x()]
-                                    .wrapping_sub(self.registers.general[instruction.operand_y()]);
-                                self.registers.general[15] = (self.registers.general
-                                    [instruction.operand_x()]
-                                    > self.registers.general[instruction.operand_y()])
-                                    as u8;
-                            }
-
-                            0x7 => {
-                                result = self.registers.general[instruction.operand_y()]
-                                    .wrapping_sub(self.registers.general[instruction.operand_x()]);
-                                self.registers.general[15] = (self.registers.general
-                                    [instruction.operand_y()]
-                                    > self.registers.general[instruction.operand_x()])
-                                    as u8;
-                            }
-
-                            _ => unreachable!(),
-                        }
-
-                        self.registers.general[instruction.operand_x()] = result
-                    }
-
-                    code @ (0x6 | 0xE) => {
-                        let result;
-
-                        match code {
-                            0x6 => {
-                                result = self.registers.general[instruction.operand_x()] >> 1;
-                                self.registers.general[15] =
-                                    self.registers.general[instruction.operand_x()] & 1;
-                            }
-                            0xE => {
-                                result = self.registers.general[instruction.operand_x()] << 1;
-                                self.registers.general[15] = self.registers.general
-                                    [instruction.operand_x()]
-                                    >> (u8::BITS - 1) as u8;
-                            }
-                            _ => unreachable!(),
-                        }
-
-                        self.registers.general[instruction.operand_x()] = result
-                    }
-
-                    _ => {
-                        return Err(BackendError {
-                            instruction: Some((last_index, Some(instruction))),
-                            kind: BackendErrorKind::UnrecognizedInstruction,
-                        })
-                    }
-                },
-
-                0xA => self.registers.address = instruction.operand_nnn(),
-
-                0xB => self.index = self.registers.general[0] as usize + instruction.operand_nnn(),
-
-                0xC => {
-                    self.registers.general[instruction.operand_x()] =
-                        rand::random::<u8>() & instruction.operand_nn();
+                for i in 0..x + 1 {
+                    self.store(self.registers.address + i, self.registers.general[i]);
                 }
 
-                0xD => {
-                    self.registers.general[15] = display_buffer.draw(
-                        (
-                            self.registers.general[instruction.operand_x()] as usize,
-                            self.registers.general[instruction.operand_y()] as usize,
-                        ),
-                        &self.memory[self.registers.address as usize
-                            ..self.registers.address as usize + instruction.operand_n() as usize],
-                    ) as u8;
+                if self.quirks.load_store_increment {
+                    self.registers.address += x + 1;
                 }
+            }
 
-                0xE => match instruction.operand_nn() {
-                    0x9E => {
-                        if keyboard_state
-                            .pressed(self.registers.general[instruction.operand_x()] as usize)
-                        {
-                            self.index += mem::size_of::<instruction::Instruction>();
-                        }
-
-                        break;
-                    }
-
-                    0xA1 => {
-                        if !keyboard_state
-                            .pressed(self.registers.general[instruction.operand_x()] as usize)
-                        {
-                            self.index += mem::size_of::<instruction::Instruction>();
-                        }
-
-                        break;
-                    }
-
-                    _ => {
-                        return Err(BackendError {
-                            instruction: Some((last_index, Some(instruction))),
-                            kind: BackendErrorKind::UnrecognizedInstruction,
-                        })
-                    }
-                },
-
-                0xF => match instruction.operand_nn() {
-                    0x07 => self.registers.general[instruction.operand_x()] = self.timers.delay,
-
-                    0x0A => {
-                        if let Some(key) = keyboard_state.pressed_key() {
-                            self.registers.general[instruction.operand_x()] = key as u8;
-                        }
-
-                        self.index = last_index;
-                        break;
-                    }
-
-                    0x15 => self.timers.delay = self.registers.general[instruction.operand_x()],
-
-                    0x18 => self.timers.sound = self.registers.general[instruction.operand_x()],
-
-                    0x1E => {
-                        self.registers.address = (self.registers.address
-                            + self.registers.general[instruction.operand_x()] as usize)
-                            & 0xFFF
-                    }
-
-                    0x29 => {
-                        let character_code =
-                            self.registers.general[instruction.operand_x()] as usize;
-
-                        if character_code as usize >= KEY_COUNT {
-                            return Err(BackendError {
-                                instruction: Some((last_index, Some(instruction))),
-                                kind: BackendErrorKind::UnrecognizedSprite,
-                            });
-                        }
-
-                        self.registers.address = character_code * CHARACTER_SIZE;
-                    }
-
-                    0x33 => {
-                        if self.registers.address + 2 >= self.memory.len() {
-                            return Err(BackendError {
-                                instruction: Some((self.index, None)),
-                                kind: BackendErrorKind::MemoryOverflow,
-                            });
-                        }
-
-                        let number = self.registers.general[instruction.operand_x()];
-
-                        self.memory[self.registers.address] = (number / 10) / 10;
-                        self.memory[self.registers.address + 1] = (number / 10) % 10;
-                        self.memory[self.registers.address + 2] = number % 10;
-                    }
-
-                    0x55 => {
-                        let x = instruction.operand_x() as usize;
-
-                        if self.registers.address + x >= self.memory.len() {
-                            return Err(BackendError {
-                                instruction: Some((self.index, None)),
-                                kind: BackendErrorKind::MemoryOverflow,
-                            });
-                        }
-
-                        for i in 0..x + 1 {
-                            self.memory[self.registers.address + i] = self.registers.general[i];
-                        }
-                    }
-
-                    0x65 => {
-                        let x = instruction.operand_x() as usize;
-
-                        if self.registers.address + x >= self.memory.len() {
-                            return Err(BackendError {
-                                instruction: Some((self.index, None)),
-                                kind: BackendErrorKind::MemoryOverflow,
-                            });
-                        }
-
-                        for i in 0..x + 1 {
-                            self.registers.general[i] = self.memory[self.registers.address + i];
-                        }
-                    }
-
-                    _ => {
-                        return Err(BackendError {
-                            instruction: Some((last_index, Some(instruction))),
-                            kind: BackendErrorKind::UnrecognizedInstruction,
-                        })
-                    }
-                },
-
-                _ => {
+            Op::Load { x } => {
+                if self.registers.address + x >= self.memory.len() {
                     return Err(BackendError {
-                        instruction: Some((last_index, Some(instruction))),
-                        kind: BackendErrorKind::UnrecognizedInstruction,
-                    })
+                        context: None,
+                        instruction: Some((self.index, None)),
+                        kind: BackendErrorKind::MemoryOverflow,
+                    });
+                }
+
+                for i in 0..x + 1 {
+                    let address = self.registers.address + i;
+
+                    self.registers.general[i] = match peripherals::offset(self, address) {
+                        Some(offset) => peripherals::read(self, offset),
+                        None => self.memory[address],
+                    };
+                }
+
+                self.cover(
+                    self.registers.address..self.registers.address + x + 1,
+                    Coverage::READ,
+                );
+
+                if self.quirks.load_store_increment {
+                    self.registers.address += x + 1;
                 }
             }
         }
 
-        Ok((
-            last_index,
-            instruction::Instruction::new([self.memory[last_index], self.memory[last_index + 1]]),
-        ))
+        Ok(false)
+    }
+}
+
+impl FrozenRegisters {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.address.is_none() && self.general.iter().all(Option::is_none)
+    }
+
+    fn apply(&self, registers: &mut Registers) {
+        if let Some(address) = self.address {
+            registers.address = address;
+        }
+
+        for (register, value) in registers.general.iter_mut().zip(self.general) {
+            if let Some(value) = value {
+                *register = value;
+            }
+        }
+    }
+}
+
+impl Statistics {
+    #[inline]
+    pub fn total(&self) -> u64 {
+        self.families.iter().sum()
+    }
+
+    fn count(&mut self, instruction: &Instruction) {
+        self.families[instruction.operator_code() as usize] += 1;
+
+        match (instruction.operator_code(), instruction.operand_nn()) {
+            (0xD, _) => self.draws += 1,
+            (0xE, 0x9E | 0xA1) | (0xF, 0x0A) => self.key_reads += 1,
+            (0xF, 0x18) => self.sounds += 1,
+            _ => (),
+        }
+    }
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            extended_memory: false,
+            jump_vx: false,
+            key_checks_continue: false,
+            load_address: MEMORY_PADDING,
+            load_store_increment: false,
+            shift_vy: false,
+            vf_reset: false,
+            wrap_sprites: false,
+        }
+    }
+}
+
+/// Returns a generator seeded with `seed`, or with a random seed when it's `None`
+fn rng(seed: Option<u64>) -> rand_chacha::ChaCha12Rng {
+    match seed {
+        Some(seed) => rand_chacha::ChaCha12Rng::seed_from_u64(seed),
+        None => rand_chacha::ChaCha12Rng::from_entropy(),
+    }
+}
+
+/// Serializes the coverage as the bits of each byte, which `bitflags` can't do itself
+#[cfg(feature = "serde")]
+mod coverage {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Coverage;
+
+    pub fn serialize<S: Serializer>(
+        coverage: &[Coverage],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let bits: Vec<u8> = coverage.iter().map(Coverage::bits).collect();
+
+        bits.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Coverage>, D::Error> {
+        let bits = Vec::<u8>::deserialize(deserializer)?;
+
+        Ok(bits.into_iter().map(Coverage::from_bits_truncate).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a backend with `program` loaded with the default quirks
+    fn loaded(program: &[u8]) -> Backend {
+        let mut state = Backend::new();
+        state.load(None, program).unwrap();
+
+        state
+    }
+
+    /// Executes `word` as if it was just fetched from the index, and returns whether it ends the
+    /// tick
+    fn execute(state: &mut Backend, word: u16) -> Result<bool, BackendError> {
+        let instruction = Instruction::new(word.to_be_bytes());
+        let op = Op::decode(instruction, &state.quirks).unwrap();
+        let index = state.index;
+        state.index += mem::size_of::<Instruction>();
+
+        state.execute(
+            op,
+            (index, instruction),
+            (
+                &mut interfaces::DisplayBuffer::new(false),
+                &interfaces::KeyboardState::default(),
+            ),
+        )
+    }
+
+    #[test]
+    fn fetch_reads_the_instruction_and_moves_past_it() {
+        let mut state = loaded(&[0x12, 0x34, 0x56, 0x78]);
+
+        let (index, instruction) = state.fetch().unwrap();
+
+        assert_eq!(index, MEMORY_PADDING);
+        assert_eq!(instruction.word(), 0x1234);
+        assert_eq!(state.index, MEMORY_PADDING + 2);
+        assert_eq!(state.fetch().unwrap().1.word(), 0x5678);
+        assert!(state.coverage()[MEMORY_PADDING..MEMORY_PADDING + 4]
+            .iter()
+            .all(|coverage| coverage.contains(Coverage::EXECUTED)));
+    }
+
+    #[test]
+    fn fetch_fails_at_the_end_of_memory() {
+        let mut state = loaded(&[0x00, 0xE0]);
+
+        state.index = MEMORY_SIZE - 2;
+        assert!(state.fetch().is_ok());
+
+        // half an instruction is left
+        state.index = MEMORY_SIZE - 1;
+        let error = state.fetch().unwrap_err();
+
+        assert!(matches!(error.kind, BackendErrorKind::MemoryOverflow));
+        assert!(matches!(error.instruction, Some((index, None)) if index == MEMORY_SIZE - 1));
+        assert_eq!(state.index, MEMORY_SIZE - 1);
+    }
+
+    #[test]
+    fn execute_calls_and_returns() {
+        let mut state = loaded(&[0x00, 0xE0]);
+
+        execute(&mut state, 0x2300).unwrap();
+        assert_eq!(state.index, 0x300);
+        assert_eq!(state.stack, [MEMORY_PADDING as u16 + 2]);
+
+        execute(&mut state, 0x00EE).unwrap();
+        assert_eq!(state.index, MEMORY_PADDING + 2);
+        assert!(state.stack.is_empty());
+
+        let error = execute(&mut state, 0x00EE).unwrap_err();
+        assert!(matches!(error.kind, BackendErrorKind::StackUnderflow));
+    }
+
+    #[test]
+    fn execute_adds_without_carry() {
+        let mut state = loaded(&[0x00, 0xE0]);
+        state.registers.general[0x1] = 0xFF;
+        state.registers.general[0xF] = 0x42;
+
+        execute(&mut state, 0x7102).unwrap();
+
+        assert_eq!(state.registers.general[0x1], 0x01);
+        assert_eq!(state.registers.general[0xF], 0x42);
+    }
+
+    #[test]
+    fn execute_skips_when_equal() {
+        let mut state = loaded(&[0x00, 0xE0]);
+        state.registers.general[0x3] = 0x12;
+
+        execute(&mut state, 0x3312).unwrap();
+        assert_eq!(state.index, MEMORY_PADDING + 4);
+
+        execute(&mut state, 0x3313).unwrap();
+        assert_eq!(state.index, MEMORY_PADDING + 6);
+    }
+
+    #[test]
+    fn execute_stores_the_digits_and_registers() {
+        let mut state = loaded(&[0x00, 0xE0]);
+        state.quirks.load_store_increment = true;
+        state.registers.address = 0x300;
+        state.registers.general[..3].copy_from_slice(&[0x07, 0x08, 0x09]);
+        state.registers.general[0x4] = 254;
+
+        execute(&mut state, 0xF433).unwrap();
+        assert_eq!(state.memory[0x300..0x303], [2, 5, 4]);
+
+        execute(&mut state, 0xF255).unwrap();
+        assert_eq!(state.memory[0x300..0x303], [0x07, 0x08, 0x09]);
+        assert_eq!(state.registers.address, 0x303);
+    }
+
+    #[test]
+    fn key_checks_end_the_tick_unless_they_continue() {
+        // a loop checking key 0 and counting in V1
+        let program = [0xE0, 0x9E, 0x71, 0x01, 0x12, 0x00];
+
+        for (continues, count) in [(false, 0), (true, 10)] {
+            let mut state = Backend::new();
+            state.quirks.key_checks_continue = continues;
+            state.load(None, &program).unwrap();
+
+            state
+                .tick(
+                    num::NonZeroU16::new(30).unwrap(),
+                    (
+                        &mut interfaces::DisplayBuffer::new(false),
+                        &interfaces::KeyboardState::default(),
+                    ),
+                )
+                .unwrap();
+
+            assert_eq!(state.registers.general[0x1], count);
+        }
     }
 }
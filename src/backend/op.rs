@@ -0,0 +1,233 @@
+use super::{Instruction, Quirks};
+
+/// What an instruction does, decoded from its operator code and its operands
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    /// `00E0`
+    Clear,
+    /// `00EE`
+    Return,
+    /// `00FD`, the SCHIP instruction ending the program
+    Exit,
+    /// `0NNN`, a call into machine code, which is ignored
+    Machine,
+    /// `1NNN`
+    Jump { nnn: usize },
+    /// `2NNN`
+    Call { nnn: usize },
+    /// `3XNN`
+    SkipEqualImmediate { x: usize, nn: u8 },
+    /// `4XNN`
+    SkipNotEqualImmediate { x: usize, nn: u8 },
+    /// `5XYN`, the last nibble isn't looked at
+    SkipEqual { x: usize, y: usize },
+    /// `6XNN`
+    LoadImmediate { x: usize, nn: u8 },
+    /// `7XNN`, without carry
+    AddImmediate { x: usize, nn: u8 },
+    /// `8XY0`
+    Move { x: usize, y: usize },
+    /// `8XY1`
+    Or { x: usize, y: usize },
+    /// `8XY2`
+    And { x: usize, y: usize },
+    /// `8XY3`
+    Xor { x: usize, y: usize },
+    /// `8XY4`
+    Add { x: usize, y: usize },
+    /// `8XY5`
+    Subtract { x: usize, y: usize },
+    /// `8XY6`
+    ShiftRight { x: usize, y: usize },
+    /// `8XY7`
+    SubtractReversed { x: usize, y: usize },
+    /// `8XYE`
+    ShiftLeft { x: usize, y: usize },
+    /// `9XYN`, the last nibble isn't looked at
+    SkipNotEqual { x: usize, y: usize },
+    /// `ANNN`
+    LoadAddress { nnn: usize },
+    /// `BNNN`, or `BXNN` with the `jump_vx` quirk
+    JumpOffset { x: usize, nnn: usize },
+    /// `CXNN`
+    Random { x: usize, nn: u8 },
+    /// `DXYN`
+    Draw { x: usize, y: usize, n: u8 },
+    /// `EX9E`
+    SkipPressed { x: usize },
+    /// `EXA1`
+    SkipNotPressed { x: usize },
+    /// `F000 NNNN` with the `extended_memory` quirk, the address is read from the next two bytes
+    LoadLongAddress,
+    /// `FX07`
+    LoadDelay { x: usize },
+    /// `FX0A`
+    WaitKey { x: usize },
+    /// `FX15`
+    SetDelay { x: usize },
+    /// `FX18`
+    SetSound { x: usize },
+    /// `FX1E`
+    AddAddress { x: usize },
+    /// `FX29`
+    LoadCharacter { x: usize },
+    /// `FX30`, a character of the big font of SCHIP
+    LoadBigCharacter { x: usize },
+    /// `FX33`
+    StoreDecimal { x: usize },
+    /// `FX55`
+    Store { x: usize },
+    /// `FX65`
+    Load { x: usize },
+}
+
+impl Op {
+    /// Returns what `instruction` does when run with `quirks`, or `None` if it doesn't do anything
+    /// known
+    pub fn decode(instruction: Instruction, quirks: &Quirks) -> Option<Self> {
+        let (x, y) = (instruction.operand_x(), instruction.operand_y());
+        let (n, nn, nnn) = (
+            instruction.operand_n(),
+            instruction.operand_nn(),
+            instruction.operand_nnn(),
+        );
+
+        Some(match instruction.operator_code() {
+            0x0 => match nnn {
+                0x0E0 => Self::Clear,
+                0x0EE => Self::Return,
+                0x0FD => Self::Exit,
+                _ => Self::Machine,
+            },
+            0x1 => Self::Jump { nnn },
+            0x2 => Self::Call { nnn },
+            0x3 => Self::SkipEqualImmediate { x, nn },
+            0x4 => Self::SkipNotEqualImmediate { x, nn },
+            0x5 => Self::SkipEqual { x, y },
+            0x6 => Self::LoadImmediate { x, nn },
+            0x7 => Self::AddImmediate { x, nn },
+            0x8 => match n {
+                0x0 => Self::Move { x, y },
+                0x1 => Self::Or { x, y },
+                0x2 => Self::And { x, y },
+                0x3 => Self::Xor { x, y },
+                0x4 => Self::Add { x, y },
+                0x5 => Self::Subtract { x, y },
+                0x6 => Self::ShiftRight { x, y },
+                0x7 => Self::SubtractReversed { x, y },
+                0xE => Self::ShiftLeft { x, y },
+                _ => return None,
+            },
+            0x9 => Self::SkipNotEqual { x, y },
+            0xA => Self::LoadAddress { nnn },
+            0xB => Self::JumpOffset { x, nnn },
+            0xC => Self::Random { x, nn },
+            0xD => Self::Draw { x, y, n },
+            0xE => match nn {
+                0x9E => Self::SkipPressed { x },
+                0xA1 => Self::SkipNotPressed { x },
+                _ => return None,
+            },
+            0xF => match nn {
+                0x00 if quirks.extended_memory && x == 0 => Self::LoadLongAddress,
+                0x07 => Self::LoadDelay { x },
+                0x0A => Self::WaitKey { x },
+                0x15 => Self::SetDelay { x },
+                0x18 => Self::SetSound { x },
+                0x1E => Self::AddAddress { x },
+                0x29 => Self::LoadCharacter { x },
+                0x30 => Self::LoadBigCharacter { x },
+                0x33 => Self::StoreDecimal { x },
+                0x55 => Self::Store { x },
+                0x65 => Self::Load { x },
+                _ => return None,
+            },
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(word: u16, quirks: &Quirks) -> Option<Op> {
+        Op::decode(Instruction::new(word.to_be_bytes()), quirks)
+    }
+
+    #[test]
+    fn decodes_every_family() {
+        let quirks = Quirks::default();
+
+        for (word, op) in [
+            (0x00E0, Op::Clear),
+            (0x00EE, Op::Return),
+            (0x00FD, Op::Exit),
+            (0x0123, Op::Machine),
+            (0x1234, Op::Jump { nnn: 0x234 }),
+            (0x2345, Op::Call { nnn: 0x345 }),
+            (0x3A12, Op::SkipEqualImmediate { x: 0xA, nn: 0x12 }),
+            (0x4B34, Op::SkipNotEqualImmediate { x: 0xB, nn: 0x34 }),
+            (0x5120, Op::SkipEqual { x: 0x1, y: 0x2 }),
+            (0x6C56, Op::LoadImmediate { x: 0xC, nn: 0x56 }),
+            (0x7D78, Op::AddImmediate { x: 0xD, nn: 0x78 }),
+            (0x8120, Op::Move { x: 0x1, y: 0x2 }),
+            (0x8121, Op::Or { x: 0x1, y: 0x2 }),
+            (0x8122, Op::And { x: 0x1, y: 0x2 }),
+            (0x8123, Op::Xor { x: 0x1, y: 0x2 }),
+            (0x8124, Op::Add { x: 0x1, y: 0x2 }),
+            (0x8125, Op::Subtract { x: 0x1, y: 0x2 }),
+            (0x8126, Op::ShiftRight { x: 0x1, y: 0x2 }),
+            (0x8127, Op::SubtractReversed { x: 0x1, y: 0x2 }),
+            (0x812E, Op::ShiftLeft { x: 0x1, y: 0x2 }),
+            (0x9340, Op::SkipNotEqual { x: 0x3, y: 0x4 }),
+            (0xA456, Op::LoadAddress { nnn: 0x456 }),
+            (0xB567, Op::JumpOffset { x: 0x5, nnn: 0x567 }),
+            (0xC6FF, Op::Random { x: 0x6, nn: 0xFF }),
+            (
+                0xD785,
+                Op::Draw {
+                    x: 0x7,
+                    y: 0x8,
+                    n: 0x5,
+                },
+            ),
+            (0xE99E, Op::SkipPressed { x: 0x9 }),
+            (0xEAA1, Op::SkipNotPressed { x: 0xA }),
+            (0xF107, Op::LoadDelay { x: 0x1 }),
+            (0xF20A, Op::WaitKey { x: 0x2 }),
+            (0xF315, Op::SetDelay { x: 0x3 }),
+            (0xF418, Op::SetSound { x: 0x4 }),
+            (0xF51E, Op::AddAddress { x: 0x5 }),
+            (0xF629, Op::LoadCharacter { x: 0x6 }),
+            (0xF730, Op::LoadBigCharacter { x: 0x7 }),
+            (0xF833, Op::StoreDecimal { x: 0x8 }),
+            (0xF955, Op::Store { x: 0x9 }),
+            (0xFA65, Op::Load { x: 0xA }),
+        ] {
+            assert_eq!(decode(word, &quirks), Some(op), "{:04X}", word);
+        }
+    }
+
+    #[test]
+    fn long_address_needs_extended_memory() {
+        let extended = Quirks {
+            extended_memory: true,
+            ..Quirks::default()
+        };
+
+        assert_eq!(decode(0xF000, &Quirks::default()), None);
+        assert_eq!(decode(0xF000, &extended), Some(Op::LoadLongAddress));
+        // only `F000` reads an address, not `FX00`
+        assert_eq!(decode(0xF100, &extended), None);
+    }
+
+    #[test]
+    fn unknown_encodings_decode_to_nothing() {
+        let quirks = Quirks::default();
+
+        for word in [0x812F, 0x8128, 0xE1FF, 0xE19F, 0xF1FF, 0xF101] {
+            assert_eq!(decode(word, &quirks), None, "{:04X}", word);
+        }
+    }
+}
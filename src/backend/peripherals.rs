@@ -0,0 +1,56 @@
+use std::time;
+
+use super::Backend;
+
+/// Size of the window of the pseudo-peripherals at the end of the memory, see the README
+pub const WINDOW_SIZE: usize = 16;
+
+/// Offset of the wall-clock time in UTC, hours, minutes, seconds and hundredths
+const CLOCK: usize = 0x0;
+/// Offset of the byte printed to the console when written
+const CONSOLE: usize = 0x9;
+/// Offset of the ticks since the program was loaded or reset, 4 bytes big-endian
+const FRAMES: usize = 0x4;
+/// Offset of a random byte, from the same generator as `CXNN`
+const RANDOM: usize = 0x8;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Returns the offset of `address` into the window, if the peripherals are enabled and it's in it
+#[inline]
+pub(super) fn offset(state: &Backend, address: usize) -> Option<usize> {
+    let start = state.memory.len() - WINDOW_SIZE;
+
+    address
+        .checked_sub(start)
+        .filter(|_| state.peripherals && address < state.memory.len())
+}
+
+/// Returns the byte at `offset` into the window, the offsets nothing is mapped to read 0
+pub(super) fn read(state: &mut Backend, offset: usize) -> u8 {
+    match offset {
+        CLOCK..=0x3 => {
+            let since_epoch = time::SystemTime::now()
+                .duration_since(time::UNIX_EPOCH)
+                .unwrap_or_default();
+            let seconds = since_epoch.as_secs() % SECONDS_PER_DAY;
+
+            [
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60,
+                since_epoch.subsec_millis() as u64 / 10,
+            ][offset - CLOCK] as u8
+        }
+        FRAMES..=0x7 => (state.frames as u32).to_be_bytes()[offset - FRAMES],
+        RANDOM => rand::Rng::gen(&mut state.rng),
+        _ => 0,
+    }
+}
+
+/// Writes `value` at `offset` into the window, writing the offsets nothing is mapped to does nothing
+pub(super) fn write(state: &mut Backend, offset: usize, value: u8) {
+    if offset == CONSOLE {
+        state.console.push(value);
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections;
+use std::hash::{Hash, Hasher};
+
+use super::{interfaces, Backend};
+
+/// Finds when a program stopped changing its display, its memory and its registers, which a running
+/// program rarely does for long unless it's stuck
+///
+/// The program counter and the timers are left out, a program stuck in a loop or waiting for a
+/// timer still changes them.
+pub struct StallDetector {
+    /// Fingerprint of the state as of the last change
+    fingerprint: Option<u64>,
+    /// Ticks the state hasn't changed for
+    still: u64,
+    /// Ticks without a change after which the program is taken for stalled
+    threshold: u64,
+}
+
+impl StallDetector {
+    #[inline]
+    pub fn new(threshold: u64) -> Self {
+        Self {
+            fingerprint: None,
+            still: 0,
+            threshold,
+        }
+    }
+
+    /// Looks at the state after a tick and returns whether the program just stalled, once per
+    /// stall
+    pub fn observe(&mut self, state: &Backend, display_buffer: &interfaces::DisplayBuffer) -> bool {
+        let mut hasher = collections::hash_map::DefaultHasher::new();
+
+        state.registers.general.hash(&mut hasher);
+        state.registers.address.hash(&mut hasher);
+        state.stack.hash(&mut hasher);
+        state.memory.hash(&mut hasher);
+        display_buffer.hash().hash(&mut hasher);
+
+        let fingerprint = hasher.finish();
+
+        match self.fingerprint.replace(fingerprint) == Some(fingerprint) {
+            true => self.still += 1,
+            false => self.still = 0,
+        }
+
+        self.threshold > 0 && self.still == self.threshold
+    }
+
+    /// Ticks the state hasn't changed for
+    #[inline]
+    pub fn still(&self) -> u64 {
+        self.still
+    }
+}
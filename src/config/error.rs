@@ -0,0 +1,48 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Deserialize(toml::de::Error),
+    InvalidGeometry(String),
+    InvalidHotkey(String),
+    InvalidKeyBinding(String),
+    InvalidMetadata(String),
+    IO(io::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialize(error) => write!(f, "invalid configuration, {}", error),
+            Self::InvalidGeometry(geometry) => write!(f, "invalid window geometry '{}'", geometry),
+            Self::InvalidHotkey(hotkey) => write!(f, "invalid hotkey '{}'", hotkey),
+            Self::InvalidKeyBinding(binding) => write!(f, "invalid key binding '{}'", binding),
+            Self::InvalidMetadata(error) => write!(f, "invalid program metadata, {}", error),
+            Self::IO(error) => write!(f, "{}", error),
+            Self::Serialize(error) => write!(f, "couldn't serialize the configuration, {}", error),
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> Self {
+        Self::IO(error)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::Deserialize(error)
+    }
+}
+
+impl From<toml::ser::Error> for ConfigError {
+    fn from(error: toml::ser::Error) -> Self {
+        Self::Serialize(error)
+    }
+}
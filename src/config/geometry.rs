@@ -0,0 +1,71 @@
+use std::str;
+
+use serde::{Deserialize, Serialize};
+
+use super::ConfigError;
+
+/// Size and position of the window in points, written `WxH+X+Y` like the geometry of X11
+/// programs, the position being optional
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WindowGeometry {
+    pub height: f32,
+    pub width: f32,
+    /// Coordinates of the top left corner of the window, left to the window manager when `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<f32>,
+}
+
+impl WindowGeometry {
+    #[inline]
+    pub fn position(&self) -> Option<egui::Pos2> {
+        Some(egui::pos2(self.x?, self.y?))
+    }
+
+    #[inline]
+    pub fn size(&self) -> egui::Vec2 {
+        egui::vec2(self.width, self.height)
+    }
+}
+
+impl str::FromStr for WindowGeometry {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ConfigError::InvalidGeometry(s.to_owned());
+        let number = |number: &str| {
+            number
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .ok_or_else(invalid)
+        };
+
+        let mut parts = s.split('+');
+        let (width, height) = parts
+            .next()
+            .and_then(|size| size.split_once(['x', 'X']))
+            .ok_or_else(invalid)?;
+
+        let (x, y) = match (parts.next(), parts.next(), parts.next()) {
+            (None, None, None) => (None, None),
+            (Some(x), Some(y), None) => (Some(number(x)?), Some(number(y)?)),
+            _ => return Err(invalid()),
+        };
+
+        let (width, height) = (number(width)?, number(height)?);
+
+        if width <= 0.0 || height <= 0.0 {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            height,
+            width,
+            x,
+            y,
+        })
+    }
+}
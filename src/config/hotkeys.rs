@@ -0,0 +1,195 @@
+use std::collections;
+use std::fmt;
+use std::str;
+
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::defaults;
+
+use super::ConfigError;
+
+/// Emulator functions that can be triggered with a hotkey
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Continue,
+    LoadState,
+    Log,
+    NextProgram,
+    Pause,
+    PreviousProgram,
+    Reset,
+    SaveState,
+    Screenshot,
+    Step,
+    Turbo,
+}
+
+/// A key and the modifiers that have to be held along with it, e.g. `Ctrl+Shift+S`
+#[derive(Clone, Copy, PartialEq)]
+pub struct Hotkey {
+    pub key: egui::Key,
+    pub modifiers: egui::Modifiers,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Hotkeys(collections::BTreeMap<Action, Hotkey>);
+
+impl Action {
+    pub const ALL: [Self; 11] = [
+        Self::Pause,
+        Self::Step,
+        Self::Continue,
+        Self::Turbo,
+        Self::Reset,
+        Self::NextProgram,
+        Self::PreviousProgram,
+        Self::SaveState,
+        Self::LoadState,
+        Self::Screenshot,
+        Self::Log,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Continue => "Continue",
+            Self::LoadState => "Load State",
+            Self::Log => "Log",
+            Self::NextProgram => "Next Program",
+            Self::Pause => "Pause",
+            Self::PreviousProgram => "Previous Program",
+            Self::Reset => "Reset",
+            Self::SaveState => "Save State",
+            Self::Screenshot => "Screenshot",
+            Self::Step => "Step",
+            Self::Turbo => "Turbo",
+        }
+    }
+}
+
+impl Hotkey {
+    #[inline]
+    pub const fn new(modifiers: egui::Modifiers, key: egui::Key) -> Self {
+        Self { key, modifiers }
+    }
+
+    /// Returns the hotkey matching a key press with `modifiers` held
+    pub fn pressed(modifiers: egui::Modifiers, key: egui::Key) -> Self {
+        Self::new(
+            egui::Modifiers {
+                command: false,
+                ..modifiers
+            },
+            key,
+        )
+    }
+
+    #[inline]
+    pub fn shortcut(&self) -> egui::KeyboardShortcut {
+        egui::KeyboardShortcut::new(self.modifiers, self.key)
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.alt, "Alt"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.mac_cmd, "Cmd"),
+        ] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        write!(f, "{:?}", self.key)
+    }
+}
+
+impl str::FromStr for Hotkey {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ConfigError::InvalidHotkey(s.to_owned());
+
+        let (modifiers, key) = match s.rsplit_once('+') {
+            Some((modifiers, key)) => (Some(modifiers), key),
+            None => (None, s),
+        };
+
+        let mut hotkey = Self::new(
+            egui::Modifiers::NONE,
+            egui::Key::deserialize(key.trim().into_deserializer())
+                .map_err(|_: serde::de::value::Error| invalid())?,
+        );
+
+        for modifier in modifiers
+            .into_iter()
+            .flat_map(|modifiers| modifiers.split('+'))
+        {
+            match modifier.trim() {
+                "Alt" => hotkey.modifiers.alt = true,
+                "Cmd" => hotkey.modifiers.mac_cmd = true,
+                "Ctrl" => hotkey.modifiers.ctrl = true,
+                "Shift" => hotkey.modifiers.shift = true,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(hotkey)
+    }
+}
+
+impl<'de> Deserialize<'de> for Hotkey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for Hotkey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Hotkeys {
+    #[inline]
+    pub fn get(&self, action: Action) -> Hotkey {
+        self.0[&action]
+    }
+
+    #[inline]
+    pub fn set(&mut self, action: Action, hotkey: Hotkey) {
+        self.0.insert(action, hotkey);
+    }
+}
+
+impl Default for Hotkeys {
+    #[inline]
+    fn default() -> Self {
+        Self(defaults::HOTKEYS.into_iter().collect())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hotkeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut hotkeys = Self::default();
+        hotkeys
+            .0
+            .extend(collections::BTreeMap::<Action, Hotkey>::deserialize(
+                deserializer,
+            )?);
+
+        Ok(hotkeys)
+    }
+}
+
+impl Serialize for Hotkeys {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections;
+use std::str;
+
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::backend;
+use crate::defaults;
+
+use super::ConfigError;
+
+/// Physical keys bound to each of the keys of the CHIP-8 keypad
+#[derive(Clone, PartialEq)]
+pub struct Keymap([egui::Key; backend::KEY_COUNT]);
+
+/// A list of `KEY=BINDING` pairs separated by commas, e.g. `1=Num1,2=Num2,F=V`
+#[derive(Clone)]
+pub struct KeyBindings(Vec<(usize, egui::Key)>);
+
+impl Keymap {
+    pub fn apply(&mut self, bindings: &KeyBindings) {
+        for (key, binding) in bindings.0.iter() {
+            self.0[*key] = *binding;
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, key: usize) -> egui::Key {
+        self.0[key]
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (usize, egui::Key)> + '_ {
+        self.0.iter().copied().enumerate()
+    }
+}
+
+impl Default for Keymap {
+    #[inline]
+    fn default() -> Self {
+        Self(defaults::KEYMAP)
+    }
+}
+
+impl<'de> Deserialize<'de> for Keymap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bindings = collections::BTreeMap::<String, egui::Key>::deserialize(deserializer)?;
+        let mut keymap = Self::default();
+
+        for (key, binding) in bindings {
+            match parse_key(&key) {
+                Some(key) => keymap.0[key] = binding,
+                None => {
+                    return Err(serde::de::Error::custom(format!(
+                        "unrecognized keypad key '{}'",
+                        key
+                    )))
+                }
+            }
+        }
+
+        Ok(keymap)
+    }
+}
+
+impl Serialize for Keymap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.iter()
+                .map(|(key, binding)| (format!("{:X}", key), binding)),
+        )
+    }
+}
+
+impl str::FromStr for KeyBindings {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .filter(|binding| !binding.trim().is_empty())
+            .map(|binding| {
+                binding
+                    .split_once('=')
+                    .and_then(|(key, binding)| Some((parse_key(key)?, parse_binding(binding)?)))
+                    .ok_or_else(|| ConfigError::InvalidKeyBinding(binding.trim().to_owned()))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+fn parse_binding(binding: &str) -> Option<egui::Key> {
+    egui::Key::deserialize(binding.trim().into_deserializer())
+        .map_err(|_: serde::de::value::Error| ())
+        .ok()
+}
+
+pub(super) fn parse_key(key: &str) -> Option<usize> {
+    usize::from_str_radix(key.trim(), 16)
+        .ok()
+        .filter(|key| *key < backend::KEY_COUNT)
+}
@@ -0,0 +1,79 @@
+use std::collections;
+use std::fs;
+use std::io;
+use std::num;
+use std::path;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::backend;
+
+use super::{keymap, ConfigError, EmulatorConfig};
+
+/// Extensions of the sidecar files a program's metadata is looked for in, in order
+const EXTENSIONS: [&str; 2] = ["toml", "json"];
+
+/// Description of a program read from a file next to it, e.g. `game.toml` for `game.ch8`
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub author: Option<String>,
+    /// What the keys of the keypad the program uses do
+    #[serde(deserialize_with = "deserialize_controls")]
+    pub controls: collections::BTreeMap<usize, String>,
+    /// Quirks the program was written for
+    pub quirks: Option<backend::Quirks>,
+    /// Instructions per tick the program is meant to run at
+    pub speed: Option<num::NonZeroU16>,
+    pub title: Option<String>,
+}
+
+impl Metadata {
+    /// Applies the recommended settings to `config`
+    pub fn apply(&self, config: &mut EmulatorConfig) {
+        if let Some(quirks) = self.quirks {
+            config.quirks = quirks;
+        }
+
+        if let Some(speed) = self.speed {
+            config.instructions_per_tick = speed;
+        }
+    }
+
+    /// Loads the metadata of `program`, if it has any
+    pub fn load(program: &path::Path) -> Result<Option<Self>, ConfigError> {
+        for extension in EXTENSIONS {
+            let contents = match fs::read_to_string(program.with_extension(extension)) {
+                Ok(contents) => contents,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            };
+
+            let metadata = match extension {
+                "toml" => toml::from_str(&contents)
+                    .map_err(|error| ConfigError::InvalidMetadata(error.to_string())),
+                _ => serde_json::from_str(&contents)
+                    .map_err(|error| ConfigError::InvalidMetadata(error.to_string())),
+            };
+
+            return metadata.map(Some);
+        }
+
+        Ok(None)
+    }
+}
+
+fn deserialize_controls<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<collections::BTreeMap<usize, String>, D::Error> {
+    collections::BTreeMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, control)| match keymap::parse_key(&key) {
+            Some(key) => Ok((key, control)),
+            None => Err(serde::de::Error::custom(format!(
+                "unrecognized keypad key '{}'",
+                key
+            ))),
+        })
+        .collect()
+}
@@ -0,0 +1,644 @@
+use std::collections;
+use std::fs;
+use std::io;
+use std::num;
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend;
+use crate::defaults;
+use crate::frontend;
+use crate::i18n;
+use crate::ui;
+
+mod error;
+mod geometry;
+mod hotkeys;
+mod keymap;
+mod metadata;
+
+pub use error::ConfigError;
+pub use geometry::WindowGeometry;
+pub use hotkeys::{Action, Hotkey, Hotkeys};
+pub use keymap::{KeyBindings, Keymap};
+pub use metadata::Metadata;
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Every user facing setting of the emulator
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct EmulatorConfig {
+    /// Keep the window above the other ones
+    pub always_on_top: bool,
+    /// Frames of the audio buffer, the default of the device is used when it's `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_buffer_size: Option<u32>,
+    /// Name of the audio output device, the default one is used when it's `None` or missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_device: Option<String>,
+    /// Minutes after which the next program in the playlist is started, zero to never advance
+    pub auto_advance_minutes: u16,
+    /// Audio file played while the sound timer is active, the built-in beep is played when it's
+    /// `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beep_sound: Option<path::PathBuf>,
+    pub colors: frontend::Colors,
+    /// Show what the keys a program uses do over its display, when its metadata tells
+    pub controls_overlay: bool,
+    pub core: backend::CoreKind,
+    /// Instruction that suspends the program into the debugger in debug mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug_break: Option<u16>,
+    pub debug_mode: bool,
+    pub fade_effect: bool,
+    /// Built-in font programs are loaded with when no font file is picked
+    pub font: backend::Font,
+    /// Show a hash of the display in its corner, to compare the frames of two runs at a glance
+    pub hash_overlay: bool,
+    pub hotkeys: Hotkeys,
+    pub instructions_per_tick: num::NonZeroU16,
+    /// Print the errors as JSON objects with their code, for scripts wrapping the emulator
+    pub json_errors: bool,
+    /// Which of the held keys a program waiting for a key press gets
+    pub key_priority: backend::KeyPriority,
+    pub keymap: Keymap,
+    pub language: String,
+    /// Directories scanned for programs to list in the library
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub library_directories: Vec<path::PathBuf>,
+    /// Directory to look for locale files in instead of the default one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale_directory: Option<path::PathBuf>,
+    pub orientation: ui::Orientation,
+    /// Patch files applied to the program when it's loaded, in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patches: Vec<path::PathBuf>,
+    /// Pause a program that keeps coming back to the same state without drawing or reading the
+    /// keys, e.g. in a jump to itself, instead of running it forever
+    pub pause_on_halt: bool,
+    /// Map the pseudo-peripherals to the end of the memory, an extension of RC-8 described in the
+    /// README
+    pub peripherals: bool,
+    /// Play the beep higher the shorter the sound timer was set and lower the longer, to tell the
+    /// sounds of a program apart
+    pub pitched_beep: bool,
+    pub quirks: backend::Quirks,
+    /// Save the size and position of the window on exit to open it the same way the next time
+    pub remember_window_geometry: bool,
+    /// How the display is filtered when it's scaled to the window
+    pub scaling_filter: frontend::ScalingFilter,
+    /// Seed of the random numbers of programs, for their runs to be reproducible, a random one is
+    /// used when it's `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Warn when a program overwrites code it executed, or stop it in debug mode
+    pub self_modification_warnings: bool,
+    /// Run the emulation in the user interface's frames instead of a thread of its own
+    pub single_threaded: bool,
+    /// Seconds of emulated time without a change of the display, the memory or the registers after
+    /// which a program is warned about being stuck, zero to never warn
+    pub stall_warning_seconds: u16,
+    /// How the display is sized in the window
+    pub stretch: ui::Stretch,
+    /// Steps per second while the step hotkey is held in debug mode, zero to step once per press
+    pub step_repeat_rate: u16,
+    /// What the diagnostics program fills the display with
+    pub test_pattern: ui::TestPattern,
+    pub theme: ui::Theme,
+    /// Slow the emulation down and stop updating the display while the window is unfocused or
+    /// minimized
+    pub throttle_in_background: bool,
+    /// Scale of the user interface relative to the native pixels per point of the display
+    pub ui_scale: f32,
+    /// Quirks of a reference run of the program to execute in lockstep with, stopping at the first
+    /// divergence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_quirks: Option<backend::Quirks>,
+    /// Outline the display while the sound timer is active, for when the sound can't be heard
+    pub visual_beep: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_geometry: Option<WindowGeometry>,
+}
+
+pub struct EmulatorConfigBuilder(EmulatorConfig);
+
+/// The configuration file, a base configuration followed by per-program overrides
+#[derive(Default, Deserialize, Serialize)]
+pub struct ConfigFile {
+    #[serde(flatten)]
+    pub config: EmulatorConfig,
+    /// Partial configurations keyed by the file name of the program they apply to
+    #[serde(default, skip_serializing_if = "collections::BTreeMap::is_empty")]
+    pub programs: collections::BTreeMap<String, toml::Table>,
+}
+
+impl EmulatorConfig {
+    #[inline]
+    pub fn builder() -> EmulatorConfigBuilder {
+        EmulatorConfigBuilder(Self::default())
+    }
+
+    pub fn locale_directory(&self) -> Option<path::PathBuf> {
+        self.locale_directory
+            .clone()
+            .or_else(i18n::Localizer::default_directory)
+    }
+
+    /// Returns a copy of the configuration with the entries of `overrides` replacing its own
+    pub fn merged(&self, overrides: &toml::Table) -> Result<Self, ConfigError> {
+        let mut table = toml::Table::try_from(self)?;
+        merge(&mut table, overrides);
+
+        Ok(table.try_into()?)
+    }
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> Self {
+        Self {
+            always_on_top: false,
+            audio_buffer_size: None,
+            audio_device: None,
+            auto_advance_minutes: 0,
+            beep_sound: None,
+            colors: defaults::COLORS,
+            controls_overlay: false,
+            core: backend::CoreKind::default(),
+            debug_break: None,
+            debug_mode: false,
+            fade_effect: false,
+            font: backend::Font::default(),
+            hash_overlay: false,
+            hotkeys: Hotkeys::default(),
+            instructions_per_tick: defaults::INSTRUCTIONS_PER_TICK,
+            json_errors: false,
+            key_priority: backend::KeyPriority::default(),
+            keymap: Keymap::default(),
+            language: i18n::DEFAULT_LANGUAGE.to_owned(),
+            library_directories: Vec::new(),
+            locale_directory: None,
+            orientation: ui::Orientation::default(),
+            patches: Vec::new(),
+            pause_on_halt: false,
+            peripherals: false,
+            pitched_beep: false,
+            quirks: backend::Quirks::default(),
+            remember_window_geometry: true,
+            scaling_filter: frontend::ScalingFilter::default(),
+            seed: None,
+            self_modification_warnings: false,
+            // there are no threads to run the emulation in on the web
+            single_threaded: cfg!(target_arch = "wasm32"),
+            stall_warning_seconds: 0,
+            step_repeat_rate: defaults::STEP_REPEAT_RATE,
+            stretch: ui::Stretch::default(),
+            test_pattern: ui::TestPattern::default(),
+            theme: ui::Theme::default(),
+            throttle_in_background: false,
+            ui_scale: 1.0,
+            verify_quirks: None,
+            visual_beep: false,
+            window_geometry: None,
+        }
+    }
+}
+
+impl EmulatorConfigBuilder {
+    #[inline]
+    pub fn build(self) -> EmulatorConfig {
+        self.0
+    }
+
+    #[inline]
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.0.always_on_top = always_on_top;
+        self
+    }
+
+    #[inline]
+    pub fn audio_buffer_size(mut self, audio_buffer_size: Option<u32>) -> Self {
+        self.0.audio_buffer_size = audio_buffer_size;
+        self
+    }
+
+    #[inline]
+    pub fn audio_device(mut self, audio_device: Option<String>) -> Self {
+        self.0.audio_device = audio_device;
+        self
+    }
+
+    #[inline]
+    pub fn auto_advance_minutes(mut self, auto_advance_minutes: u16) -> Self {
+        self.0.auto_advance_minutes = auto_advance_minutes;
+        self
+    }
+
+    #[inline]
+    pub fn beep_sound(mut self, beep_sound: Option<path::PathBuf>) -> Self {
+        self.0.beep_sound = beep_sound;
+        self
+    }
+
+    #[inline]
+    pub fn colors(mut self, colors: frontend::Colors) -> Self {
+        self.0.colors = colors;
+        self
+    }
+
+    #[inline]
+    pub fn controls_overlay(mut self, controls_overlay: bool) -> Self {
+        self.0.controls_overlay = controls_overlay;
+        self
+    }
+
+    #[inline]
+    pub fn core(mut self, core: backend::CoreKind) -> Self {
+        self.0.core = core;
+        self
+    }
+
+    #[inline]
+    pub fn debug_break(mut self, debug_break: Option<u16>) -> Self {
+        self.0.debug_break = debug_break;
+        self
+    }
+
+    #[inline]
+    pub fn debug_mode(mut self, debug_mode: bool) -> Self {
+        self.0.debug_mode = debug_mode;
+        self
+    }
+
+    #[inline]
+    pub fn fade_effect(mut self, fade_effect: bool) -> Self {
+        self.0.fade_effect = fade_effect;
+        self
+    }
+
+    #[inline]
+    pub fn font(mut self, font: backend::Font) -> Self {
+        self.0.font = font;
+        self
+    }
+
+    #[inline]
+    pub fn hash_overlay(mut self, hash_overlay: bool) -> Self {
+        self.0.hash_overlay = hash_overlay;
+        self
+    }
+
+    #[inline]
+    pub fn hotkeys(mut self, hotkeys: Hotkeys) -> Self {
+        self.0.hotkeys = hotkeys;
+        self
+    }
+
+    #[inline]
+    pub fn instructions_per_tick(mut self, instructions_per_tick: num::NonZeroU16) -> Self {
+        self.0.instructions_per_tick = instructions_per_tick;
+        self
+    }
+
+    #[inline]
+    pub fn json_errors(mut self, json_errors: bool) -> Self {
+        self.0.json_errors = json_errors;
+        self
+    }
+
+    #[inline]
+    pub fn key_priority(mut self, key_priority: backend::KeyPriority) -> Self {
+        self.0.key_priority = key_priority;
+        self
+    }
+
+    #[inline]
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.0.keymap = keymap;
+        self
+    }
+
+    #[inline]
+    pub fn language(mut self, language: String) -> Self {
+        self.0.language = language;
+        self
+    }
+
+    #[inline]
+    pub fn library_directories(mut self, library_directories: Vec<path::PathBuf>) -> Self {
+        self.0.library_directories = library_directories;
+        self
+    }
+
+    #[inline]
+    pub fn locale_directory(mut self, locale_directory: Option<path::PathBuf>) -> Self {
+        self.0.locale_directory = locale_directory;
+        self
+    }
+
+    #[inline]
+    pub fn patches(mut self, patches: Vec<path::PathBuf>) -> Self {
+        self.0.patches = patches;
+        self
+    }
+
+    #[inline]
+    pub fn pause_on_halt(mut self, pause_on_halt: bool) -> Self {
+        self.0.pause_on_halt = pause_on_halt;
+        self
+    }
+
+    #[inline]
+    pub fn orientation(mut self, orientation: ui::Orientation) -> Self {
+        self.0.orientation = orientation;
+        self
+    }
+
+    #[inline]
+    pub fn peripherals(mut self, peripherals: bool) -> Self {
+        self.0.peripherals = peripherals;
+        self
+    }
+
+    #[inline]
+    pub fn pitched_beep(mut self, pitched_beep: bool) -> Self {
+        self.0.pitched_beep = pitched_beep;
+        self
+    }
+
+    #[inline]
+    pub fn quirks(mut self, quirks: backend::Quirks) -> Self {
+        self.0.quirks = quirks;
+        self
+    }
+
+    #[inline]
+    pub fn remember_window_geometry(mut self, remember_window_geometry: bool) -> Self {
+        self.0.remember_window_geometry = remember_window_geometry;
+        self
+    }
+
+    #[inline]
+    pub fn scaling_filter(mut self, scaling_filter: frontend::ScalingFilter) -> Self {
+        self.0.scaling_filter = scaling_filter;
+        self
+    }
+
+    #[inline]
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.0.seed = seed;
+        self
+    }
+
+    #[inline]
+    pub fn self_modification_warnings(mut self, self_modification_warnings: bool) -> Self {
+        self.0.self_modification_warnings = self_modification_warnings;
+        self
+    }
+
+    #[inline]
+    pub fn single_threaded(mut self, single_threaded: bool) -> Self {
+        self.0.single_threaded = single_threaded;
+        self
+    }
+
+    #[inline]
+    pub fn stall_warning_seconds(mut self, stall_warning_seconds: u16) -> Self {
+        self.0.stall_warning_seconds = stall_warning_seconds;
+        self
+    }
+
+    #[inline]
+    pub fn step_repeat_rate(mut self, step_repeat_rate: u16) -> Self {
+        self.0.step_repeat_rate = step_repeat_rate;
+        self
+    }
+
+    #[inline]
+    pub fn stretch(mut self, stretch: ui::Stretch) -> Self {
+        self.0.stretch = stretch;
+        self
+    }
+
+    #[inline]
+    pub fn test_pattern(mut self, test_pattern: ui::TestPattern) -> Self {
+        self.0.test_pattern = test_pattern;
+        self
+    }
+
+    #[inline]
+    pub fn theme(mut self, theme: ui::Theme) -> Self {
+        self.0.theme = theme;
+        self
+    }
+
+    #[inline]
+    pub fn throttle_in_background(mut self, throttle_in_background: bool) -> Self {
+        self.0.throttle_in_background = throttle_in_background;
+        self
+    }
+
+    #[inline]
+    pub fn ui_scale(mut self, ui_scale: f32) -> Self {
+        self.0.ui_scale = ui_scale;
+        self
+    }
+
+    #[inline]
+    pub fn verify_quirks(mut self, verify_quirks: Option<backend::Quirks>) -> Self {
+        self.0.verify_quirks = verify_quirks;
+        self
+    }
+
+    #[inline]
+    pub fn visual_beep(mut self, visual_beep: bool) -> Self {
+        self.0.visual_beep = visual_beep;
+        self
+    }
+
+    #[inline]
+    pub fn window_geometry(mut self, window_geometry: Option<WindowGeometry>) -> Self {
+        self.0.window_geometry = window_geometry;
+        self
+    }
+
+    #[inline]
+    pub fn wrap_sprites(mut self, wrap_sprites: bool) -> Self {
+        self.0.quirks.wrap_sprites = wrap_sprites;
+        self
+    }
+}
+
+impl From<EmulatorConfig> for EmulatorConfigBuilder {
+    #[inline]
+    fn from(config: EmulatorConfig) -> Self {
+        Self(config)
+    }
+}
+
+impl ConfigFile {
+    pub fn default_path() -> Option<path::PathBuf> {
+        dirs::config_dir().map(|path| path.join(env!("CARGO_PKG_NAME")).join(CONFIG_FILE_NAME))
+    }
+
+    /// Loads the configuration file at `path`, a missing file is treated as an empty one
+    pub fn load(path: &path::Path) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Returns the patches set for `program`
+    pub fn patches(&self, program: &path::Path) -> Vec<path::PathBuf> {
+        program_key(program)
+            .and_then(|key| self.programs.get(key))
+            .and_then(|overrides| overrides.get("patches"))
+            .and_then(|patches| patches.clone().try_into().ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the configuration to run `program` with
+    pub fn program_config(
+        &self,
+        config: &EmulatorConfig,
+        program: &path::Path,
+    ) -> Result<EmulatorConfig, ConfigError> {
+        match program_key(program).and_then(|key| self.programs.get(key)) {
+            Some(overrides) => config.merged(overrides),
+            None => Ok(config.clone()),
+        }
+    }
+
+    /// Sets the patches of `program` in its overrides
+    pub fn set_patches(
+        &mut self,
+        program: &path::Path,
+        patches: &[path::PathBuf],
+    ) -> Result<(), ConfigError> {
+        let key = match program_key(program) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
+        let overrides = self.programs.entry(key.to_owned()).or_default();
+
+        match patches.is_empty() {
+            true => overrides.remove("patches"),
+            false => overrides.insert("patches".to_owned(), toml::Value::try_from(patches)?),
+        };
+
+        if overrides.is_empty() {
+            self.programs.remove(key);
+        }
+
+        Ok(())
+    }
+
+    /// Applies the settings that differ between `from` and `to` to the base configuration, leaving
+    /// the rest of it as it was loaded
+    pub fn apply_changes(
+        &mut self,
+        from: &EmulatorConfig,
+        to: &EmulatorConfig,
+    ) -> Result<(), ConfigError> {
+        let mut table = toml::Table::try_from(&self.config)?;
+        apply_changes(
+            &mut table,
+            &toml::Table::try_from(from)?,
+            &toml::Table::try_from(to)?,
+        );
+        self.config = table.try_into()?;
+
+        Ok(())
+    }
+
+    pub fn save(&self, path: &path::Path) -> Result<(), ConfigError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the key of the overrides of `program`, its file name
+fn program_key(program: &path::Path) -> Option<&str> {
+    program.file_name().and_then(|file_name| file_name.to_str())
+}
+
+/// Writes the entries of `to` that differ from the ones of `from` to `table`, and removes the ones
+/// `to` left out, e.g. the settings cleared to `None`
+fn apply_changes(table: &mut toml::Table, from: &toml::Table, to: &toml::Table) {
+    for (key, value) in to {
+        match (table.get_mut(key), from.get(key), value) {
+            (
+                Some(toml::Value::Table(table)),
+                Some(toml::Value::Table(from)),
+                toml::Value::Table(to),
+            ) => apply_changes(table, from, to),
+            (_, Some(from), to) if from == to => {}
+            _ => {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    for key in from.keys().filter(|key| !to.contains_key(*key)) {
+        table.remove(key);
+    }
+}
+
+fn merge(table: &mut toml::Table, overrides: &toml::Table) {
+    for (key, value) in overrides {
+        match (table.get_mut(key), value) {
+            (Some(toml::Value::Table(table)), toml::Value::Table(overrides)) => {
+                merge(table, overrides)
+            }
+            _ => {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleared_settings_are_removed_from_the_file() {
+        let config = EmulatorConfig {
+            library_directories: vec![path::PathBuf::from("roms")],
+            seed: Some(7),
+            verify_quirks: Some(backend::Quirks::default()),
+            ..EmulatorConfig::default()
+        };
+
+        let mut file = ConfigFile {
+            config: config.clone(),
+            ..ConfigFile::default()
+        };
+        // a setting left alone in the menu keeps the value it was loaded with
+        file.config.ui_scale = 2.0;
+
+        let mut cleared = config.clone();
+        cleared.library_directories.clear();
+        cleared.seed = None;
+        cleared.verify_quirks = None;
+
+        file.apply_changes(&config, &cleared).unwrap();
+
+        let written: toml::Table = toml::from_str(&toml::to_string(&file).unwrap()).unwrap();
+
+        for key in ["library_directories", "seed", "verify_quirks"] {
+            assert!(!written.contains_key(key), "{} is still written", key);
+        }
+
+        assert_eq!(written["ui_scale"].as_float(), Some(2.0));
+    }
+}
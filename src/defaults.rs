@@ -1,4 +1,7 @@
+use std::num;
+
 use crate::backend;
+use crate::config::{Action, Hotkey};
 use crate::frontend;
 
 pub const COLORS: frontend::Colors = frontend::Colors {
@@ -6,6 +9,97 @@ pub const COLORS: frontend::Colors = frontend::Colors {
     inactive: egui::Color32::BLACK,
 };
 
+pub const HOTKEYS: [(Action, Hotkey); Action::ALL.len()] = [
+    (
+        Action::Continue,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::Space),
+    ),
+    (
+        Action::LoadState,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::F7),
+    ),
+    (
+        Action::Log,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::F9),
+    ),
+    (
+        Action::NextProgram,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::PageDown),
+    ),
+    (
+        Action::Pause,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::Escape),
+    ),
+    (
+        Action::PreviousProgram,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::PageUp),
+    ),
+    (
+        Action::Reset,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::F2),
+    ),
+    (
+        Action::SaveState,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::F5),
+    ),
+    (
+        Action::Screenshot,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::F12),
+    ),
+    (
+        Action::Step,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::Enter),
+    ),
+    (
+        Action::Turbo,
+        Hotkey::new(egui::Modifiers::NONE, egui::Key::Tab),
+    ),
+];
+
+/// Steps per second while the step hotkey is held in debug mode
+pub const STEP_REPEAT_RATE: u16 = 10;
+
+pub const KEYMAP: [egui::Key; backend::KEY_COUNT] = [
+    egui::Key::X,
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Q,
+    egui::Key::W,
+    egui::Key::E,
+    egui::Key::A,
+    egui::Key::S,
+    egui::Key::D,
+    egui::Key::Z,
+    egui::Key::C,
+    egui::Key::Num4,
+    egui::Key::R,
+    egui::Key::F,
+    egui::Key::V,
+];
+
+pub const INSTRUCTIONS_PER_TICK: num::NonZeroU16 = num::NonZeroU16::new(18).unwrap();
+
+/// The big font of SCHIP, along with the letters added by later interpreters
+pub const BIG_FONT: [u8; backend::BIG_FONT_SIZE] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
 pub const FONT: [u8; backend::FONT_SIZE] = [
     0b11110000, 0b10010000, 0b10010000, 0b10010000, 0b11110000, 0b00100000, 0b01100000, 0b00100000,
     0b00100000, 0b01110000, 0b11110000, 0b00010000, 0b11110000, 0b10000000, 0b11110000, 0b11110000,
@@ -0,0 +1,278 @@
+use std::collections;
+use std::mem;
+use std::ops;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{self, Instruction};
+
+/// What the bytes of a program are taken for in a disassembly
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    Code,
+    Data,
+}
+
+/// An instruction referring to an address
+#[derive(Clone, Copy, Debug)]
+pub struct Reference {
+    /// Address of the instruction
+    pub address: usize,
+    pub kind: ReferenceKind,
+}
+
+/// How an instruction refers to an address
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReferenceKind {
+    /// `2NNN`
+    Call,
+    /// `1NNN` and `BNNN`
+    Jump,
+    /// `ANNN`
+    Load,
+}
+
+/// A line of a disassembly, an instruction of a code region or a byte of a data region
+pub struct Line {
+    pub address: usize,
+    pub bytes: Vec<u8>,
+    pub region: Region,
+    /// The mnemonic of the instruction, or a `db` directive for data and unrecognized instructions
+    pub text: String,
+}
+
+impl Region {
+    /// Infers the region of a byte from how the program used it, bytes that weren't used are taken
+    /// for code
+    pub fn infer(coverage: backend::Coverage) -> Self {
+        match coverage.contains(backend::Coverage::READ)
+            && !coverage.contains(backend::Coverage::EXECUTED)
+        {
+            true => Self::Data,
+            false => Self::Code,
+        }
+    }
+}
+
+/// Disassembles the bytes of `memory` in `addresses`, `region` telling what each byte is
+pub fn disassemble(
+    memory: &[u8],
+    addresses: ops::Range<usize>,
+    region: impl Fn(usize) -> Region,
+) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut address = addresses.start;
+
+    while address < addresses.end.min(memory.len()) {
+        let line = match memory.get(address..address + mem::size_of::<Instruction>()) {
+            Some(bytes)
+                if region(address) == Region::Code && region(address + 1) == Region::Code =>
+            {
+                let instruction = Instruction::new([bytes[0], bytes[1]]);
+
+                Line {
+                    address,
+                    bytes: bytes.to_vec(),
+                    region: Region::Code,
+                    text: mnemonic(&instruction).unwrap_or_else(|| directive(bytes)),
+                }
+            }
+            _ => Line {
+                address,
+                bytes: vec![memory[address]],
+                region: Region::Data,
+                text: directive(&memory[address..address + 1]),
+            },
+        };
+
+        address += line.bytes.len();
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Returns the instructions of `lines` that jump to, call or point I to each address, in the order
+/// of the lines
+pub fn references(lines: &[Line]) -> collections::BTreeMap<usize, Vec<Reference>> {
+    let mut references: collections::BTreeMap<usize, Vec<Reference>> = collections::BTreeMap::new();
+
+    for line in lines {
+        let Some(address) = target(line) else {
+            continue;
+        };
+
+        let kind = match Instruction::new([line.bytes[0], line.bytes[1]]).operator_code() {
+            0x2 => ReferenceKind::Call,
+            0xA => ReferenceKind::Load,
+            _ => ReferenceKind::Jump,
+        };
+
+        references.entry(address).or_default().push(Reference {
+            address: line.address,
+            kind,
+        });
+    }
+
+    references
+}
+
+/// Returns whether `line` matches `query`, which is either an opcode with X, Y, N or ? standing for
+/// any digit like `DXYN`, an address like `0x300` that the line is at or refers to, or a part of
+/// its mnemonic
+pub fn matches(line: &Line, query: &str) -> bool {
+    let query = query.trim().to_ascii_uppercase();
+
+    if query.is_empty() {
+        return false;
+    }
+
+    let opcode = query.len() == mem::size_of::<Instruction>() * 2
+        && query
+            .chars()
+            .all(|digit| digit.is_ascii_hexdigit() || "XYN?".contains(digit));
+
+    if opcode {
+        let bytes: String = line
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+
+        return line.region == Region::Code
+            && query
+                .chars()
+                .zip(bytes.chars())
+                .all(|(digit, byte)| digit == byte || !digit.is_ascii_hexdigit());
+    }
+
+    if let Some(Ok(address)) = query
+        .strip_prefix("0X")
+        .map(|address| usize::from_str_radix(address, 16))
+    {
+        return line.address == address || target(line) == Some(address);
+    }
+
+    line.text.to_ascii_uppercase().contains(&query)
+}
+
+/// Writes `lines` as source for the assembler, with labels for the addresses that instructions
+/// jump to or point I to, which reassembles to the same bytes
+pub fn source(lines: &[Line]) -> String {
+    let starts: collections::BTreeSet<usize> = lines.iter().map(|line| line.address).collect();
+    let labels: collections::BTreeSet<usize> = lines
+        .iter()
+        .filter_map(target)
+        .filter(|address| starts.contains(address))
+        .collect();
+
+    let mut source = String::new();
+
+    for line in lines {
+        if labels.contains(&line.address) {
+            source.push_str(&format!("{}:\n", label(line.address)));
+        }
+
+        let text = match target(line) {
+            Some(address) if labels.contains(&address) => line
+                .text
+                .replace(&format!("0x{:03X}", address), &label(address)),
+            _ => line.text.clone(),
+        };
+
+        source.push_str(&format!("    {}\n", text));
+    }
+
+    source
+}
+
+/// Returns the address an instruction of `line` jumps to or points I to
+fn target(line: &Line) -> Option<usize> {
+    if line.region != Region::Code {
+        return None;
+    }
+
+    let instruction = Instruction::new([line.bytes[0], line.bytes[1]]);
+
+    matches!(instruction.operator_code(), 0x1 | 0x2 | 0xA | 0xB).then(|| instruction.operand_nnn())
+}
+
+#[inline]
+fn label(address: usize) -> String {
+    format!("L{:03X}", address)
+}
+
+/// Returns the mnemonic of `instruction`, `None` if it isn't recognized
+pub fn mnemonic(instruction: &Instruction) -> Option<String> {
+    let (x, y, n, nn, nnn) = (
+        instruction.operand_x(),
+        instruction.operand_y(),
+        instruction.operand_n(),
+        instruction.operand_nn(),
+        instruction.operand_nnn(),
+    );
+
+    Some(match instruction.operator_code() {
+        0x0 => match nnn {
+            0x0E0 => "CLS".to_owned(),
+            0x0EE => "RET".to_owned(),
+            0x0FD => "EXIT".to_owned(),
+            _ => format!("SYS 0x{:03X}", nnn),
+        },
+        0x1 => format!("JP 0x{:03X}", nnn),
+        0x2 => format!("CALL 0x{:03X}", nnn),
+        0x3 => format!("SE V{:X}, 0x{:02X}", x, nn),
+        0x4 => format!("SNE V{:X}, 0x{:02X}", x, nn),
+        0x5 if n == 0x0 => format!("SE V{:X}, V{:X}", x, y),
+        0x6 => format!("LD V{:X}, 0x{:02X}", x, nn),
+        0x7 => format!("ADD V{:X}, 0x{:02X}", x, nn),
+        0x8 => {
+            let operation = match n {
+                0x0 => "LD",
+                0x1 => "OR",
+                0x2 => "AND",
+                0x3 => "XOR",
+                0x4 => "ADD",
+                0x5 => "SUB",
+                0x6 => "SHR",
+                0x7 => "SUBN",
+                0xE => "SHL",
+                _ => return None,
+            };
+
+            format!("{} V{:X}, V{:X}", operation, x, y)
+        }
+        0x9 if n == 0x0 => format!("SNE V{:X}, V{:X}", x, y),
+        0xA => format!("LD I, 0x{:03X}", nnn),
+        0xB => format!("JP V0, 0x{:03X}", nnn),
+        0xC => format!("RND V{:X}, 0x{:02X}", x, nn),
+        0xD => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        0xE => match nn {
+            0x9E => format!("SKP V{:X}", x),
+            0xA1 => format!("SKNP V{:X}", x),
+            _ => return None,
+        },
+        0xF => match nn {
+            0x00 if x == 0x0 => "LD I, long".to_owned(),
+            0x07 => format!("LD V{:X}, DT", x),
+            0x0A => format!("LD V{:X}, K", x),
+            0x15 => format!("LD DT, V{:X}", x),
+            0x18 => format!("LD ST, V{:X}", x),
+            0x1E => format!("ADD I, V{:X}", x),
+            0x29 => format!("LD F, V{:X}", x),
+            0x30 => format!("LD HF, V{:X}", x),
+            0x33 => format!("LD B, V{:X}", x),
+            0x55 => format!("LD [I], V{:X}", x),
+            0x65 => format!("LD V{:X}, [I]", x),
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+fn directive(bytes: &[u8]) -> String {
+    let bytes: Vec<String> = bytes.iter().map(|byte| format!("0x{:02X}", byte)).collect();
+
+    format!("db {}", bytes.join(", "))
+}
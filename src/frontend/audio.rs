@@ -0,0 +1,170 @@
+use std::sync::{self, atomic};
+use std::time;
+
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SizedSample};
+use rodio::{dynamic_mixer, Source};
+
+use super::Sound;
+
+/// Mixer of an audio output, which sinks are played through
+pub type Mixer = dynamic_mixer::DynamicMixerController<f32>;
+
+/// Where the beeps of a program are played, so that programs run the same without an audio output
+pub trait AudioSink: Send {
+    /// Queues `sound`, played `speed` times faster than it is
+    fn play(&self, sound: &Sound, speed: f32);
+}
+
+/// Plays nothing, for when there's no audio output
+pub struct NullSink;
+
+/// An open audio output stream, which plays for as long as it's kept
+pub struct Output {
+    /// Frames of the buffer of the stream, `None` for the default of the device
+    pub buffer_size: Option<u32>,
+    /// Name of the device the stream was opened on, `None` for the default device
+    pub device: Option<String>,
+    /// Time between filling the buffer and playing it, in microseconds, as of the last time the
+    /// buffer was filled, `u64::MAX` until it's known
+    latency: sync::Arc<atomic::AtomicU64>,
+    mixer: sync::Arc<Mixer>,
+    _stream: cpal::Stream,
+}
+
+impl AudioSink for NullSink {
+    fn play(&self, _sound: &Sound, _speed: f32) {}
+}
+
+impl AudioSink for rodio::Sink {
+    fn play(&self, sound: &Sound, speed: f32) {
+        // the sound was decoded once already when it was made
+        if let Ok(decoder) = sound.decode() {
+            self.append(decoder.speed(speed));
+        }
+    }
+}
+
+impl Output {
+    /// Returns the time between the sound being queued and it being played by the device
+    pub fn latency(&self) -> Option<time::Duration> {
+        match self.latency.load(atomic::Ordering::Relaxed) {
+            u64::MAX => None,
+            latency => Some(time::Duration::from_micros(latency)),
+        }
+    }
+
+    /// Returns a handle to the mixer, which can't play anymore once the output is dropped
+    #[inline]
+    pub fn mixer(&self) -> sync::Weak<Mixer> {
+        sync::Arc::downgrade(&self.mixer)
+    }
+
+    /// Opens a stream with a buffer of `buffer_size` frames on the device named `device`, or on
+    /// the default device when it's `None` or there's no such device anymore
+    pub fn open(
+        device: Option<&str>,
+        buffer_size: Option<u32>,
+    ) -> Result<Self, rodio::StreamError> {
+        let host = cpal::default_host();
+        let found = device.and_then(|name| {
+            host.output_devices()
+                .ok()?
+                .find(|device| device.name().is_ok_and(|device| device == name))
+        });
+
+        if let Some(found) = found {
+            match Self::open_device(&found, buffer_size) {
+                Ok(mut output) => {
+                    output.device = device.map(str::to_owned);
+                    return Ok(output);
+                }
+                Err(error) => eprintln!("couldn't open the audio device, {}", error),
+            }
+        }
+
+        let default = host
+            .default_output_device()
+            .ok_or(rodio::StreamError::NoDevice)?;
+
+        Self::open_device(&default, buffer_size)
+    }
+
+    fn open_device(
+        device: &cpal::Device,
+        buffer_size: Option<u32>,
+    ) -> Result<Self, rodio::StreamError> {
+        let supported = device.default_output_config()?;
+        let mut config = supported.config();
+
+        // a size out of the range of the device is taken for the nearest one in it
+        config.buffer_size = match (buffer_size, supported.buffer_size()) {
+            (Some(size), cpal::SupportedBufferSize::Range { min, max }) => {
+                cpal::BufferSize::Fixed(size.clamp(*min, *max))
+            }
+            (Some(size), cpal::SupportedBufferSize::Unknown) => cpal::BufferSize::Fixed(size),
+            (None, _) => cpal::BufferSize::Default,
+        };
+
+        let (mixer, output) = dynamic_mixer::mixer(config.channels, config.sample_rate.0);
+        let latency = sync::Arc::new(atomic::AtomicU64::new(u64::MAX));
+
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => build::<f32>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::F64 => build::<f64>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::I8 => build::<i8>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::I16 => build::<i16>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::I32 => build::<i32>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::I64 => build::<i64>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::U8 => build::<u8>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::U16 => build::<u16>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::U32 => build::<u32>(device, &config, output, latency.clone()),
+            cpal::SampleFormat::U64 => build::<u64>(device, &config, output, latency.clone()),
+            _ => Err(cpal::BuildStreamError::StreamConfigNotSupported),
+        }?;
+
+        stream.play()?;
+
+        Ok(Self {
+            buffer_size,
+            device: None,
+            latency,
+            mixer,
+            _stream: stream,
+        })
+    }
+}
+
+/// Builds a stream playing `output` in samples of `T`, recording the latency of the stream to
+/// `latency`
+fn build<T: SizedSample + FromSample<f32>>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut output: dynamic_mixer::DynamicMixer<f32>,
+    latency: sync::Arc<atomic::AtomicU64>,
+) -> Result<cpal::Stream, cpal::BuildStreamError> {
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+            for sample in data.iter_mut() {
+                *sample = T::from_sample(output.next().unwrap_or(0.0));
+            }
+
+            let timestamp = info.timestamp();
+
+            if let Some(duration) = timestamp.playback.duration_since(&timestamp.callback) {
+                latency.store(duration.as_micros() as u64, atomic::Ordering::Relaxed);
+            }
+        },
+        |error| eprintln!("an error occurred on the audio stream, {}", error),
+        None,
+    )
+}
+
+/// Returns the names of the audio output devices
+pub fn output_devices() -> Vec<String> {
+    match cpal::default_host().output_devices() {
+        Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
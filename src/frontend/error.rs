@@ -6,12 +6,47 @@ use crate::backend;
 
 #[derive(Debug)]
 pub enum FrontendError {
-    Play(rodio::PlayError),
     Backend(backend::BackendError),
+    /// The state of the backend differs from the one of the reference after executing an instruction
+    Divergence {
+        index: usize,
+        instruction: backend::Instruction,
+        differences: Vec<String>,
+    },
+    /// The program got stuck in a loop at the address that it can't leave
+    Halted(usize),
     IO(io::Error),
+    /// The program changed neither its display, its memory nor its registers for the seconds of
+    /// emulated time, the last instruction executed being at the address
+    Stalled {
+        index: usize,
+        seconds: u16,
+    },
+    /// The program overwrote the executed code at the address
+    SelfModification(usize),
 }
 
 impl FrontendError {
+    /// Returns the code of the error, which stays the same across versions for scripts to tell
+    /// errors apart by, the codes of backend errors are the ones of their kind
+    ///
+    /// Code 20 was the one of an audio output gone, which programs are now played without.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Backend(error) => error.code(),
+            Self::Divergence { .. } => 21,
+            Self::IO(_) => 22,
+            Self::SelfModification(_) => 23,
+            Self::Halted(_) => 24,
+            Self::Stalled { .. } => 25,
+        }
+    }
+
+    /// Returns whether the program ended itself, which stops it without being a failure
+    pub fn is_exit(&self) -> bool {
+        matches!(self, Self::Backend(error) if matches!(error.kind, backend::BackendErrorKind::ProgramExited))
+    }
+
     pub fn is_fatal(&self) -> bool {
         match self {
             Self::Backend(error) => matches!(
@@ -20,18 +55,52 @@ impl FrontendError {
                     | backend::BackendErrorKind::ProgramInvalid
                     | backend::BackendErrorKind::ProgramNotLoaded
             ),
+            Self::Halted(_) | Self::SelfModification(_) | Self::Stalled { .. } => false,
             _ => true,
         }
     }
+
+    /// Returns the error as a JSON object with its code and its message
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+        })
+        .to_string()
+    }
 }
 
 impl fmt::Display for FrontendError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Play(error) => write!(f, "{}", error),
-            Self::Backend(error) => write!(f, "{}", error),
+            // the code is already part of the message of the backend error
+            Self::Backend(error) => return write!(f, "{}", error),
+            Self::Divergence {
+                index,
+                instruction,
+                differences,
+            } => write!(
+                f,
+                "diverged from the reference after instruction {} at 0x{:03x}, {}",
+                instruction,
+                index,
+                differences.join(", ")
+            ),
+            Self::Halted(address) => {
+                write!(f, "the program halted in a loop at 0x{:03x}", address)
+            }
             Self::IO(error) => write!(f, "{}", error),
-        }
+            Self::SelfModification(address) => {
+                write!(f, "the program overwrote its code at 0x{:03x}", address)
+            }
+            Self::Stalled { index, seconds } => write!(
+                f,
+                "the program changed nothing for {} seconds at 0x{:03x}, it may be stuck",
+                seconds, index
+            ),
+        }?;
+
+        write!(f, " (error {})", self.code())
     }
 }
 
@@ -0,0 +1,60 @@
+use std::iter;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend;
+
+/// Times the display is enlarged with the nearest pixels before it's filtered bilinearly
+const PRESCALE_FACTOR: usize = 4;
+
+/// How the display is filtered when it's scaled to the window
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingFilter {
+    /// Sharp square pixels
+    #[default]
+    Nearest,
+    /// Smooth, blurred pixels
+    Bilinear,
+    /// Enlarged with the nearest pixels then filtered bilinearly, for square pixels with soft
+    /// edges at scales that aren't whole numbers
+    Prescaled,
+}
+
+impl ScalingFilter {
+    pub const ALL: [Self; 3] = [Self::Nearest, Self::Bilinear, Self::Prescaled];
+
+    /// Returns the image of the display to upload to its texture, from its `pixels`, row by row
+    pub fn image(self, pixels: &[egui::Color32]) -> egui::ColorImage {
+        let size = [
+            backend::DISPLAY_BUFFER_WIDTH,
+            backend::DISPLAY_BUFFER_HEIGHT,
+        ];
+
+        match self {
+            Self::Nearest | Self::Bilinear => egui::ColorImage {
+                size,
+                pixels: pixels.to_vec(),
+            },
+            Self::Prescaled => egui::ColorImage {
+                size: size.map(|length| length * PRESCALE_FACTOR),
+                pixels: pixels
+                    .chunks(backend::DISPLAY_BUFFER_WIDTH)
+                    .flat_map(|row| {
+                        let row = row.iter().flat_map(|pixel| [*pixel; PRESCALE_FACTOR]);
+
+                        iter::repeat_n(row, PRESCALE_FACTOR).flatten()
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    /// Returns the options of the display texture
+    pub fn options(self) -> egui::TextureOptions {
+        match self {
+            Self::Nearest => egui::TextureOptions::NEAREST,
+            Self::Bilinear | Self::Prescaled => egui::TextureOptions::LINEAR,
+        }
+    }
+}
@@ -1,16 +1,23 @@
-use std::sync::{self, mpsc};
+use std::collections;
+use std::sync::{self, atomic, mpsc};
 use std::thread;
+use std::time;
 
-use crate::backend::interfaces;
-
-const MESSAGE_BUFFER_SIZE: usize = 8;
+/// Bytes of console output kept for the user interface at most, the oldest ones are dropped past it
+const CONSOLE_BUFFER_SIZE: usize = 65536;
+/// Messages kept for the user interface at most, the oldest ones are dropped past it
+const MESSAGE_BUFFER_SIZE: usize = 256;
+/// Ticks run in a single frame at most in single-threaded mode, so that a slow frame isn't followed by a burst of ticks
+const MAX_TICKS_PER_FRAME: u32 = 4;
 
 pub struct FrontendHandle {
-    command_handle: sync::Arc<(sync::Mutex<Command>, sync::Condvar)>,
     frontend: Option<super::Frontend>,
+    handles: Handles,
+    /// The frontend and its runner while it's started in single-threaded mode
+    inline: Option<Inline>,
     join_handle: Option<thread::JoinHandle<super::Frontend>>,
-    keyboard_handle: sync::Arc<sync::Mutex<interfaces::KeyboardState>>,
-    receiver: Option<mpsc::Receiver<super::Message>>,
+    /// Where the changes of the keys go while the frontend is started
+    keys: Option<mpsc::Sender<super::KeyEvent>>,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -20,14 +27,127 @@ pub(super) enum Command {
     Stop,
 }
 
+/// The last command given to the frontend thread, and what the thread made of it
+pub(super) struct Control {
+    pub command: Command,
+    pub status: Status,
+}
+
+/// What the frontend thread is doing, updated by the thread as it observes commands so that the
+/// handle can wait for a command to take effect
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Status {
+    Running,
+    Parked,
+    Finished,
+}
+
+/// State shared between the handle and the frontend while it runs
+#[derive(Clone)]
+pub(super) struct Handles {
+    /// Colors of the display and whether the fade effect is on, until the frontend applies them
+    pub appearance: sync::Arc<sync::Mutex<Option<(super::Colors, bool)>>>,
+    /// Whether the window is in the background and the frontend should be throttled
+    pub background: sync::Arc<atomic::AtomicBool>,
+    /// Whether the sound timer of the running program is active
+    pub beeping: sync::Arc<atomic::AtomicBool>,
+    /// Bytes the program printed to the console, until the user interface takes them
+    pub console: sync::Arc<sync::Mutex<Vec<u8>>>,
+    pub control: sync::Arc<(sync::Mutex<Control>, sync::Condvar)>,
+    pub continuing: sync::Arc<atomic::AtomicBool>,
+    /// Hash of the display, along with the tick it first looked that way in, while the hash overlay
+    /// is shown
+    pub display_hash: sync::Arc<sync::Mutex<Option<(u64, u64)>>>,
+    /// Time between the last key press and the program first finding the key held
+    pub latency: sync::Arc<sync::Mutex<Option<time::Duration>>>,
+    /// Messages kept across runs of the frontend, until the user interface takes them
+    pub mailbox: sync::Arc<sync::Mutex<Mailbox>>,
+    pub turbo: sync::Arc<atomic::AtomicBool>,
+}
+
+impl Handles {
+    /// Leaves `message` for the user interface to take
+    pub fn send(&self, message: super::Message) {
+        let mut mailbox = self.mailbox.lock().unwrap();
+
+        if mailbox.messages.len() == MESSAGE_BUFFER_SIZE {
+            mailbox.messages.pop_front();
+            mailbox.dropped += 1;
+        }
+
+        mailbox.messages.push_back(message);
+    }
+
+    /// Keeps the bytes the program printed to the console for the user interface to take
+    pub fn print(&self, bytes: &[u8]) {
+        let mut console = self.console.lock().unwrap();
+        console.extend_from_slice(bytes);
+
+        if console.len() > CONSOLE_BUFFER_SIZE {
+            let excess = console.len() - CONSOLE_BUFFER_SIZE;
+            console.drain(..excess);
+        }
+    }
+}
+
+/// Messages of the frontend waiting for the user interface
+///
+/// The frontend never waits on the user interface to take them, which would deadlock in
+/// single-threaded mode, the oldest ones are dropped instead and counted for the user interface to
+/// report.
+#[derive(Default)]
+pub(super) struct Mailbox {
+    /// Messages dropped since the count was last taken
+    dropped: usize,
+    messages: collections::VecDeque<super::Message>,
+}
+
+struct Inline {
+    frontend: super::Frontend,
+    next_tick: time::Instant,
+    /// `None` once the run is over, like a thread that returned
+    runner: Option<super::Runner>,
+}
+
 impl FrontendHandle {
+    /// Resumes the frontend thread, returning once the thread acknowledged it
     pub fn resume(&mut self) {
         if !self.suspended() {
             panic!("attempt to resume the frontend thread while it's not suspended");
         }
 
-        *self.command_handle.0.lock().unwrap() = Command::None;
-        self.command_handle.1.notify_one();
+        let control = self.command(Command::None);
+
+        match self.inline.as_ref() {
+            Some(inline) => inline.frontend.context.request_repaint(),
+            // a suspension given right after would otherwise be acknowledged by the previous parking
+            None => drop(
+                self.handles
+                    .control
+                    .1
+                    .wait_while(control, |control| control.status == Status::Parked)
+                    .unwrap(),
+            ),
+        }
+    }
+
+    /// Changes the colors of the display and whether the pixels turned off fade out, whether the
+    /// frontend is started or not
+    pub fn set_appearance(&mut self, colors: super::Colors, fade_effect: bool) {
+        let frontend = match self.inline.as_mut() {
+            Some(inline) => Some(&mut inline.frontend),
+            None => self.frontend.as_mut(),
+        };
+
+        if let Some(frontend) = frontend {
+            return frontend.set_appearance(colors, fade_effect);
+        }
+
+        // a parked frontend thread would only apply them once resumed
+        match self.suspended() {
+            true => self.with_stopped(|frontend| frontend.set_appearance(colors, fade_effect)),
+            false => *self.handles.appearance.lock().unwrap() = Some((colors, fade_effect)),
+        }
     }
 
     pub fn start(&mut self) {
@@ -35,17 +155,29 @@ impl FrontendHandle {
             panic!("attempt to start the already started frontend thread");
         }
 
-        let frontend = self.frontend.take().unwrap();
-        let command_handle = sync::Arc::clone(&self.command_handle);
-        let keyboard_handle = sync::Arc::clone(&self.keyboard_handle);
+        let mut frontend = self.frontend.take().unwrap();
+        let handles = self.handles.clone();
+
+        self.handles.control.0.lock().unwrap().status = Status::Running;
+
+        // the user interface never waits on the frontend to take a key
+        let (key_sender, keys) = mpsc::channel();
 
-        let (sender, receiver) = mpsc::sync_channel(MESSAGE_BUFFER_SIZE);
+        let _ = self.keys.insert(key_sender);
 
-        let _ = self.receiver.insert(receiver);
+        if frontend.config().single_threaded {
+            let _ = self.inline.insert(Inline {
+                runner: Some(frontend.runner(keys)),
+                frontend,
+                next_tick: time::Instant::now(),
+            });
+
+            return;
+        }
 
-        let _ = self.join_handle.insert(thread::spawn(|| {
-            frontend.run(command_handle, keyboard_handle, sender)
-        }));
+        let _ = self
+            .join_handle
+            .insert(thread::spawn(|| frontend.run(handles, keys)));
     }
 
     pub fn stop(&mut self) -> &mut super::Frontend {
@@ -53,19 +185,93 @@ impl FrontendHandle {
             panic!("attempt to stop the already stopped frontend thread");
         }
 
-        *self.command_handle.0.lock().unwrap() = Command::Stop;
-        self.command_handle.1.notify_one();
+        let frontend = match self.inline.take() {
+            Some(mut inline) => {
+                // like a thread that returned, unless the run is over and kept them already
+                if let Some(runner) = inline.runner {
+                    inline.frontend.checks = Some(runner.checks);
+                }
 
-        let join_handle = self.join_handle.take().unwrap();
-        let frontend = self.frontend.insert(join_handle.join().unwrap());
+                inline.frontend
+            }
+            None => {
+                drop(self.command(Command::Stop));
 
-        self.receiver.take();
+                self.join_handle.take().unwrap().join().unwrap()
+            }
+        };
 
-        *self.command_handle.0.lock().unwrap() = Command::None;
+        let frontend = self.frontend.insert(frontend);
+
+        self.keys.take();
+        self.handles.beeping.store(false, atomic::Ordering::Relaxed);
+
+        self.handles.control.0.lock().unwrap().command = Command::None;
 
         frontend
     }
 
+    /// Runs the ticks that are due when started in single-threaded mode, to be called every frame
+    pub fn update(&mut self) {
+        if self.suspended() {
+            return;
+        }
+
+        let Some(inline) = self.inline.as_mut() else {
+            return;
+        };
+
+        for _ in 0..MAX_TICKS_PER_FRAME {
+            let Some(runner) = inline.runner.as_mut() else {
+                return;
+            };
+
+            let suspended = self.handles.control.0.lock().unwrap().command == Command::Suspend;
+
+            if inline.next_tick > time::Instant::now() || suspended {
+                break;
+            }
+
+            match inline.frontend.tick(runner, &self.handles) {
+                super::Flow::Stop => {
+                    inline.frontend.checks = inline.runner.take().map(|runner| runner.checks)
+                }
+                super::Flow::Idle | super::Flow::Wait => {
+                    inline.next_tick += super::interval(&self.handles)
+                }
+                super::Flow::Next => {}
+            }
+        }
+
+        if inline.runner.is_some() {
+            let now = time::Instant::now();
+
+            // fall behind instead of catching up with the ticks that didn't fit in the frame
+            inline.next_tick = inline.next_tick.max(now);
+
+            inline
+                .frontend
+                .context
+                .request_repaint_after(inline.next_tick - now);
+        }
+    }
+
+    /// Stops the frontend thread, passes the frontend to `f` and restarts the thread the way it was,
+    /// the checks of the run going on unless `f` changed the state or the configuration
+    pub fn with_stopped<T>(&mut self, f: impl FnOnce(&mut super::Frontend) -> T) -> T {
+        let suspended = self.suspended();
+        let result = f(self.stop());
+
+        if suspended {
+            self.handles.control.0.lock().unwrap().command = Command::Suspend;
+        }
+
+        self.start();
+
+        result
+    }
+
+    /// Suspends the frontend thread, returning once the thread parked
     pub fn suspend(&mut self) {
         if !self.started() {
             panic!("attempt to suspend the frontend thread while it not started");
@@ -75,39 +281,150 @@ impl FrontendHandle {
             panic!("attempt to suspend the already suspended frontend thread");
         }
 
-        *self.command_handle.0.lock().unwrap() = Command::Suspend;
+        let control = self.command(Command::Suspend);
+
+        // the frontend has to be parked for its state to be inspected, unless it runs in this thread
+        if self.inline.is_none() {
+            drop(
+                self.handles
+                    .control
+                    .1
+                    .wait_while(control, |control| control.status == Status::Running)
+                    .unwrap(),
+            );
+        }
+    }
+
+    /// Gives `command` to the frontend thread, returning the lock to wait for its acknowledgement with
+    fn command(&self, command: Command) -> sync::MutexGuard<'_, Control> {
+        let mut control = self.handles.control.0.lock().unwrap();
+        control.command = command;
+        self.handles.control.1.notify_all();
+
+        control
     }
 }
 
 impl FrontendHandle {
+    /// Returns whether the sound timer of the running program is active
+    #[inline]
+    pub fn beeping(&self) -> bool {
+        self.handles.beeping.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Takes the bytes the program printed to the console since they were last taken
+    #[inline]
+    pub fn take_console(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.handles.console.lock().unwrap())
+    }
+
+    /// Returns whether the frontend thread is running to the next breakpoint in debug mode
+    #[inline]
+    pub fn continuing(&self) -> bool {
+        self.handles.continuing.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Returns the hash of the display and the tick it first looked that way in, while the hash
+    /// overlay is shown
+    #[inline]
+    pub fn display_hash(&self) -> Option<(u64, u64)> {
+        *self.handles.display_hash.lock().unwrap()
+    }
+
     #[inline]
     pub fn get(&mut self) -> Option<&mut super::Frontend> {
         self.frontend.as_mut()
     }
 
+    /// Returns the time between the last key press and the program first finding the key held
     #[inline]
-    pub fn keyboard_state<'a>(&'a mut self) -> sync::MutexGuard<'a, interfaces::KeyboardState> {
-        self.keyboard_handle.lock().unwrap()
+    pub fn input_latency(&self) -> Option<time::Duration> {
+        *self.handles.latency.lock().unwrap()
     }
 
+    /// Takes every message of the frontend, oldest first, messages are kept while it's stopped
     #[inline]
-    pub fn message(&self) -> Option<super::Message> {
-        self.receiver
-            .as_ref()
-            .and_then(|receiver| receiver.try_recv().ok())
+    pub fn messages(&self) -> Vec<super::Message> {
+        self.handles
+            .mailbox
+            .lock()
+            .unwrap()
+            .messages
+            .drain(..)
+            .collect()
+    }
+
+    /// Takes the number of messages dropped since it was last taken, because they weren't taken in
+    /// time
+    #[inline]
+    pub fn take_dropped_messages(&self) -> usize {
+        std::mem::take(&mut self.handles.mailbox.lock().unwrap().dropped)
     }
 
     #[inline]
     pub fn new(frontend: super::Frontend) -> Self {
         Self {
-            command_handle: (sync::Mutex::new(Command::None), sync::Condvar::new()).into(),
             frontend: Some(frontend),
+            handles: Handles {
+                appearance: sync::Arc::new(sync::Mutex::new(None)),
+                background: sync::Arc::new(atomic::AtomicBool::new(false)),
+                beeping: sync::Arc::new(atomic::AtomicBool::new(false)),
+                console: sync::Arc::new(sync::Mutex::new(Vec::new())),
+                control: (
+                    sync::Mutex::new(Control {
+                        command: Command::None,
+                        status: Status::Finished,
+                    }),
+                    sync::Condvar::new(),
+                )
+                    .into(),
+                continuing: sync::Arc::new(atomic::AtomicBool::new(false)),
+                display_hash: sync::Arc::new(sync::Mutex::new(None)),
+                latency: sync::Arc::new(sync::Mutex::new(None)),
+                mailbox: sync::Arc::new(sync::Mutex::new(Mailbox::default())),
+                turbo: sync::Arc::new(atomic::AtomicBool::new(false)),
+            },
+            inline: None,
             join_handle: None,
-            keyboard_handle: sync::Arc::new(sync::Mutex::new(interfaces::KeyboardState::new())),
-            receiver: None,
+            keys: None,
+        }
+    }
+
+    /// Forwards a change of a keypad key to the frontend, for its next tick to see
+    #[inline]
+    pub fn send_key(&self, key: usize, pressed: bool) {
+        if let Some(keys) = self.keys.as_ref() {
+            // the frontend thread may have returned already
+            let _ = keys.send(super::KeyEvent {
+                key,
+                pressed,
+                timestamp: time::Instant::now(),
+            });
         }
     }
 
+    /// Throttles the frontend while `background` is set, for when the window isn't looked at
+    #[inline]
+    pub fn set_background(&self, background: bool) {
+        self.handles
+            .background
+            .store(background, atomic::Ordering::Relaxed);
+    }
+
+    /// Runs the frontend thread in debug mode until a breakpoint or a watchpoint is hit while `continuing` is set
+    #[inline]
+    pub fn set_continuing(&self, continuing: bool) {
+        self.handles
+            .continuing
+            .store(continuing, atomic::Ordering::Relaxed);
+    }
+
+    /// Runs the frontend thread faster than real time while `turbo` is set
+    #[inline]
+    pub fn set_turbo(&self, turbo: bool) {
+        self.handles.turbo.store(turbo, atomic::Ordering::Relaxed);
+    }
+
     #[inline]
     pub fn started(&self) -> bool {
         self.frontend.is_none()
@@ -115,6 +432,6 @@ impl FrontendHandle {
 
     #[inline]
     pub fn suspended(&self) -> bool {
-        *self.command_handle.0.lock().unwrap() == Command::Suspend
+        self.handles.control.0.lock().unwrap().command == Command::Suspend
     }
 }
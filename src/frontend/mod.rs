@@ -1,50 +1,216 @@
+use std::collections;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::mem;
 use std::num;
-use std::sync::{self, mpsc};
+use std::sync::{self, atomic, mpsc};
 use std::thread;
 use std::time;
 
-use crate::backend::{self, interfaces};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{self, interfaces, stall};
+use crate::config;
 use crate::defaults;
 
+pub mod audio;
 mod error;
+mod filter;
 mod handle;
+mod octo;
 mod sound;
+mod verifier;
 
 pub use error::FrontendError;
+pub use filter::ScalingFilter;
 pub use handle::FrontendHandle;
+pub use octo::OctoError;
 pub use sound::Sound;
 
+/// Called after every tick a program got through with the display and the state of the machine,
+/// for embedders to observe the emulation without running it themselves
+pub type FrameHook = Box<dyn FnMut(&interfaces::DisplayBuffer, &backend::Backend) + Send>;
+
 pub type Message = Result<String, FrontendError>;
 
+/// How many times slower the program runs while the window is in the background
+const BACKGROUND_FACTOR: u32 = 4;
 const FADE_DURATION: time::Duration = time::Duration::from_millis(1000 / 60 * 2);
-const INSTRUCTIONS_PER_TICK: u16 = 18;
+/// Ticks whose states a halted program is looked for coming back to, enough for the loops
+/// shorter than a tick to line up with the ticks again
+const HALT_WINDOW: usize = 8;
 const TICK_INTERVAL: time::Duration = time::Duration::from_millis(1000 / 60);
-
-#[derive(Clone, Copy)]
+/// Ticks kept in the input timeline
+const TIMELINE_LENGTH: usize = 600;
+/// Number of the last collisions kept in debug mode
+const COLLISION_LOG_LENGTH: usize = 256;
+/// Ticks between the snapshots taken in debug mode, which the debugger rewinds to
+const SNAPSHOT_INTERVAL: u64 = 60;
+const TURBO_FACTOR: u32 = 4;
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Serialize)]
 pub struct Colors {
     pub active: egui::Color32,
     pub inactive: egui::Color32,
 }
 
+/// The state of a frontend saved by `Frontend::snapshot`
+#[derive(Clone)]
+pub struct Snapshot {
+    backend: backend::Backend,
+    display_buffer: interfaces::DisplayBuffer,
+}
+
+impl Snapshot {
+    #[inline]
+    pub fn backend(&self) -> &backend::Backend {
+        &self.backend
+    }
+
+    #[inline]
+    pub fn display_buffer(&self) -> &interfaces::DisplayBuffer {
+        &self.display_buffer
+    }
+}
+
+bitflags::bitflags! {
+    /// What a program did during a tick, for the event track of the debugger
+    #[derive(Default)]
+    pub struct TickEvents: u8 {
+        /// Drew a sprite
+        const DRAW = 0b0001;
+        /// Set the sound timer
+        const BEEP = 0b0010;
+        /// Checked or waited for a key
+        const KEY_READ = 0b0100;
+        /// Stopped with an error
+        const ERROR = 0b1000;
+    }
+}
+
+/// A change of a keypad key, forwarded by the user interface as soon as it sees it
+pub struct KeyEvent {
+    pub key: usize,
+    pub pressed: bool,
+    pub timestamp: time::Instant,
+}
+
+/// A collision of a sprite in debug mode, for the collision log of the debugger
+#[derive(Clone)]
+pub struct CollisionEvent {
+    pub collision: interfaces::Collision,
+    /// Tick the sprite was drawn in, counted like the ones of the timeline
+    pub frame: u64,
+}
+
+/// The keys held, the timers and the events of a tick in debug mode, for the input timeline, the
+/// timer plot and the event track of the debugger
+#[derive(Clone, Copy)]
+pub struct TimelineFrame {
+    /// Delay timer at the end of the tick
+    pub delay: u8,
+    pub events: TickEvents,
+    /// Ticks executed before this one since the program was started
+    pub frame: u64,
+    /// Address of the last instruction executed in the tick
+    pub index: usize,
+    /// Bit mask of the held keys
+    pub keys: u16,
+    /// Sound timer at the end of the tick
+    pub sound: u8,
+}
+
+/// What a run of the frontend does after a tick
+enum Flow {
+    /// Waits for the next tick interval, or less when given a command, nothing is playing nor
+    /// changing on the display
+    Idle,
+    /// Waits for the next tick interval
+    Wait,
+    /// Executes the next tick right away
+    Next,
+    /// Stops, after reporting a fatal error or an error in debug mode
+    Stop,
+}
+
 pub struct Frontend {
-    pub backend: backend::Backend,
-    pub colors: Colors,
+    /// Checks of the last run, for the next one to go on with
+    checks: Option<Checks>,
+    /// The last collisions in debug mode, oldest first
+    collisions: collections::VecDeque<CollisionEvent>,
+    pub core: Box<dyn backend::Core>,
+    config: config::EmulatorConfig,
     context: egui::Context,
+    /// Plays the program instead of the user while it's set
+    controller: Option<Box<dyn backend::controller::Controller>>,
     display_buffer: interfaces::DisplayBuffer,
     display_texture: egui::TextureHandle,
-    pub options: Options,
+    frame_hook: Option<FrameHook>,
+    /// Keys held as of the last key event, kept while the frontend is stopped and started again to
+    /// be inspected
+    keyboard_state: interfaces::KeyboardState,
+    /// Mixer of the audio output the sound is played on
+    mixer: sync::Weak<audio::Mixer>,
+    /// Pixels last set to the display texture, to leave it and the screen alone when they're the same
+    pixels: Vec<egui::Color32>,
+    /// Snapshots taken every `SNAPSHOT_INTERVAL` ticks of the timeline in debug mode, oldest
+    /// first, each with the tick it was taken before
+    snapshots: collections::VecDeque<(u64, Snapshot)>,
     sound: Sound,
-    stream: rodio::OutputStreamHandle,
+    /// Ticks executed since the program was loaded or reset
+    ticks: u64,
+    /// The last ticks executed in debug mode, oldest first
+    timeline: collections::VecDeque<TimelineFrame>,
+    /// Addresses whose contents changing stops a debugger run
+    watchpoints: Vec<usize>,
+}
+
+/// What a run of the frontend checks the program with, kept while the frontend is stopped and
+/// started again to be inspected, until the state or the configuration is changed
+struct Checks {
+    reference: Option<verifier::Reference>,
+    /// Finds when the program stops changing anything, to warn that it may be stuck
+    stall: stall::StallDetector,
+    /// Whether the program was already warned about overwriting its code, which it's only warned
+    /// about once
+    warned: bool,
 }
 
-#[derive(Default)]
-pub struct Options {
-    pub debug_mode: bool,
-    pub fade_effect: bool,
-    pub wrap_sprites: bool,
+/// What a run of the frontend keeps between ticks
+struct Runner {
+    /// Value the sound timer was last set to, the length of the current beep in ticks
+    beep_length: u8,
+    checks: Checks,
+    /// Fingerprints of the states at the end of the last ticks without events, oldest first, to
+    /// find when the program is halted
+    halt_states: collections::VecDeque<u64>,
+    keys: mpsc::Receiver<KeyEvent>,
+    /// When each held key was pressed, until a program first finds it held
+    pressed_at: [Option<time::Instant>; backend::KEY_COUNT],
+    sink: Box<dyn audio::AudioSink>,
+    /// Sound timer at the start of the last tick, to find when the program sets it
+    sound_timer: u8,
 }
 
 impl Colors {
+    /// Returns the color of a pixel turned off `elapsed` ago with the fade effect, until it's done
+    /// fading
+    pub fn fade(&self, elapsed: time::Duration) -> Option<egui::Color32> {
+        if elapsed >= FADE_DURATION {
+            return None;
+        }
+
+        Some(fade(
+            self.active,
+            self.inactive,
+            match elapsed.as_secs_f32() / FADE_DURATION.as_secs_f32() {
+                x if x < 0.5 => 4.0,
+                x if x < 0.75 => 2.0,
+                _ => 1.3,
+            },
+        ))
+    }
+
     fn get(&self, pixel: bool) -> egui::Color32 {
         match pixel {
             true => self.active,
@@ -54,132 +220,685 @@ impl Colors {
 }
 
 impl Frontend {
+    #[inline]
+    pub fn config(&self) -> &config::EmulatorConfig {
+        &self.config
+    }
+
+    /// Returns the pixels of the display as they are, without colors, scaling or effects
+    #[inline]
+    pub fn display_image(&self) -> interfaces::DisplayImage {
+        self.display_buffer.to_image()
+    }
+
     #[inline]
     pub fn display_texture(&self) -> egui::TextureId {
         self.display_texture.id()
     }
 
+    /// Keeps the program running when it's found halted, until it's started again
+    #[inline]
+    pub fn ignore_halts(&mut self) {
+        self.config.pause_on_halt = false;
+    }
+
     #[inline]
-    pub fn new(ctx: &egui::Context, options: Options, stream: rodio::OutputStreamHandle) -> Self {
+    pub fn new(
+        ctx: &egui::Context,
+        config: config::EmulatorConfig,
+        mixer: sync::Weak<audio::Mixer>,
+    ) -> Self {
+        let mut state = backend::Backend::new();
+        state.debug_break = config.debug_break.filter(|_| config.debug_mode);
+        state.peripherals = config.peripherals;
+        state.seed = config.seed;
+        state.quirks = config.quirks;
+
+        let mut display_buffer = backend::interfaces::DisplayBuffer::new(config.fade_effect);
+        display_buffer.track_draws = config.debug_mode;
+
         Self {
-            colors: defaults::COLORS,
+            checks: None,
+            collisions: collections::VecDeque::with_capacity(COLLISION_LOG_LENGTH),
             context: ctx.clone(),
-            backend: backend::Backend::new(),
-            display_buffer: backend::interfaces::DisplayBuffer::new(interfaces::Options {
-                track_changes: options.fade_effect,
-                wrap_sprites: options.wrap_sprites,
-            }),
+            controller: None,
+            core: config.core.create(state),
+            display_buffer,
             display_texture: ctx.load_texture(
                 "Display Texture",
-                egui::ColorImage::new(
-                    [
-                        backend::DISPLAY_BUFFER_WIDTH,
-                        backend::DISPLAY_BUFFER_HEIGHT,
-                    ],
-                    defaults::COLORS.inactive,
-                ),
-                egui::TextureOptions::default(),
+                config.scaling_filter.image(&vec![
+                    defaults::COLORS.inactive;
+                    backend::DISPLAY_BUFFER_WIDTH
+                        * backend::DISPLAY_BUFFER_HEIGHT
+                ]),
+                config.scaling_filter.options(),
             ),
-            options,
+            frame_hook: None,
+            keyboard_state: interfaces::KeyboardState::new(config.key_priority),
+            config,
+            mixer,
+            pixels: Vec::new(),
+            snapshots: collections::VecDeque::new(),
             sound: Sound::new().unwrap(),
-            stream,
+            ticks: 0,
+            timeline: collections::VecDeque::with_capacity(TIMELINE_LENGTH),
+            watchpoints: Vec::new(),
         }
     }
 
     pub fn reset(&mut self) {
-        self.backend.reset();
+        self.core.reset();
+        self.checks = None;
         self.display_buffer.clear();
+        // the key events sent while stopped never came
+        self.keyboard_state = interfaces::KeyboardState::new(self.config.key_priority);
+        self.collisions.clear();
+        self.snapshots.clear();
+        self.ticks = 0;
+        self.timeline.clear();
     }
 
-    pub(self) fn run(
-        mut self,
-        command_handle: sync::Arc<(sync::Mutex<handle::Command>, sync::Condvar)>,
-        keyboard_handle: sync::Arc<sync::Mutex<interfaces::KeyboardState>>,
-        sender: mpsc::SyncSender<Message>,
-    ) -> Self {
-        let n = num::NonZeroU16::new(match self.options.debug_mode {
-            true => 1,
-            false => INSTRUCTIONS_PER_TICK,
-        })
-        .unwrap();
-
-        let sink = match rodio::Sink::try_new(&self.stream) {
-            Ok(sink) => sink,
-            Err(error) => {
-                let error = FrontendError::Play(error);
-                sender
-                    .send(Err(error))
-                    .expect("receiver dropped before the frontend thread is stopped");
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let mut state = snapshot.backend.clone();
+        state.breakpoints = mem::take(&mut self.core.state_mut().breakpoints);
+        state.frozen = self.core.state().frozen;
+
+        self.core.restore(state);
+        self.checks = None;
+        self.display_buffer = snapshot.display_buffer.clone();
+        self.display_buffer.track_changes = self.config.fade_effect;
+        self.display_buffer.track_draws = self.config.debug_mode;
+        // the ticks recorded lead to a state that's gone
+        self.collisions.clear();
+        self.snapshots.clear();
+        self.timeline.clear();
+
+        self.update_texture();
+    }
 
-                return self;
-            }
-        };
+    /// Restores the snapshot taken in debug mode nearest to `frame`, dropping what was recorded after
+    /// it, and returns the tick it was taken before
+    pub fn rewind(&mut self, frame: u64) -> Option<u64> {
+        let mut snapshots = mem::take(&mut self.snapshots);
+        let position = snapshots
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (taken, _))| taken.abs_diff(frame))?
+            .0;
+
+        // the snapshot restored is taken again when its tick is executed again
+        let (taken, snapshot) = snapshots.drain(position..).next()?;
+        let timeline = mem::take(&mut self.timeline);
+        let collisions = mem::take(&mut self.collisions);
+
+        self.restore(&snapshot);
+        self.snapshots = snapshots;
+        self.timeline = timeline
+            .into_iter()
+            .filter(|frame| frame.frame < taken)
+            .collect();
+        self.collisions = collisions
+            .into_iter()
+            .filter(|event| event.frame < taken)
+            .collect();
+
+        Some(taken)
+    }
 
-        loop {
-            let command = command_handle.0.lock().unwrap();
+    /// Returns the display as it is, without any effects applied
+    pub fn screenshot(&self) -> egui::ColorImage {
+        egui::ColorImage {
+            size: [
+                backend::DISPLAY_BUFFER_WIDTH,
+                backend::DISPLAY_BUFFER_HEIGHT,
+            ],
+            pixels: self
+                .display_buffer
+                .buffer
+                .iter()
+                .flat_map(|row| row.iter().map(|pixel| self.config.colors.get(*pixel)))
+                .collect(),
+        }
+    }
 
-            match *command {
-                handle::Command::None => drop(command),
+    #[inline]
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            backend: self.core.state().clone(),
+            display_buffer: self.display_buffer.clone(),
+        }
+    }
+
+    /// Changes the colors of the display and whether the pixels turned off fade out, redrawing the
+    /// display with them right away
+    pub fn set_appearance(&mut self, colors: Colors, fade_effect: bool) {
+        self.config.colors = colors;
+        self.config.fade_effect = fade_effect;
+        self.display_buffer.track_changes = fade_effect;
+
+        if !fade_effect {
+            self.display_buffer.changed.clear();
+        }
+
+        self.update_texture();
+    }
+
+    /// Sets the addresses at which a debugger run stops, and the ones whose contents changing stops it
+    pub fn set_breakpoints(
+        &mut self,
+        breakpoints: collections::BTreeSet<usize>,
+        watchpoints: Vec<usize>,
+    ) {
+        self.core.state_mut().breakpoints = breakpoints;
+        self.watchpoints = watchpoints;
+    }
+
+    /// Sets what holds the keys instead of the user, or gives them back to the user when it's `None`
+    #[inline]
+    pub fn set_controller(&mut self, controller: Option<Box<dyn backend::controller::Controller>>) {
+        self.controller = controller;
+    }
+
+    /// Replaces the font in the memory of the loaded program, with the built-in one when it's `None`
+    pub fn set_font(&mut self, font: Option<&[u8; backend::FONT_SIZE]>) {
+        let mut state = self.core.state().clone();
+        state.set_font(font);
+
+        // restoring drops whatever the core derived from the replaced bytes
+        self.core.restore(state);
+        self.checks = None;
+    }
+
+    /// Sets the hook called after every tick, or removes it when it's `None`
+    #[inline]
+    pub fn set_frame_hook(&mut self, hook: Option<FrameHook>) {
+        self.frame_hook = hook;
+    }
+
+    /// Sets the registers pinned to a value
+    #[inline]
+    pub fn set_frozen(&mut self, frozen: backend::FrozenRegisters) {
+        // the reference would tell the registers that were pinned since apart
+        if frozen != self.core.state().frozen {
+            self.checks = None;
+        }
+
+        self.core.state_mut().frozen = frozen;
+    }
+
+    /// Sets the mixer of the audio output the sound is played on from the next start
+    #[inline]
+    pub fn set_mixer(&mut self, mixer: sync::Weak<audio::Mixer>) {
+        self.mixer = mixer;
+    }
+
+    /// Sets the sound played while the sound timer is active from the next start
+    #[inline]
+    pub fn set_sound(&mut self, sound: Sound) {
+        self.sound = sound;
+    }
+
+    pub fn set_config(&mut self, config: config::EmulatorConfig) {
+        if config.core != self.config.core {
+            self.core = config.core.create(self.core.state().clone());
+        }
+
+        let state = self.core.state_mut();
+        state.debug_break = config.debug_break.filter(|_| config.debug_mode);
+        state.peripherals = config.peripherals;
+        state.seed = config.seed;
+        state.quirks = config.quirks;
+        self.checks = None;
+        self.display_buffer.track_changes = config.fade_effect;
+        self.display_buffer.track_draws = config.debug_mode;
+        self.keyboard_state.priority = config.key_priority;
+
+        // the display is uploaded again with the other filter even if it looks the same
+        if config.scaling_filter != self.config.scaling_filter {
+            self.pixels.clear();
+        }
+
+        self.config = config;
+    }
+
+    /// Returns the last collisions of sprites in debug mode, oldest first
+    #[inline]
+    pub fn collisions(&self) -> &collections::VecDeque<CollisionEvent> {
+        &self.collisions
+    }
+
+    /// Returns the ticks the snapshots kept in debug mode were taken before, oldest first
+    pub fn snapshot_frames(&self) -> Vec<u64> {
+        self.snapshots.iter().map(|(frame, _)| *frame).collect()
+    }
+
+    /// Returns the keys held during the last ticks executed in debug mode, oldest first
+    #[inline]
+    pub fn timeline(&self) -> &collections::VecDeque<TimelineFrame> {
+        &self.timeline
+    }
+
+    pub(self) fn run(mut self, handles: handle::Handles, keys: mpsc::Receiver<KeyEvent>) -> Self {
+        let mut runner = self.runner(keys);
+        let mut control = handles.control.0.lock().unwrap();
+
+        loop {
+            match control.command {
+                handle::Command::None => {
+                    if control.status != handle::Status::Running {
+                        control.status = handle::Status::Running;
+                        handles.control.1.notify_all();
+                    }
+                }
                 handle::Command::Stop => break,
                 handle::Command::Suspend => {
-                    let _ = command_handle.1.wait(command);
+                    control.status = handle::Status::Parked;
+                    handles.control.1.notify_all();
+
+                    control = handles.control.1.wait(control).unwrap();
                     continue;
                 }
             }
 
-            if self.backend.timers.sound > 0 {
-                self.sound.play(&sink)
+            drop(control);
+
+            let flow = self.tick(&mut runner, &handles);
+
+            // the lock taken after the tick is the one the next command is read with
+            control = match flow {
+                Flow::Idle => {
+                    handles
+                        .control
+                        .1
+                        .wait_timeout_while(
+                            handles.control.0.lock().unwrap(),
+                            interval(&handles),
+                            |control| control.command == handle::Command::None,
+                        )
+                        .unwrap()
+                        .0
+                }
+                Flow::Wait => {
+                    thread::sleep(interval(&handles));
+                    handles.control.0.lock().unwrap()
+                }
+                Flow::Next | Flow::Stop => handles.control.0.lock().unwrap(),
+            };
+
+            if let Flow::Stop = flow {
+                break;
             }
+        }
 
-            let keyboard_state = keyboard_handle.lock().unwrap();
+        self.checks = Some(runner.checks);
 
-            match self
-                .backend
-                .tick(n, (&mut self.display_buffer, &keyboard_state))
-            {
-                Ok((index, instruction)) => {
-                    if self.options.debug_mode {
-                        sender
-                            .send(Ok(format!(
-                                "Executed intruction {} at 0x{:03x}",
-                                instruction, index
-                            )))
-                            .expect("receiver dropped before the frontend thread is stopped");
-
-                        let mut command = command_handle.0.lock().unwrap();
-                        *command = handle::Command::Suspend;
+        // nothing waiting on the thread to park or resume is left waiting after it returned
+        control.status = handle::Status::Finished;
+        handles.control.1.notify_all();
+
+        self
+    }
+
+    /// Applies the key events the user interface sent since the last tick
+    fn forward_keys(&mut self, runner: &mut Runner) {
+        for event in runner.keys.try_iter() {
+            match event.pressed {
+                true => {
+                    if !self.keyboard_state.held(event.key) {
+                        runner.pressed_at[event.key] = Some(event.timestamp);
                     }
+
+                    self.keyboard_state.hold(event.key);
+                }
+                false => {
+                    runner.pressed_at[event.key] = None;
+                    self.keyboard_state.release(event.key);
                 }
-                Err(error) => {
-                    let error = FrontendError::Backend(error);
-                    let fatal = error.is_fatal();
+            }
+        }
+    }
+
+    /// Prepares a run of the frontend, the keys are changed by the events received through `keys`
+    pub(self) fn runner(&mut self, keys: mpsc::Receiver<KeyEvent>) -> Runner {
+        // without an audio output, the program runs all the same without sound
+        let sink: Box<dyn audio::AudioSink> = match self.mixer.upgrade() {
+            Some(mixer) => {
+                let (sink, queue) = rodio::Sink::new_idle();
+                mixer.add(queue);
+
+                Box::new(sink)
+            }
+            None => Box::new(audio::NullSink),
+        };
+
+        // the reference starts over from the current state once the state was changed
+        let checks = self.checks.take().unwrap_or_else(|| Checks {
+            reference: self.config.verify_quirks.map(|quirks| {
+                verifier::Reference::new(self.core.state(), &self.display_buffer, quirks)
+            }),
+            stall: stall::StallDetector::new(
+                self.config.stall_warning_seconds as u64 * backend::TICKS_PER_SECOND,
+            ),
+            warned: false,
+        });
+
+        Runner {
+            beep_length: 0,
+            checks,
+            halt_states: collections::VecDeque::with_capacity(HALT_WINDOW),
+            keys,
+            pressed_at: [None; backend::KEY_COUNT],
+            sink,
+            sound_timer: 0,
+        }
+    }
+
+    /// Executes a tick worth of instructions and updates the display, the part of a run shared by the
+    /// threaded and the single-threaded mode
+    pub(self) fn tick(&mut self, runner: &mut Runner, handles: &handle::Handles) -> Flow {
+        if let Some((colors, fade_effect)) = handles.appearance.lock().unwrap().take() {
+            self.set_appearance(colors, fade_effect);
+        }
+
+        let sound_timer = self.core.state().timers.sound;
+        let beeping = sound_timer > 0;
 
-                    sender
-                        .send(Err(error))
-                        .expect("receiver dropped before the frontend thread is stopped");
+        // the timer only goes up when the program sets it, to the length of the beep
+        if sound_timer > runner.sound_timer {
+            runner.beep_length = sound_timer;
+        }
+
+        runner.sound_timer = sound_timer;
+
+        if beeping {
+            self.sound.play(
+                runner.sink.as_ref(),
+                self.config.pitched_beep.then_some(runner.beep_length),
+            )
+        }
 
-                    if fatal || self.options.debug_mode {
-                        self.context.request_repaint();
-                        break;
+        // the visual beep is drawn by the user interface, which has to be repainted when it changes
+        if handles.beeping.swap(beeping, atomic::Ordering::Relaxed) != beeping {
+            self.context.request_repaint();
+        }
+
+        // in debug mode, instructions are stepped through one by one unless continuing to a breakpoint
+        let continuing =
+            self.config.debug_mode && handles.continuing.load(atomic::Ordering::Relaxed);
+        let n = match self.config.debug_mode && !continuing {
+            true => num::NonZeroU16::MIN,
+            false => self.config.instructions_per_tick,
+        };
+
+        let watched: Vec<Option<u8>> = self
+            .watchpoints
+            .iter()
+            .map(|address| self.core.state().memory.get(*address).copied())
+            .collect();
+
+        // the keys are applied once per tick, the whole batch of instructions reads the same keys
+        // and the key checks that end it early see them as of its start
+        self.forward_keys(runner);
+
+        // the keys the user pressed are overridden by the controller, if there's one
+        if let Some(controller) = self.controller.as_mut() {
+            let keys = controller.keys(&self.display_buffer, self.core.state());
+            backend::controller::apply(&mut self.keyboard_state, keys);
+        }
+
+        let frame = self.timeline.back().map_or(0, |last| last.frame + 1);
+
+        if self.config.debug_mode && frame.is_multiple_of(SNAPSHOT_INTERVAL) {
+            if self.snapshots.len() as u64 * SNAPSHOT_INTERVAL >= TIMELINE_LENGTH as u64 {
+                self.snapshots.pop_front();
+            }
+
+            self.snapshots.push_back((frame, self.snapshot()));
+        }
+
+        let statistics = &self.core.state().statistics;
+        let counts = (
+            statistics.draws,
+            statistics.sounds,
+            statistics.key_reads,
+            statistics.families[0xC],
+        );
+
+        let result = match runner.checks.reference.as_mut() {
+            Some(reference) => reference.tick(
+                self.core.as_mut(),
+                n,
+                (&mut self.display_buffer, &self.keyboard_state),
+            ),
+            None => self
+                .core
+                .tick(n, (&mut self.display_buffer, &self.keyboard_state))
+                .map_err(FrontendError::Backend),
+        };
+
+        let statistics = &self.core.state().statistics;
+        let mut events = TickEvents::empty();
+
+        events.set(TickEvents::DRAW, statistics.draws != counts.0);
+        events.set(TickEvents::BEEP, statistics.sounds != counts.1);
+        events.set(TickEvents::KEY_READ, statistics.key_reads != counts.2);
+        events.set(TickEvents::ERROR, result.is_err());
+
+        // a program that comes back to a state it was in without drawing, beeping, reading the keys
+        // or drawing random numbers in between goes around the same loop forever
+        let random = statistics.families[0xC] != counts.3;
+        let result = match result {
+            Ok((index, _)) if self.config.pause_on_halt && !self.config.debug_mode => {
+                match events.is_empty() && !random {
+                    true => {
+                        let state = fingerprint(self.core.state(), &self.display_buffer);
+                        let halted = runner.halt_states.contains(&state);
+
+                        if runner.halt_states.len() == HALT_WINDOW {
+                            runner.halt_states.pop_front();
+                        }
+
+                        runner.halt_states.push_back(state);
+
+                        match halted {
+                            true => {
+                                runner.halt_states.clear();
+                                Err(FrontendError::Halted(index))
+                            }
+                            false => result,
+                        }
+                    }
+                    false => {
+                        runner.halt_states.clear();
+                        result
                     }
+                }
+            }
+            result => result,
+        };
+
+        if self.config.debug_mode {
+            if self.timeline.len() == TIMELINE_LENGTH {
+                self.timeline.pop_front();
+            }
+
+            let state = self.core.state();
+
+            self.timeline.push_back(TimelineFrame {
+                delay: state.timers.delay,
+                events,
+                frame,
+                index: match result.as_ref() {
+                    Ok((index, _)) => *index,
+                    Err(_) => state.index(),
+                },
+                keys: (0..backend::KEY_COUNT)
+                    .filter(|key| self.keyboard_state.held(*key))
+                    .fold(0, |keys, key| keys | 1 << key),
+                sound: state.timers.sound,
+            });
+
+            for collision in self.display_buffer.collisions.drain(..) {
+                if self.collisions.len() == COLLISION_LOG_LENGTH {
+                    self.collisions.pop_front();
+                }
 
-                    let mut command = command_handle.0.lock().unwrap();
-                    *command = handle::Command::Suspend;
+                self.collisions
+                    .push_back(CollisionEvent { collision, frame });
+            }
+        }
+
+        if let (Some(hook), Ok(_)) = (self.frame_hook.as_mut(), result.as_ref()) {
+            hook(&self.display_buffer, self.core.state());
+        }
+
+        // the console of the pseudo-peripherals is printed to the standard output and the log
+        let console = mem::take(&mut self.core.state_mut().console);
+
+        if !console.is_empty() {
+            let mut stdout = io::stdout().lock();
+            let _ = stdout.write_all(&console).and_then(|_| stdout.flush());
+
+            handles.print(&console);
+            self.context.request_repaint();
+        }
+
+        if result.is_ok() {
+            self.ticks += 1;
+
+            if self.config.hash_overlay {
+                let hash = self.display_buffer.hash();
+                let mut display_hash = handles.display_hash.lock().unwrap();
+
+                // the tick is the one the display first looked this way in, so that it only changes
+                // along with the display
+                if display_hash.is_none_or(|(tick, last)| last != hash || tick > self.ticks) {
+                    *display_hash = Some((self.ticks, hash));
+                    self.context.request_repaint();
                 }
             }
+        }
 
-            if self.display_buffer.dirty {
-                self.display_buffer.dirty = false;
+        // the latency is measured up to the end of the tick the program first found the key held in
+        let observed = self.keyboard_state.take_observed();
 
-                self.update_texture();
+        for (key, pressed_at) in runner.pressed_at.iter_mut().enumerate() {
+            if observed & 1 << key != 0 {
+                if let Some(pressed_at) = pressed_at.take() {
+                    *handles.latency.lock().unwrap() = Some(pressed_at.elapsed());
+                }
             }
+        }
 
-            if !self.options.debug_mode {
-                thread::sleep(TICK_INTERVAL);
+        // like overwriting code, stalling is only warned about and stepping through a program
+        // stalls it all the time
+        if let (false, Ok((index, _))) = (self.config.debug_mode, result.as_ref()) {
+            if runner
+                .checks
+                .stall
+                .observe(self.core.state(), &self.display_buffer)
+            {
+                handles.send(Err(FrontendError::Stalled {
+                    index: *index,
+                    seconds: self.config.stall_warning_seconds,
+                }));
             }
         }
 
-        self
+        let self_modified = self
+            .core
+            .state_mut()
+            .self_modified
+            .take()
+            .filter(|_| self.config.self_modification_warnings);
+
+        let message = match result {
+            // in debug mode, overwriting executed code stops the program like a watchpoint
+            Ok(_) if self_modified.is_some() && self.config.debug_mode => Some(format!(
+                "Executed code at 0x{:03x} was overwritten",
+                self_modified.unwrap()
+            )),
+            Ok((index, instruction)) => match continuing {
+                true => {
+                    let state = self.core.state();
+                    let written = self
+                        .watchpoints
+                        .iter()
+                        .zip(&watched)
+                        .find(|(address, value)| state.memory.get(**address).copied() != **value);
+
+                    match written {
+                        Some((address, _)) => {
+                            Some(format!("Watched address 0x{:03x} was written", address))
+                        }
+                        None if state.breakpoints.contains(&state.index()) => {
+                            Some(format!("Hit breakpoint at 0x{:03x}", state.index()))
+                        }
+                        None => None,
+                    }
+                }
+                false if self.config.debug_mode => Some(format!(
+                    "Executed intruction {} at 0x{:03x}",
+                    instruction, index
+                )),
+                false => None,
+            },
+            Err(FrontendError::Backend(error))
+                if matches!(error.kind, backend::BackendErrorKind::DebugBreak) =>
+            {
+                Some(format!("Stopped by {}", error))
+            }
+            Err(error) => {
+                let stopped = error.is_fatal() || error.is_exit();
+
+                handles.send(Err(error));
+
+                if stopped || self.config.debug_mode {
+                    self.context.request_repaint();
+                    return Flow::Stop;
+                }
+
+                suspend(&handles.control);
+                None
+            }
+        };
+
+        // out of debug mode, the program keeps running after the warning
+        if let Some(address) =
+            self_modified.filter(|_| !self.config.debug_mode && !runner.checks.warned)
+        {
+            runner.checks.warned = true;
+
+            handles.send(Err(FrontendError::SelfModification(address)));
+        }
+
+        if let Some(message) = message {
+            handles.continuing.store(false, atomic::Ordering::Relaxed);
+
+            // suspend before reporting the step so that the state can be inspected as soon as it's received
+            suspend(&handles.control);
+
+            handles.send(Ok(message));
+            self.context.request_repaint();
+        }
+
+        // nobody is looking at the display in the background, it's uploaded once back in the foreground
+        if self.display_buffer.dirty && !handles.background.load(atomic::Ordering::Relaxed) {
+            self.display_buffer.dirty = false;
+
+            self.update_texture();
+        }
+
+        // a silent program with a still display has nothing to show nor play before the next tick
+        let idle = self.core.state().timers.sound == 0
+            && !events.contains(TickEvents::DRAW)
+            && !self.display_buffer.dirty;
+
+        match (!self.config.debug_mode || continuing, idle) {
+            (true, true) => Flow::Idle,
+            (true, false) => Flow::Wait,
+            (false, _) => Flow::Next,
+        }
     }
 
     pub fn update_texture(&mut self) {
@@ -188,22 +907,12 @@ impl Frontend {
 
         for (y, row) in self.display_buffer.buffer.iter().enumerate() {
             for (x, pixel) in row.iter().enumerate() {
-                if self.options.fade_effect {
+                if self.config.fade_effect {
                     let changed = self.display_buffer.changed.remove(&(x, y));
 
                     if let Some(timestamp) = changed {
-                        let elapsed = timestamp.elapsed();
-
-                        if elapsed < FADE_DURATION {
-                            pixels.push(fade(
-                                self.colors.active,
-                                self.colors.inactive,
-                                match elapsed.as_secs_f32() / FADE_DURATION.as_secs_f32() {
-                                    x if x < 0.5 => 4.0,
-                                    x if x < 0.75 => 2.0,
-                                    _ => 1.3,
-                                },
-                            ));
+                        if let Some(color) = self.config.colors.fade(timestamp.elapsed()) {
+                            pixels.push(color);
                             self.display_buffer.changed.insert((x, y), timestamp);
                             self.display_buffer.dirty = true;
 
@@ -212,19 +921,19 @@ impl Frontend {
                     }
                 }
 
-                pixels.push(self.colors.get(*pixel));
+                pixels.push(self.config.colors.get(*pixel));
             }
         }
 
+        // a display that looks the same as before doesn't need to be uploaded nor repainted
+        if pixels == self.pixels {
+            return;
+        }
+
+        self.pixels = pixels;
         self.display_texture.set(
-            egui::ColorImage {
-                size: [
-                    backend::DISPLAY_BUFFER_WIDTH,
-                    backend::DISPLAY_BUFFER_HEIGHT,
-                ],
-                pixels,
-            },
-            egui::TextureOptions::NEAREST,
+            self.config.scaling_filter.image(&self.pixels),
+            self.config.scaling_filter.options(),
         );
 
         self.context.request_repaint();
@@ -238,3 +947,45 @@ fn fade(src: egui::Color32, dst: egui::Color32, stp: f32) -> egui::Color32 {
         (src.b().saturating_add(dst.b()) as f32 / stp) as u8,
     )
 }
+
+/// Returns the time between two ticks
+///
+/// In the background every tick is slowed down as a whole, timers included, so that the program
+/// keeps behaving the same, only slower.
+fn interval(handles: &handle::Handles) -> time::Duration {
+    let interval = match handles.turbo.load(atomic::Ordering::Relaxed) {
+        true => TICK_INTERVAL / TURBO_FACTOR,
+        false => TICK_INTERVAL,
+    };
+
+    match handles.background.load(atomic::Ordering::Relaxed) {
+        true => interval * BACKGROUND_FACTOR,
+        false => interval,
+    }
+}
+
+/// Hashes what the program can tell apart of its state and its display, to find when it's back in
+/// a state it was in
+fn fingerprint(state: &backend::Backend, display_buffer: &interfaces::DisplayBuffer) -> u64 {
+    let mut hasher = collections::hash_map::DefaultHasher::new();
+
+    state.index().hash(&mut hasher);
+    state.registers.general.hash(&mut hasher);
+    state.registers.address.hash(&mut hasher);
+    state.stack.hash(&mut hasher);
+    state.timers.delay.hash(&mut hasher);
+    state.timers.sound.hash(&mut hasher);
+    state.memory.hash(&mut hasher);
+    display_buffer.hash().hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// Suspends the frontend thread unless another command was given to it in the meantime
+fn suspend(control: &(sync::Mutex<handle::Control>, sync::Condvar)) {
+    let mut control = control.0.lock().unwrap();
+
+    if control.command == handle::Command::None {
+        control.command = handle::Command::Suspend;
+    }
+}
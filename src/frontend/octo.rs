@@ -0,0 +1,152 @@
+use std::error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend;
+
+/// The state of the machine laid out like the emulator of Octo, so that it can be compared against its test harness
+#[derive(Serialize)]
+struct OctoState<'a> {
+    dt: u8,
+    hires: bool,
+    i: usize,
+    m: &'a [u8],
+    /// Pixels of the two display planes, row by row
+    p: [Vec<u8>; 2],
+    pc: usize,
+    /// Return stack
+    r: &'a [u16],
+    st: u8,
+    v: &'a [u8],
+}
+
+/// A state in the layout of `OctoState`, as read from a file
+#[derive(Deserialize)]
+struct OctoImport {
+    dt: u8,
+    hires: bool,
+    i: usize,
+    m: Vec<u8>,
+    p: Vec<Vec<u8>>,
+    pc: usize,
+    r: Vec<u16>,
+    st: u8,
+    v: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum OctoError {
+    /// The state is in the high resolution mode, which RC-8 doesn't have
+    HiRes,
+    Json(serde_json::Error),
+    /// The part of the state that doesn't fit the machine of the program it's imported into
+    Mismatch(&'static str),
+}
+
+impl super::Snapshot {
+    /// Reads a state in the JSON format used by the test harness of Octo, taking what Octo doesn't
+    /// keep, like the quirks and the random numbers, from `base`
+    ///
+    /// Only the first of the two display planes of XO-CHIP is kept, RC-8 has a single one.
+    pub fn from_octo_json(json: &str, base: &Self) -> Result<Self, OctoError> {
+        let state: OctoImport = serde_json::from_str(json).map_err(OctoError::Json)?;
+
+        if state.hires {
+            return Err(OctoError::HiRes);
+        }
+
+        let mut backend = base.backend.clone();
+
+        if state.m.len() != backend.memory.len() {
+            return Err(OctoError::Mismatch("memory"));
+        }
+
+        if state.pc + 1 >= backend.memory.len() {
+            return Err(OctoError::Mismatch("program counter"));
+        }
+
+        if state.r.len() > backend::STACK_SIZE {
+            return Err(OctoError::Mismatch("stack"));
+        }
+
+        backend.registers.general = state
+            .v
+            .try_into()
+            .map_err(|_| OctoError::Mismatch("registers"))?;
+        backend.registers.address = state.i;
+        backend.memory = state.m;
+        backend.stack = state.r;
+        backend.timers.delay = state.dt;
+        backend.timers.sound = state.st;
+        backend.set_index(state.pc);
+
+        let pixels = state
+            .p
+            .first()
+            .filter(|pixels| {
+                pixels.len() == backend::DISPLAY_BUFFER_WIDTH * backend::DISPLAY_BUFFER_HEIGHT
+            })
+            .ok_or(OctoError::Mismatch("display"))?;
+
+        let mut display_buffer = base.display_buffer.clone();
+        display_buffer.clear();
+
+        for (row, pixels) in display_buffer
+            .buffer
+            .iter_mut()
+            .zip(pixels.chunks(backend::DISPLAY_BUFFER_WIDTH))
+        {
+            for (x, pixel) in pixels.iter().enumerate() {
+                row.set(x, *pixel != 0);
+            }
+        }
+
+        display_buffer.dirty = true;
+
+        Ok(Self {
+            backend,
+            display_buffer,
+        })
+    }
+
+    /// Returns the state in the JSON format used by the test harness of Octo
+    pub fn to_octo_json(&self) -> String {
+        let pixels = self
+            .display_buffer
+            .buffer
+            .iter()
+            .flat_map(|row| row.iter().take(backend::DISPLAY_BUFFER_WIDTH))
+            .map(|pixel| *pixel as u8)
+            .collect::<Vec<u8>>();
+
+        let state = OctoState {
+            dt: self.backend.timers.delay,
+            hires: false,
+            i: self.backend.registers.address,
+            m: &self.backend.memory,
+            p: [
+                pixels,
+                vec![0; backend::DISPLAY_BUFFER_WIDTH * backend::DISPLAY_BUFFER_HEIGHT],
+            ],
+            pc: self.backend.index(),
+            r: &self.backend.stack,
+            st: self.backend.timers.sound,
+            v: &self.backend.registers.general,
+        };
+
+        serde_json::to_string(&state).unwrap()
+    }
+}
+
+impl fmt::Display for OctoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HiRes => write!(f, "the state is in the high resolution mode"),
+            Self::Json(error) => write!(f, "invalid state, {}", error),
+            Self::Mismatch(part) => write!(f, "the {} doesn't fit the machine", part),
+        }
+    }
+}
+
+impl error::Error for OctoError {}
@@ -1,27 +1,49 @@
 use std::io;
 use std::sync;
 
-const SOUND_OGG: &'static [u8] =
-    include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/sound.ogg"));
+use super::audio;
 
-pub struct Sound(sync::Arc<&'static [u8]>);
+/// Length of a beep in ticks played at the pitch of the sound, shorter ones are played higher and
+/// longer ones lower, an octave for every quadrupling of the length
+const PITCH_REFERENCE_LENGTH: f32 = 8.0;
+/// Octaves a beep is played above or below the pitch of the sound at most
+const PITCH_RANGE: f32 = 1.0;
+const SOUND_OGG: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/sound.ogg"));
+
+/// An encoded audio file played while the sound timer is active
+#[derive(Clone)]
+pub struct Sound(sync::Arc<[u8]>);
 
 impl Sound {
     pub fn decode(
         &self,
     ) -> Result<rodio::Decoder<io::Cursor<Sound>>, rodio::decoder::DecoderError> {
-        rodio::Decoder::new_vorbis(io::Cursor::new(Sound(sync::Arc::clone(&self.0))))
+        rodio::Decoder::new(io::Cursor::new(self.clone()))
     }
 
-    pub fn new() -> Result<Self, rodio::decoder::DecoderError> {
-        let sound = Self(sync::Arc::new(SOUND_OGG));
+    /// Returns the sound encoded in `bytes`, in any format rodio decodes, e.g. Ogg Vorbis, WAV or
+    /// MP3
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, rodio::decoder::DecoderError> {
+        let sound = Self(bytes.into());
         sound.decode()?;
 
         Ok(sound)
     }
 
-    pub fn play(&self, sink: &rodio::Sink) {
-        sink.append(self.decode().unwrap());
+    /// Returns the built-in beep
+    pub fn new() -> Result<Self, rodio::decoder::DecoderError> {
+        Self::from_bytes(SOUND_OGG.to_vec())
+    }
+
+    /// Plays the sound on `sink`, pitched by the length of the beep in ticks when it's given
+    pub fn play(&self, sink: &dyn audio::AudioSink, length: Option<u8>) {
+        let speed = length.map_or(1.0, |length| {
+            (PITCH_REFERENCE_LENGTH / length.max(1) as f32)
+                .sqrt()
+                .clamp(2f32.powf(-PITCH_RANGE), 2f32.powf(PITCH_RANGE))
+        });
+
+        sink.play(self, speed);
     }
 }
 
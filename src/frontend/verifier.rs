@@ -0,0 +1,170 @@
+use std::num;
+
+use crate::backend::{self, interfaces};
+
+use super::FrontendError;
+
+/// A second backend running the same program in lockstep with the one being verified
+pub(super) struct Reference {
+    backend: backend::Backend,
+    display_buffer: interfaces::DisplayBuffer,
+}
+
+impl Reference {
+    /// Returns a reference starting from the state of `backend`, running with `quirks`
+    pub fn new(
+        backend: &backend::Backend,
+        display_buffer: &interfaces::DisplayBuffer,
+        quirks: backend::Quirks,
+    ) -> Self {
+        let mut reference = Self {
+            backend: backend.clone(),
+            display_buffer: display_buffer.clone(),
+        };
+        reference.backend.quirks = quirks;
+        reference.display_buffer.track_changes = false;
+        reference.display_buffer.track_draws = false;
+
+        reference
+    }
+
+    /// Ticks `core` like `Backend::tick` does, stepping the reference along after every
+    /// instruction and failing at the first difference between the two
+    pub fn tick(
+        &mut self,
+        core: &mut dyn backend::Core,
+        n: num::NonZeroU16,
+        (display_buffer, keyboard_state): (
+            &mut interfaces::DisplayBuffer,
+            &interfaces::KeyboardState,
+        ),
+    ) -> Result<(usize, backend::Instruction), FrontendError> {
+        core.tick_timers();
+        self.backend.tick_timers();
+
+        let mut last = None;
+
+        for _ in 0..n.get() {
+            if let Some((_, _, yielded)) = last {
+                if yielded || core.state().breakpoints.contains(&core.state().index()) {
+                    break;
+                }
+            }
+
+            let (index, instruction, yielded) = core
+                .step((display_buffer, keyboard_state))
+                .map_err(FrontendError::Backend)?;
+
+            let differences = match self
+                .backend
+                .step((&mut self.display_buffer, keyboard_state))
+            {
+                Ok(_) => {
+                    // random numbers are taken from the backend being verified, so that they match
+                    if instruction.operator_code() == 0xC {
+                        self.backend.registers.general[instruction.operand_x()] =
+                            core.state().registers.general[instruction.operand_x()];
+                    }
+
+                    self.differences(core.state(), display_buffer)
+                }
+                Err(error) => vec![format!("the reference failed with {}", error)],
+            };
+
+            if !differences.is_empty() {
+                return Err(FrontendError::Divergence {
+                    index,
+                    instruction,
+                    differences,
+                });
+            }
+
+            last = Some((index, instruction, yielded));
+        }
+
+        let (index, instruction, _) = last.unwrap();
+
+        Ok((index, instruction))
+    }
+
+    fn differences(
+        &self,
+        backend: &backend::Backend,
+        display_buffer: &interfaces::DisplayBuffer,
+    ) -> Vec<String> {
+        let reference = &self.backend;
+        let mut differences = Vec::new();
+
+        if backend.index() != reference.index() {
+            differences.push(format!(
+                "PC 0x{:03x} instead of 0x{:03x}",
+                backend.index(),
+                reference.index()
+            ));
+        }
+
+        if backend.registers.address != reference.registers.address {
+            differences.push(format!(
+                "I 0x{:03x} instead of 0x{:03x}",
+                backend.registers.address, reference.registers.address
+            ));
+        }
+
+        for (register, (value, expected)) in backend
+            .registers
+            .general
+            .iter()
+            .zip(reference.registers.general.iter())
+            .enumerate()
+        {
+            if value != expected {
+                differences.push(format!(
+                    "V{:X} 0x{:02x} instead of 0x{:02x}",
+                    register, value, expected
+                ));
+            }
+        }
+
+        if backend.stack != reference.stack {
+            differences.push(format!(
+                "stack {:03x?} instead of {:03x?}",
+                backend.stack, reference.stack
+            ));
+        }
+
+        if backend.timers.delay != reference.timers.delay
+            || backend.timers.sound != reference.timers.sound
+        {
+            differences.push(format!(
+                "timers {}/{} instead of {}/{}",
+                backend.timers.delay,
+                backend.timers.sound,
+                reference.timers.delay,
+                reference.timers.sound
+            ));
+        }
+
+        if backend.memory.len() != reference.memory.len() {
+            differences.push(format!(
+                "memory size {} instead of {}",
+                backend.memory.len(),
+                reference.memory.len()
+            ));
+        }
+
+        if let Some(address) = (0..backend.memory.len().min(reference.memory.len()))
+            .find(|address| backend.memory[*address] != reference.memory[*address])
+        {
+            differences.push(format!(
+                "memory at 0x{:03x} 0x{:02x} instead of 0x{:02x}",
+                address, backend.memory[address], reference.memory[address]
+            ));
+        }
+
+        if display_buffer.buffer != self.display_buffer.buffer {
+            differences.push("display".to_owned());
+        }
+
+        differences
+    }
+}
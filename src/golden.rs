@@ -0,0 +1,258 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::num;
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{self, controller, interfaces};
+use crate::defaults;
+use crate::loader;
+
+const GOLDEN_FILE_SUFFIX: &str = ".golden.json";
+const INPUTS_FILE_SUFFIX: &str = ".inputs";
+
+/// The displays of a reproducible run of a program, for regression suites to check later runs
+/// against
+#[derive(Deserialize, Serialize)]
+pub struct Golden {
+    /// Hashes of the display after every tick, each with the number of ticks in a row it lasted
+    pub hashes: Vec<(u64, u64)>,
+    pub inputs: Vec<Input>,
+    pub instructions_per_tick: num::NonZeroU16,
+    pub quirks: backend::Quirks,
+    /// Seed of the random numbers of the run
+    pub seed: u64,
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Backend(backend::BackendError),
+    /// The inputs are malformed at the line
+    InvalidInputs(usize),
+    IO(io::Error),
+    Json(serde_json::Error),
+    Load(loader::LoadError),
+}
+
+/// Keys held from a tick on, until the next input
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Input {
+    /// Bit mask of the held keys
+    pub keys: u16,
+    pub tick: u64,
+}
+
+impl Golden {
+    pub fn load(path: &path::Path) -> Result<Self, GoldenError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Runs `program` for `ticks` ticks, or until it exits, holding the keys of `inputs`, and
+    /// records its displays
+    pub fn record(
+        program: &[u8],
+        inputs: Vec<Input>,
+        seed: u64,
+        ticks: u64,
+    ) -> Result<Self, GoldenError> {
+        let mut golden = Self {
+            hashes: Vec::new(),
+            inputs,
+            instructions_per_tick: defaults::INSTRUCTIONS_PER_TICK,
+            quirks: backend::Quirks::default(),
+            seed,
+        };
+        let mut hashes: Vec<(u64, u64)> = Vec::new();
+
+        golden.run(program, ticks, |_, hash| {
+            match hashes.last_mut() {
+                Some((last, count)) if *last == hash => *count += 1,
+                _ => hashes.push((hash, 1)),
+            }
+
+            true
+        })?;
+
+        golden.hashes = hashes;
+
+        Ok(golden)
+    }
+
+    /// Runs the program the way it was recorded, calling `observe` with every tick and the hash of
+    /// the display after it until it returns false or the program exits
+    fn run(
+        &self,
+        program: &[u8],
+        ticks: u64,
+        mut observe: impl FnMut(u64, u64) -> bool,
+    ) -> Result<(), GoldenError> {
+        let mut state = backend::Backend::new();
+        state.quirks = self.quirks;
+        state.seed = Some(self.seed);
+
+        let mut core = backend::CoreKind::Interpreter.create(state);
+        core.load(None, program).map_err(GoldenError::Backend)?;
+
+        let mut display_buffer = interfaces::DisplayBuffer::new(false);
+        let mut keyboard_state = interfaces::KeyboardState::default();
+        let mut inputs = self.inputs.iter().peekable();
+        let mut keys = 0;
+
+        for tick in 0..ticks {
+            while let Some(input) = inputs.next_if(|input| input.tick <= tick) {
+                keys = input.keys;
+            }
+
+            controller::apply(&mut keyboard_state, keys);
+
+            let exited = match core.tick(
+                self.instructions_per_tick,
+                (&mut display_buffer, &keyboard_state),
+            ) {
+                Err(error) if matches!(error.kind, backend::BackendErrorKind::ProgramExited) => {
+                    true
+                }
+                result => result.map(|_| false).map_err(GoldenError::Backend)?,
+            };
+
+            // the display of the tick a program exited in is the last one it shows
+            if !observe(tick, display_buffer.hash()) || exited {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn save(&self, path: &path::Path) -> Result<(), GoldenError> {
+        Ok(fs::write(path, serde_json::to_vec(self)?)?)
+    }
+
+    /// Returns the number of ticks recorded
+    #[inline]
+    pub fn ticks(&self) -> u64 {
+        self.hashes.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Runs `program` again and returns the first tick its display differs from the recording
+    /// after, if it does
+    pub fn verify(&self, program: &[u8]) -> Result<Option<u64>, GoldenError> {
+        let mut expected = self
+            .hashes
+            .iter()
+            .flat_map(|(hash, count)| (0..*count).map(move |_| *hash));
+        let mut diverged = None;
+        let mut ticks = 0;
+
+        self.run(program, self.ticks(), |tick, hash| {
+            ticks = tick + 1;
+
+            match expected.next() == Some(hash) {
+                true => true,
+                false => {
+                    diverged = Some(tick);
+                    false
+                }
+            }
+        })?;
+
+        // exiting before the end of the recording diverges from it too
+        if diverged.is_none() && expected.next().is_some() {
+            diverged = Some(ticks);
+        }
+
+        Ok(diverged)
+    }
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Backend(error) => write!(f, "{}", error),
+            Self::InvalidInputs(line) => write!(f, "invalid inputs at line {}", line),
+            Self::IO(error) => write!(f, "{}", error),
+            Self::Json(error) => write!(f, "invalid recording, {}", error),
+            Self::Load(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for GoldenError {}
+
+impl From<io::Error> for GoldenError {
+    fn from(error: io::Error) -> Self {
+        Self::IO(error)
+    }
+}
+
+impl From<serde_json::Error> for GoldenError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl From<loader::LoadError> for GoldenError {
+    fn from(error: loader::LoadError) -> Self {
+        Self::Load(error)
+    }
+}
+
+/// Returns the path of the recording of the program at `program`, next to it
+pub fn golden_path(program: &path::Path) -> path::PathBuf {
+    sidecar(program, GOLDEN_FILE_SUFFIX)
+}
+
+/// Reads the inputs of the program at `program` from the file next to it, none if there's no such
+/// file, see `read_inputs`
+pub fn load_inputs(program: &path::Path) -> Result<Vec<Input>, GoldenError> {
+    match read_inputs(&sidecar(program, INPUTS_FILE_SUFFIX)) {
+        Err(GoldenError::IO(error)) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        result => result,
+    }
+}
+
+/// Reads the inputs at `path`
+///
+/// Every line is a tick and the keys held from it on in hexadecimal, e.g. `120 5A`, or `-` for no
+/// key, and `#` starts a comment.
+pub fn read_inputs(path: &path::Path) -> Result<Vec<Input>, GoldenError> {
+    let contents = fs::read_to_string(path)?;
+    let mut inputs = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let invalid = || GoldenError::InvalidInputs(number + 1);
+
+        let (tick, keys) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+        let tick = tick.parse().map_err(|_| invalid())?;
+        let keys = match keys.trim() {
+            "-" => 0,
+            keys => keys.chars().try_fold(0, |keys, key| {
+                key.to_digit(16)
+                    .map(|key| keys | 1 << key)
+                    .ok_or_else(invalid)
+            })?,
+        };
+
+        inputs.push(Input { keys, tick });
+    }
+
+    inputs.sort_by_key(|input| input.tick);
+
+    Ok(inputs)
+}
+
+fn sidecar(program: &path::Path, suffix: &str) -> path::PathBuf {
+    let mut path = program.as_os_str().to_owned();
+    path.push(suffix);
+
+    path::PathBuf::from(path)
+}
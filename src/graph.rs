@@ -0,0 +1,229 @@
+use std::collections;
+use std::mem;
+
+use crate::backend::Instruction;
+use crate::disassembler::{Line, Region};
+
+/// A run of instructions only entered at its first one and only left after its last one
+#[derive(Clone, Debug)]
+pub struct Block {
+    /// Address of the last instruction
+    pub end: usize,
+    /// Address of the first instruction
+    pub start: usize,
+    /// The blocks executed after this one, in no particular order
+    pub successors: Vec<Edge>,
+}
+
+/// A way from the end of a block to the start of another one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edge {
+    pub kind: EdgeKind,
+    /// Address of the block
+    pub target: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EdgeKind {
+    /// `2NNN`, the block after the call is reached once it returns
+    Call,
+    /// `1NNN`
+    Jump,
+    /// The next instruction, after an instruction that doesn't branch or a skip that didn't skip
+    Next,
+    /// A skip that skipped the next instruction
+    Skip,
+}
+
+/// Code entered through calls, or the entry of the program
+#[derive(Clone, Debug)]
+pub struct Subroutine {
+    /// Address of the first instruction
+    pub address: usize,
+    /// Addresses of the subroutines it calls, in the order of their addresses
+    pub callees: Vec<usize>,
+    /// Addresses of the instructions calling it
+    pub callers: Vec<usize>,
+}
+
+/// Splits the code of `lines` into blocks, linked by the jumps, calls and skips between them
+///
+/// The targets of `BNNN` depend on V0 and are left out, as are the blocks reached only through
+/// them.
+pub fn control_flow(lines: &[Line]) -> Vec<Block> {
+    let code: collections::BTreeMap<usize, Instruction> = lines
+        .iter()
+        .filter(|line| line.region == Region::Code)
+        .map(|line| {
+            (
+                line.address,
+                Instruction::new([line.bytes[0], line.bytes[1]]),
+            )
+        })
+        .collect();
+    let size = mem::size_of::<Instruction>();
+
+    // the instructions that a block ends with and where they go
+    let exits = |address: usize, instruction: &Instruction| -> Option<Vec<Edge>> {
+        let edge = |kind, target| Edge { kind, target };
+
+        Some(
+            match (instruction.operator_code(), instruction.operand_nn()) {
+                (0x0, 0xEE) | (0x0, 0xFD) | (0xB, _) => Vec::new(),
+                (0x1, _) => vec![edge(EdgeKind::Jump, instruction.operand_nnn())],
+                (0x2, _) => vec![
+                    edge(EdgeKind::Call, instruction.operand_nnn()),
+                    edge(EdgeKind::Next, address + size),
+                ],
+                (0x3 | 0x4 | 0x5 | 0x9, _) | (0xE, 0x9E | 0xA1) => vec![
+                    edge(EdgeKind::Next, address + size),
+                    edge(EdgeKind::Skip, address + size * 2),
+                ],
+                _ => return None,
+            },
+        )
+    };
+
+    let mut starts: collections::BTreeSet<usize> =
+        code.keys().next().copied().into_iter().collect();
+
+    for (address, instruction) in code.iter() {
+        if let Some(edges) = exits(*address, instruction) {
+            starts.extend(edges.iter().map(|edge| edge.target));
+            starts.insert(address + size);
+        }
+    }
+
+    let mut blocks = Vec::new();
+    let mut block: Option<Block> = None;
+
+    for (address, instruction) in code.iter() {
+        // a block also ends before data or an instruction jumped to
+        if let Some(mut open) = block.take() {
+            match open.end + size == *address && !starts.contains(address) {
+                true => block = Some(open),
+                false => {
+                    if open.end + size == *address {
+                        open.successors.push(Edge {
+                            kind: EdgeKind::Next,
+                            target: *address,
+                        });
+                    }
+
+                    blocks.push(open);
+                }
+            }
+        }
+
+        let open = block.get_or_insert(Block {
+            end: *address,
+            start: *address,
+            successors: Vec::new(),
+        });
+        open.end = *address;
+
+        if let Some(edges) = exits(*address, instruction) {
+            open.successors = edges
+                .into_iter()
+                .filter(|edge| code.contains_key(&edge.target))
+                .collect();
+            blocks.extend(block.take());
+        }
+    }
+
+    blocks.extend(block);
+    blocks
+}
+
+/// Returns the entry of the program, the first block of `blocks`, followed by the subroutines
+/// called in them, each with the subroutines it calls and the instructions calling it
+///
+/// What a subroutine calls is found by following its blocks from its start without following the
+/// calls, so code shared by several subroutines counts for each of them.
+pub fn call_graph(blocks: &[Block]) -> Vec<Subroutine> {
+    let by_start: collections::BTreeMap<usize, &Block> =
+        blocks.iter().map(|block| (block.start, block)).collect();
+
+    let mut callers: collections::BTreeMap<usize, Vec<usize>> = collections::BTreeMap::new();
+
+    for block in blocks {
+        for edge in block
+            .successors
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Call)
+        {
+            callers.entry(edge.target).or_default().push(block.end);
+        }
+    }
+
+    let entry = blocks.first().map(|block| block.start);
+    let addresses = entry.into_iter().chain(
+        callers
+            .keys()
+            .copied()
+            .filter(|address| Some(*address) != entry),
+    );
+
+    addresses
+        .map(|address| {
+            let mut callees = collections::BTreeSet::new();
+            let mut visited = collections::BTreeSet::new();
+            let mut pending = vec![address];
+
+            while let Some(start) = pending.pop() {
+                let Some(block) = by_start.get(&start).filter(|_| visited.insert(start)) else {
+                    continue;
+                };
+
+                for edge in block.successors.iter() {
+                    match edge.kind {
+                        EdgeKind::Call => {
+                            callees.insert(edge.target);
+                        }
+                        EdgeKind::Jump | EdgeKind::Next | EdgeKind::Skip => {
+                            pending.push(edge.target)
+                        }
+                    }
+                }
+            }
+
+            Subroutine {
+                address,
+                callees: callees.into_iter().collect(),
+                callers: callers.get(&address).cloned().unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Writes `blocks` as a graph in the DOT language of Graphviz, with `label` naming the blocks by
+/// their address
+pub fn dot(blocks: &[Block], label: impl Fn(usize) -> String) -> String {
+    let mut dot = String::from("digraph {\n    node [shape=box, fontname=monospace];\n");
+
+    for block in blocks {
+        dot.push_str(&format!(
+            "    b{:X} [label=\"{}\"];\n",
+            block.start,
+            label(block.start).replace('"', "\\\"")
+        ));
+    }
+
+    for block in blocks {
+        for edge in block.successors.iter() {
+            let style = match edge.kind {
+                EdgeKind::Call => " [style=dashed]",
+                EdgeKind::Jump | EdgeKind::Next => "",
+                EdgeKind::Skip => " [style=dotted]",
+            };
+
+            dot.push_str(&format!(
+                "    b{:X} -> b{:X}{};\n",
+                block.start, edge.target, style
+            ));
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+use std::num;
+use std::time;
+
+use crate::backend::{self, controller, interfaces, stall};
+use crate::golden;
+#[cfg(feature = "serde")]
+use crate::save_state;
+
+/// How a program is run without a window
+pub struct Options {
+    /// Keys held from a tick on, as read from an inputs file
+    pub inputs: Vec<golden::Input>,
+    pub instructions_per_tick: num::NonZeroU16,
+    /// Ticks after which the run is stopped, however the program is doing
+    pub max_frames: Option<u64>,
+    /// Seed of the random numbers, a random one is used when it's `None`
+    pub seed: Option<u64>,
+    /// State to start from instead of the start of the program
+    #[cfg(feature = "serde")]
+    pub state: Option<save_state::SaveState>,
+    /// Seconds of emulated time without a change after which the program is taken for stuck, zero
+    /// to never take it for stuck
+    pub stall_seconds: u16,
+    /// Real time after which the run is stopped, however the program is doing
+    pub timeout: Option<time::Duration>,
+}
+
+/// Why a run without a window ended
+#[derive(Debug)]
+pub enum Outcome {
+    /// The program failed with the error
+    Error(backend::BackendError),
+    /// The program ended itself with `00FD`
+    Exited,
+    /// `Options::max_frames` ticks were executed
+    MaxFrames,
+    /// The display, the memory and the registers stopped changing, see `stall::StallDetector`
+    Stalled,
+    /// The run took longer than `Options::timeout`
+    Timeout,
+}
+
+/// The end of a run without a window, with the state it was left in
+pub struct Report {
+    /// The display at the end
+    pub display: interfaces::DisplayBuffer,
+    pub outcome: Outcome,
+    pub state: backend::Backend,
+    /// Ticks the state didn't change for at the end
+    pub still: u64,
+    /// Ticks executed
+    pub ticks: u64,
+}
+
+impl Report {
+    /// Returns the report as a JSON object, for test harnesses and scripts
+    pub fn to_json(&self) -> String {
+        let statistics = &self.state.statistics;
+
+        serde_json::json!({
+            "outcome": match self.outcome {
+                Outcome::Error(_) => "error",
+                Outcome::Exited => "exited",
+                Outcome::MaxFrames => "max_frames",
+                Outcome::Stalled => "stalled",
+                Outcome::Timeout => "timeout",
+            },
+            "error": match &self.outcome {
+                Outcome::Error(error) => serde_json::json!({
+                    "code": error.code(),
+                    "message": error.to_string(),
+                }),
+                _ => serde_json::Value::Null,
+            },
+            "frames": self.ticks,
+            "still_frames": self.still,
+            "registers": {
+                "pc": self.state.index(),
+                "i": self.state.registers.address,
+                "v": self.state.registers.general,
+                "stack": self.state.stack,
+                "delay": self.state.timers.delay,
+                "sound": self.state.timers.sound,
+            },
+            // as a string, the hash doesn't fit the numbers of every JSON parser
+            "display_hash": format!("{:016x}", self.display.hash()),
+            "statistics": {
+                "instructions": statistics.total(),
+                "draws": statistics.draws,
+                "sounds": statistics.sounds,
+                "key_reads": statistics.key_reads,
+                "families": statistics.families,
+            },
+        })
+        .to_string()
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error(error) => write!(f, "failed, {}", error),
+            Self::Exited => write!(f, "exited"),
+            Self::MaxFrames => write!(f, "reached the maximum number of ticks"),
+            Self::Stalled => write!(f, "stalled"),
+            Self::Timeout => write!(f, "timed out"),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let context = backend::ErrorContext {
+            registers: self.state.registers.clone(),
+            stack_top: self.state.stack.last().copied(),
+        };
+
+        write!(
+            f,
+            "{} after {} ticks, unchanged for the last {} ticks, PC={:03X} {} display={:016x}",
+            self.outcome,
+            self.ticks,
+            self.still,
+            self.state.index(),
+            context,
+            self.display.hash()
+        )
+    }
+}
+
+/// Runs `program` without a window as fast as it can, holding the keys of the inputs, until it
+/// fails, stalls or exceeds the limits of `options`, only failing itself when the program can't
+/// be loaded
+pub fn run(program: &[u8], options: &Options) -> Result<Report, backend::BackendError> {
+    let mut state = backend::Backend::new();
+    state.seed = options.seed;
+
+    let mut core = backend::CoreKind::Interpreter.create(state);
+    core.load(None, program)?;
+
+    let mut display_buffer = interfaces::DisplayBuffer::new(false);
+
+    #[cfg(feature = "serde")]
+    if let Some(state) = options.state.as_ref() {
+        core.restore(state.backend.clone());
+        display_buffer = state.display.clone();
+    }
+    let mut keyboard_state = interfaces::KeyboardState::default();
+    let mut detector =
+        stall::StallDetector::new(options.stall_seconds as u64 * backend::TICKS_PER_SECOND);
+    let mut inputs = options.inputs.iter().peekable();
+    let mut keys = 0;
+    let mut ticks = 0;
+    let started = time::Instant::now();
+
+    loop {
+        while let Some(input) = inputs.next_if(|input| input.tick <= ticks) {
+            keys = input.keys;
+        }
+
+        controller::apply(&mut keyboard_state, keys);
+
+        let result = core.tick(
+            options.instructions_per_tick,
+            (&mut display_buffer, &keyboard_state),
+        );
+        ticks += 1;
+
+        let outcome = if let Err(error) = result {
+            match error.kind {
+                backend::BackendErrorKind::ProgramExited => Outcome::Exited,
+                _ => Outcome::Error(error),
+            }
+        } else if detector.observe(core.state(), &display_buffer) {
+            Outcome::Stalled
+        } else if options
+            .max_frames
+            .is_some_and(|max_frames| ticks >= max_frames)
+        {
+            Outcome::MaxFrames
+        } else if options
+            .timeout
+            .is_some_and(|timeout| started.elapsed() >= timeout)
+        {
+            Outcome::Timeout
+        } else {
+            continue;
+        };
+
+        return Ok(Report {
+            display: display_buffer,
+            outcome,
+            state: core.state().clone(),
+            still: detector.still(),
+            ticks,
+        });
+    }
+}
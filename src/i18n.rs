@@ -0,0 +1,347 @@
+use std::fmt;
+use std::fs;
+use std::path;
+
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource};
+
+use crate::backend;
+use crate::config;
+use crate::disassembler;
+use crate::frontend;
+use crate::graph;
+use crate::loader;
+use crate::ui;
+
+pub const DEFAULT_LANGUAGE: &str = "en-US";
+
+const DEFAULT_MESSAGES: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/locales/en-US.ftl"));
+const LOCALE_EXTENSION: &str = "ftl";
+
+/// Looks up the user facing messages in the selected language, falling back to English
+pub struct Localizer {
+    bundle: Option<FluentBundle<FluentResource>>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl Localizer {
+    pub fn action(&self, action: config::Action) -> String {
+        self.get(match action {
+            config::Action::Continue => "action-continue",
+            config::Action::LoadState => "action-load-state",
+            config::Action::Log => "action-log",
+            config::Action::NextProgram => "action-next-program",
+            config::Action::Pause => "action-pause",
+            config::Action::PreviousProgram => "action-previous-program",
+            config::Action::Reset => "action-reset",
+            config::Action::SaveState => "action-save-state",
+            config::Action::Screenshot => "action-screenshot",
+            config::Action::Step => "action-step",
+            config::Action::Turbo => "action-turbo",
+        })
+    }
+
+    /// Returns the languages with a locale file in `directory`, along with the default language
+    pub fn available_languages(directory: Option<&path::Path>) -> Vec<String> {
+        let mut languages = vec![DEFAULT_LANGUAGE.to_owned()];
+
+        if let Some(entries) = directory.and_then(|directory| fs::read_dir(directory).ok()) {
+            languages.extend(entries.filter_map(|entry| {
+                let path = entry.ok()?.path();
+
+                match path.extension()? == LOCALE_EXTENSION {
+                    true => Some(path.file_stem()?.to_str()?.to_owned()),
+                    false => None,
+                }
+            }));
+        }
+
+        languages.sort();
+        languages.dedup();
+
+        languages
+    }
+
+    pub fn backend_error(&self, error: &backend::BackendError) -> String {
+        let kind = self.get(match error.kind {
+            backend::BackendErrorKind::DebugBreak => "backend-error-debug-break",
+            backend::BackendErrorKind::MemoryOverflow => "backend-error-memory-overflow",
+            backend::BackendErrorKind::ProgramExited => "backend-error-program-exited",
+            backend::BackendErrorKind::ProgramInvalid => "backend-error-program-invalid",
+            backend::BackendErrorKind::ProgramNotLoaded => "backend-error-program-not-loaded",
+            backend::BackendErrorKind::StackOverflow => "backend-error-stack-overflow",
+            backend::BackendErrorKind::StackUnderflow => "backend-error-stack-underflow",
+            backend::BackendErrorKind::UnrecognizedInstruction => {
+                "backend-error-unrecognized-instruction"
+            }
+            backend::BackendErrorKind::UnrecognizedSprite => "backend-error-unrecognized-sprite",
+        });
+
+        let message = match error.instruction {
+            Some((index, Some(instruction))) => self.format(
+                "backend-error-instruction",
+                &[
+                    ("instruction", &instruction),
+                    ("address", &format!("0x{:03x}", index)),
+                    ("kind", &kind),
+                ],
+            ),
+            Some((index, None)) => self.format(
+                "backend-error-address",
+                &[("address", &format!("0x{:x}", index)), ("kind", &kind)],
+            ),
+            None => kind,
+        };
+
+        match error.context.as_ref() {
+            Some(context) => self.format(
+                "backend-error-context",
+                &[("error", &message), ("context", context)],
+            ),
+            None => message,
+        }
+    }
+
+    pub fn core(&self, core: backend::CoreKind) -> String {
+        self.get(match core {
+            backend::CoreKind::Interpreter => "core-interpreter",
+            #[cfg(feature = "jit")]
+            backend::CoreKind::Jit => "core-jit",
+        })
+    }
+
+    pub fn default_directory() -> Option<path::PathBuf> {
+        dirs::data_dir().map(|path| path.join(env!("CARGO_PKG_NAME")).join("locales"))
+    }
+
+    pub fn edge(&self, kind: graph::EdgeKind) -> String {
+        self.get(match kind {
+            graph::EdgeKind::Call => "edge-call",
+            graph::EdgeKind::Jump => "edge-jump",
+            graph::EdgeKind::Next => "edge-next",
+            graph::EdgeKind::Skip => "edge-skip",
+        })
+    }
+
+    pub fn font(&self, font: backend::Font) -> String {
+        self.get(match font {
+            backend::Font::Standard => "font-standard",
+            backend::Font::CosmacVip => "font-cosmac-vip",
+            backend::Font::Dream6800 => "font-dream-6800",
+            backend::Font::Eti660 => "font-eti-660",
+        })
+    }
+
+    pub fn format(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+
+        for (name, value) in args {
+            fluent_args.set(*name, value.to_string());
+        }
+
+        self.lookup(id, Some(&fluent_args))
+    }
+
+    pub fn frontend_error(&self, error: &frontend::FrontendError) -> String {
+        match error {
+            frontend::FrontendError::Backend(error) => self.backend_error(error),
+            frontend::FrontendError::Divergence {
+                index,
+                instruction,
+                differences,
+            } => self.format(
+                "frontend-error-divergence",
+                &[
+                    ("instruction", instruction),
+                    ("address", &format!("0x{:03x}", index)),
+                    ("differences", &differences.join(", ")),
+                ],
+            ),
+            frontend::FrontendError::Halted(address) => self.format(
+                "frontend-error-halted",
+                &[("address", &format!("0x{:03x}", address))],
+            ),
+            frontend::FrontendError::SelfModification(address) => self.format(
+                "frontend-error-self-modification",
+                &[("address", &format!("0x{:03x}", address))],
+            ),
+            frontend::FrontendError::Stalled { index, seconds } => self.format(
+                "frontend-error-stalled",
+                &[
+                    ("address", &format!("0x{:03x}", index)),
+                    ("seconds", seconds),
+                ],
+            ),
+            error => error.to_string(),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, id: &str) -> String {
+        self.lookup(id, None)
+    }
+
+    /// Loads the messages of `language` from its locale file in `directory`
+    pub fn new(language: &str, directory: Option<&path::Path>) -> Result<Self, String> {
+        let mut localizer = Self::default();
+
+        if language == DEFAULT_LANGUAGE {
+            return Ok(localizer);
+        }
+
+        let path = directory
+            .ok_or_else(|| format!("no locale file for '{}'", language))?
+            .join(language)
+            .with_extension(LOCALE_EXTENSION);
+
+        let messages = fs::read_to_string(&path).map_err(|error| format!("{}", error))?;
+        let resource = FluentResource::try_new(messages)
+            .map_err(|(_, errors)| format!("invalid locale file, {}", errors[0]))?;
+
+        let mut bundle = new_bundle(language)?;
+        bundle
+            .add_resource(resource)
+            .map_err(|errors| format!("invalid locale file, {}", errors[0]))?;
+
+        let _ = localizer.bundle.insert(bundle);
+
+        Ok(localizer)
+    }
+
+    pub fn key_priority(&self, priority: backend::KeyPriority) -> String {
+        self.get(match priority {
+            backend::KeyPriority::LowestIndex => "key-priority-lowest-index",
+            backend::KeyPriority::MostRecent => "key-priority-most-recent",
+        })
+    }
+
+    pub fn load_error(&self, error: &loader::LoadError) -> String {
+        match error {
+            loader::LoadError::FontSize(size) => self.format(
+                "load-error-font-size",
+                &[("size", size), ("expected", &backend::FONT_SIZE)],
+            ),
+            loader::LoadError::IO(error) => format!("{}", error),
+            loader::LoadError::NotFound(path) => self.format(
+                "error-file-not-found",
+                &[(
+                    "file",
+                    &path
+                        .file_name()
+                        .and_then(|file_name| file_name.to_str())
+                        .unwrap_or_default(),
+                )],
+            ),
+            loader::LoadError::NotARom => self.get("load-error-not-a-rom"),
+            loader::LoadError::OddLength(size) => {
+                self.format("load-error-odd-length", &[("size", size)])
+            }
+            loader::LoadError::ProgramSize { size, space } => self.format(
+                "load-error-program-size",
+                &[("size", size), ("space", space)],
+            ),
+        }
+    }
+
+    pub fn menu_tab(&self, tab: ui::MenuTab) -> String {
+        self.get(match tab {
+            ui::MenuTab::Program => "menu-tab-program",
+            ui::MenuTab::Display => "menu-tab-display",
+            ui::MenuTab::Audio => "menu-tab-audio",
+            ui::MenuTab::Input => "menu-tab-input",
+            ui::MenuTab::Quirks => "menu-tab-quirks",
+            ui::MenuTab::Debug => "menu-tab-debug",
+        })
+    }
+
+    /// Describes a reference of the kind `kind` from the instruction at `address`
+    pub fn reference(&self, kind: disassembler::ReferenceKind, address: &str) -> String {
+        self.format(
+            match kind {
+                disassembler::ReferenceKind::Call => "reference-call",
+                disassembler::ReferenceKind::Jump => "reference-jump",
+                disassembler::ReferenceKind::Load => "reference-load",
+            },
+            &[("address", &address)],
+        )
+    }
+
+    pub fn rotation(&self, rotation: ui::Rotation) -> String {
+        self.get(match rotation {
+            ui::Rotation::None => "rotation-none",
+            ui::Rotation::Quarter => "rotation-quarter",
+            ui::Rotation::Half => "rotation-half",
+            ui::Rotation::ThreeQuarters => "rotation-three-quarters",
+        })
+    }
+
+    pub fn scaling_filter(&self, filter: frontend::ScalingFilter) -> String {
+        self.get(match filter {
+            frontend::ScalingFilter::Nearest => "scaling-filter-nearest",
+            frontend::ScalingFilter::Bilinear => "scaling-filter-bilinear",
+            frontend::ScalingFilter::Prescaled => "scaling-filter-prescaled",
+        })
+    }
+
+    pub fn stretch(&self, stretch: ui::Stretch) -> String {
+        self.get(match stretch {
+            ui::Stretch::Fit => "stretch-fit",
+            ui::Stretch::Fill => "stretch-fill",
+            ui::Stretch::FourThirds => "stretch-four-thirds",
+        })
+    }
+
+    pub fn test_pattern(&self, pattern: ui::TestPattern) -> String {
+        self.get(match pattern {
+            ui::TestPattern::Checkerboard => "test-pattern-checkerboard",
+            ui::TestPattern::Noise => "test-pattern-noise",
+        })
+    }
+
+    pub fn theme(&self, theme: ui::Theme) -> String {
+        self.get(match theme {
+            ui::Theme::Default => "theme-default",
+            ui::Theme::HighContrast => "theme-high-contrast",
+            ui::Theme::ColorblindSafe => "theme-colorblind-safe",
+        })
+    }
+
+    fn lookup(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in self.bundle.iter().chain([&self.fallback]) {
+            if let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) {
+                let mut errors = Vec::new();
+
+                return bundle
+                    .format_pattern(pattern, args, &mut errors)
+                    .into_owned();
+            }
+        }
+
+        id.to_owned()
+    }
+}
+
+impl Default for Localizer {
+    fn default() -> Self {
+        let mut fallback = new_bundle(DEFAULT_LANGUAGE).unwrap();
+        fallback
+            .add_resource(FluentResource::try_new(DEFAULT_MESSAGES.to_owned()).unwrap())
+            .unwrap();
+
+        Self {
+            bundle: None,
+            fallback,
+        }
+    }
+}
+
+fn new_bundle(language: &str) -> Result<FluentBundle<FluentResource>, String> {
+    let language = language
+        .parse::<unic_langid::LanguageIdentifier>()
+        .map_err(|error| format!("{}", error))?;
+
+    let mut bundle = FluentBundle::new(vec![language]);
+    bundle.set_use_isolating(false);
+
+    Ok(bundle)
+}
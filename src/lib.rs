@@ -1,4 +1,17 @@
+pub mod assembler;
 pub mod backend;
-mod defaults;
+pub mod config;
+pub mod defaults;
+pub mod disassembler;
 pub mod frontend;
+pub mod golden;
+pub mod graph;
+pub mod headless;
+pub mod i18n;
+pub mod loader;
+pub mod patch;
+#[cfg(feature = "serde")]
+pub mod replay;
+#[cfg(feature = "serde")]
+pub mod save_state;
 pub mod ui;
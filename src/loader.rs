@@ -0,0 +1,127 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path;
+
+use crate::backend;
+
+/// Starts of the kinds of files picked by mistake for a program, e.g. a zipped one
+const SIGNATURES: [&[u8]; 3] = [b"PK\x03\x04", b"\x1F\x8B", b"\x89PNG"];
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The font isn't `backend::FONT_SIZE` bytes long, with its size
+    FontSize(usize),
+    IO(io::Error),
+    /// The file doesn't exist, with its path
+    NotFound(path::PathBuf),
+    /// The program is empty, or is an archive or an image
+    NotARom,
+    /// The program has an odd number of bytes, with its size
+    OddLength(usize),
+    /// The program doesn't fit between the load address and the end of the memory, with its size
+    /// and the space there is
+    ProgramSize {
+        size: usize,
+        space: usize,
+    },
+}
+
+/// Checks that `program` fits the memory with `quirks` and is made of whole instructions
+pub fn check_program(program: &[u8], quirks: &backend::Quirks) -> Result<(), LoadError> {
+    let size = match quirks.extended_memory {
+        true => backend::EXTENDED_MEMORY_SIZE,
+        false => backend::MEMORY_SIZE,
+    };
+    let space = size.checked_sub(quirks.load_address);
+
+    // a load address past the end of the memory leaves no room even for an empty program
+    if space.is_none_or(|space| program.len() > space) {
+        return Err(LoadError::ProgramSize {
+            size: program.len(),
+            space: space.unwrap_or_default(),
+        });
+    }
+
+    match program.len().is_multiple_of(2) {
+        true => Ok(()),
+        false => Err(LoadError::OddLength(program.len())),
+    }
+}
+
+/// Reads the font at `path`
+pub fn font(path: &path::Path) -> Result<Box<[u8; backend::FONT_SIZE]>, LoadError> {
+    let font = read(path)?;
+    let size = font.len();
+
+    font.into_boxed_slice()
+        .try_into()
+        .map_err(|_| LoadError::FontSize(size))
+}
+
+/// Reads the program at `path` and checks it can be loaded with `quirks`, for the programs that
+/// aren't patched
+pub fn program(path: &path::Path, quirks: &backend::Quirks) -> Result<Vec<u8>, LoadError> {
+    let program = read_program(path)?;
+    check_program(&program, quirks)?;
+
+    Ok(program)
+}
+
+/// Reads the file at `path`, telling a missing one apart
+pub fn read(path: &path::Path) -> Result<Vec<u8>, LoadError> {
+    fs::read(path).map_err(|error| match error.kind() {
+        io::ErrorKind::NotFound => LoadError::NotFound(path.to_owned()),
+        _ => LoadError::IO(error),
+    })
+}
+
+/// Reads the program at `path`, checking it looks like one, but not whether it fits the memory,
+/// which is left to `check_program` once it's patched
+pub fn read_program(path: &path::Path) -> Result<Vec<u8>, LoadError> {
+    let program = read(path)?;
+
+    match program.is_empty()
+        || SIGNATURES
+            .iter()
+            .any(|signature| program.starts_with(signature))
+    {
+        true => Err(LoadError::NotARom),
+        false => Ok(program),
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FontSize(size) => write!(
+                f,
+                "the font is {} bytes long instead of {}",
+                size,
+                backend::FONT_SIZE
+            ),
+            Self::IO(error) => write!(f, "{}", error),
+            Self::NotFound(path) => write!(f, "file '{}' does not exist", path.display()),
+            Self::NotARom => write!(f, "not a program"),
+            Self::OddLength(size) => write!(
+                f,
+                "the program is {} bytes long, an odd number, its last instruction is cut",
+                size
+            ),
+            Self::ProgramSize { size, space } => write!(
+                f,
+                "the program is {} bytes long, more than the {} bytes after its load address",
+                size, space
+            ),
+        }
+    }
+}
+
+impl error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        Self::IO(error)
+    }
+}
@@ -1,27 +1,672 @@
-use clap::Parser;
+use std::fs;
+use std::num;
+use std::path;
+use std::process;
+use std::time;
+
+use clap::{Parser, Subcommand};
+
+use rc_8::{assembler, backend, config, defaults, disassembler, golden, headless, loader, ui};
+#[cfg(feature = "serde")]
+use rc_8::{replay, save_state};
+
+/// Exit code of a run without a window that stalled or exceeded its limits, the one of timeout(1)
+const TIMEOUT_EXIT_CODE: i32 = 124;
 
 #[derive(Parser)]
 #[command(about, author, version)]
 struct Options {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Keep the window above the other ones
+    #[arg(long)]
+    always_on_top: bool,
+
+    /// Name of the audio output device to play the sound on instead of the default one
+    #[arg(long)]
+    audio_device: Option<String>,
+
+    /// Audio file to play while the sound timer is active instead of the built-in beep, e.g. an
+    /// Ogg Vorbis, WAV or MP3 file
+    #[arg(long)]
+    beep_sound: Option<path::PathBuf>,
+
+    /// Path of the configuration file to use instead of the default one
+    #[arg(long)]
+    config: Option<path::PathBuf>,
+
+    /// Instruction that suspends the program into the debugger, in hexadecimal, e.g. 0000
+    #[arg(long, value_parser = parse_word)]
+    debug_break: Option<u16>,
+
     /// Run in debugger mode
     #[arg(long = "debugger")]
     debug_mode: bool,
 
+    /// Fade out the pixels that are turned off
+    #[arg(long)]
+    fade_effect: bool,
+
+    /// Size and position of the window in points, e.g. 800x400+100+50 or 800x400
+    #[arg(long)]
+    geometry: Option<config::WindowGeometry>,
+
+    /// Show a hash of the display in its corner, to compare the frames of two runs
+    #[arg(long)]
+    hash_overlay: bool,
+
+    /// Print the errors of the program as JSON objects with a stable numeric code
+    #[arg(long)]
+    json_errors: bool,
+
+    /// Bind keypad keys to physical keys, e.g. 1=Num1,2=Num2,F=V
+    #[arg(long)]
+    keymap: Option<config::KeyBindings>,
+
+    /// Pause the program when it's stuck in a loop it can't leave, e.g. a jump to itself
+    #[arg(long)]
+    pause_on_halt: bool,
+
+    /// Map the pseudo-peripherals to the end of the memory, see the README
+    #[arg(long)]
+    peripherals: bool,
+
+    /// Play the beep higher the shorter the sound timer was set and lower the longer
+    #[arg(long)]
+    pitched_beep: bool,
+
+    /// Seed of the random numbers of the program, for its runs to be reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Run the emulation in the user interface thread instead of a thread of its own
+    #[arg(long)]
+    single_threaded: bool,
+
+    /// Number of instructions to execute per tick (60 ticks per second)
+    #[arg(long)]
+    speed: Option<num::NonZeroU16>,
+
+    /// Warn when the program changed neither its display, its memory nor its registers for this
+    /// many seconds of emulated time, 0 to never warn
+    #[arg(long = "stall-warning")]
+    stall_warning_seconds: Option<u16>,
+
+    /// Steps per second while the step hotkey is held in debug mode, 0 to step once per press
+    #[arg(long)]
+    step_repeat_rate: Option<u16>,
+
+    /// Run a reference copy of the program in lockstep and stop at the first divergence, the
+    /// reference uses the verification quirks of the configuration file or the default ones
+    #[arg(long)]
+    verify: bool,
+
+    /// Outline the display while the sound timer is active
+    #[arg(long)]
+    visual_beep: bool,
+
     /// Wrap the sprites drawn beyond the edge of the screen, (clips/crops them by default)
     #[arg(long)]
     wrap_sprites: bool,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Assemble source written like the one of `disasm --source` into a program
+    Asm {
+        /// Path of the source
+        source: path::PathBuf,
+
+        /// Path of the program, the one of the source with the ch8 extension by default
+        #[arg(long, short)]
+        output: Option<path::PathBuf>,
+    },
+
+    /// Branch a replay from a state saved along it, keeping the keys held then and dropping the
+    /// inputs after it, and record new inputs from there
+    #[cfg(feature = "serde")]
+    Branch {
+        /// Path of the replay
+        replay: path::PathBuf,
+
+        /// Path of the state to branch from, e.g. one saved by `run --save-state`
+        state: path::PathBuf,
+
+        /// Path of the inputs to record from the state on, written like the .inputs files with
+        /// their ticks counted from the state
+        #[arg(long)]
+        inputs: Option<path::PathBuf>,
+
+        /// Path of the new replay, the one of the replay by default
+        #[arg(long, short)]
+        output: Option<path::PathBuf>,
+    },
+
+    /// Print the disassembly of a program, the sprites it draws taken for data
+    Disasm {
+        /// Path of the program
+        program: path::PathBuf,
+
+        /// Address the program is loaded at, in hexadecimal, e.g. 600 for ETI-660 programs
+        #[arg(long, value_parser = parse_word)]
+        load_address: Option<u16>,
+
+        /// Print source that `asm` assembles back into the same program, with labels
+        #[arg(long)]
+        source: bool,
+    },
+
+    /// Record the displays of reproducible runs of programs next to them, holding the keys read
+    /// from their .inputs files if they have some
+    RecordGolden {
+        /// Paths of the programs
+        #[arg(required = true)]
+        programs: Vec<path::PathBuf>,
+
+        /// Seed of the random numbers of the runs
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// Number of ticks to run the programs for (60 ticks per second)
+        #[arg(long, default_value_t = 600)]
+        ticks: u64,
+    },
+
+    /// Run a program without a window until it fails, stalls or exceeds the limits, holding the
+    /// keys read from its .inputs file if it has one, and print the state it was stopped in
+    ///
+    /// Exits with 0 when the program ends itself, with the code of the error when it fails, and
+    /// with 124 when it stalls or exceeds a limit.
+    Run {
+        /// Path of the program
+        program: path::PathBuf,
+
+        /// Path of an image to save the display at the end to as it is, without scaling or
+        /// effects, a PPM file when its extension is ppm and a PNG file otherwise
+        #[arg(long)]
+        dump_display: Option<path::PathBuf>,
+
+        /// Print the report as a JSON object instead of a line of text
+        #[arg(long)]
+        json: bool,
+
+        /// Path of a save state to start from instead of the start of the program
+        #[cfg(feature = "serde")]
+        #[arg(long)]
+        load_state: Option<path::PathBuf>,
+
+        /// Number of ticks after which the program is stopped (60 ticks per second)
+        #[arg(long)]
+        max_frames: Option<u64>,
+
+        /// Path of a file to save a replay of the run to, for `branch` to branch it
+        #[cfg(feature = "serde")]
+        #[arg(long)]
+        record_replay: Option<path::PathBuf>,
+
+        /// Path of a replay to play instead of the .inputs file, from its state if it has one
+        #[cfg(feature = "serde")]
+        #[arg(long, conflicts_with_all = ["load_state", "seed", "speed"])]
+        replay: Option<path::PathBuf>,
+
+        /// Path of a file to write the report to as a JSON object as well
+        #[arg(long)]
+        report: Option<path::PathBuf>,
+
+        /// Path of a file to save the state the program was stopped in to
+        #[cfg(feature = "serde")]
+        #[arg(long)]
+        save_state: Option<path::PathBuf>,
+
+        /// Seed of the random numbers of the run
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Number of instructions to execute per tick (60 ticks per second)
+        #[arg(long, default_value_t = defaults::INSTRUCTIONS_PER_TICK)]
+        speed: num::NonZeroU16,
+
+        /// Seconds of emulated time without a change of the display, the memory or the registers
+        /// after which the program is taken for stuck, 0 to never take it for stuck
+        #[arg(long, default_value_t = 10)]
+        stall_seconds: u16,
+
+        /// Seconds of real time after which the program is stopped
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+
+    /// Save the sprites found in a program as a PNG sheet next to it
+    Sprites {
+        /// Path of the program
+        program: path::PathBuf,
+
+        /// Address the program is loaded at, in hexadecimal, e.g. 600 for ETI-660 programs
+        #[arg(long, value_parser = parse_word)]
+        load_address: Option<u16>,
+    },
+
+    /// Run programs again the way their displays were recorded and check they're the same
+    VerifyGolden {
+        /// Paths of the programs
+        #[arg(required = true)]
+        programs: Vec<path::PathBuf>,
+    },
+}
+
 fn main() {
     let options = Options::parse();
 
+    if let Some(command) = options.command {
+        if let Err(error) = run_command(command) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    let config_path = options.config.or_else(config::ConfigFile::default_path);
+
+    // a file that couldn't be loaded isn't saved over on exit
+    let (config_file, config_path) = match config_path {
+        Some(path) => match config::ConfigFile::load(&path) {
+            Ok(config_file) => (config_file, Some(path)),
+            Err(error) => {
+                eprintln!("couldn't load the configuration file, {}", error);
+                (config::ConfigFile::default(), None)
+            }
+        },
+        None => (config::ConfigFile::default(), None),
+    };
+
+    let keymap = options.keymap.map(|bindings| {
+        let mut keymap = config_file.config.keymap.clone();
+        keymap.apply(&bindings);
+
+        keymap
+    });
+
+    let verify_quirks = options
+        .verify
+        .then(|| config_file.config.verify_quirks.unwrap_or_default());
+
+    let mut builder = config::EmulatorConfigBuilder::from(config_file.config.clone());
+
+    if options.always_on_top {
+        builder = builder.always_on_top(true);
+    }
+
+    if let Some(audio_device) = options.audio_device {
+        builder = builder.audio_device(Some(audio_device));
+    }
+
+    if let Some(beep_sound) = options.beep_sound {
+        builder = builder.beep_sound(Some(beep_sound));
+    }
+
+    if let Some(debug_break) = options.debug_break {
+        builder = builder.debug_break(Some(debug_break));
+    }
+
+    if options.debug_mode {
+        builder = builder.debug_mode(true);
+    }
+
+    if options.fade_effect {
+        builder = builder.fade_effect(true);
+    }
+
+    if options.hash_overlay {
+        builder = builder.hash_overlay(true);
+    }
+
+    if options.json_errors {
+        builder = builder.json_errors(true);
+    }
+
+    if let Some(keymap) = keymap {
+        builder = builder.keymap(keymap);
+    }
+
+    if options.pause_on_halt {
+        builder = builder.pause_on_halt(true);
+    }
+
+    if options.peripherals {
+        builder = builder.peripherals(true);
+    }
+
+    if options.pitched_beep {
+        builder = builder.pitched_beep(true);
+    }
+
+    if let Some(seed) = options.seed {
+        builder = builder.seed(Some(seed));
+    }
+
+    if options.single_threaded {
+        builder = builder.single_threaded(true);
+    }
+
+    if let Some(speed) = options.speed {
+        builder = builder.instructions_per_tick(speed);
+    }
+
+    if let Some(stall_warning_seconds) = options.stall_warning_seconds {
+        builder = builder.stall_warning_seconds(stall_warning_seconds);
+    }
+
+    if let Some(step_repeat_rate) = options.step_repeat_rate {
+        builder = builder.step_repeat_rate(step_repeat_rate);
+    }
+
+    if let Some(verify_quirks) = verify_quirks {
+        builder = builder.verify_quirks(Some(verify_quirks));
+    }
+
+    if options.visual_beep {
+        builder = builder.visual_beep(true);
+    }
+
+    if options.wrap_sprites {
+        builder = builder.wrap_sprites(true);
+    }
+
+    if let Some(geometry) = options.geometry {
+        builder = builder.window_geometry(Some(geometry));
+    }
+
+    let config = builder.build();
+
     eframe::run_native(
         "RC-8",
         eframe::NativeOptions {
+            always_on_top: config.always_on_top,
             drag_and_drop_support: false,
+            initial_window_pos: config
+                .window_geometry
+                .and_then(|geometry| geometry.position()),
+            initial_window_size: config.window_geometry.map(|geometry| geometry.size()),
             run_and_return: false,
             ..Default::default()
         },
-        Box::new(move |cc| Box::new(rc_8::ui::App::new(cc, rc_8::frontend::Options { debug_mode: options.debug_mode, wrap_sprites: options.wrap_sprites, ..Default::default() }))),
+        Box::new(move |cc| Box::new(rc_8::ui::App::new(cc, config_file, config, config_path))),
     );
 }
+
+fn run_command(command: Command) -> Result<(), String> {
+    match command {
+        Command::Asm { source, output } => {
+            let text = fs::read_to_string(&source)
+                .map_err(|error| format!("couldn't read the source, {}", error))?;
+            let program = assembler::assemble(&text)
+                .map_err(|error| format!("couldn't assemble the source, {}", error))?;
+            let output = output.unwrap_or_else(|| source.with_extension("ch8"));
+
+            fs::write(&output, program)
+                .map_err(|error| format!("couldn't save the program, {}", error))?;
+            println!("Saved the program to {}", output.display());
+        }
+
+        #[cfg(feature = "serde")]
+        Command::Branch {
+            replay,
+            state,
+            inputs,
+            output,
+        } => {
+            let state = save_state::SaveState::load(&state)
+                .map_err(|error| format!("couldn't load the state, {}", error))?;
+            let mut branch = replay::Replay::load(&replay)
+                .and_then(|replay| replay.branch(state))
+                .map_err(|error| format!("couldn't branch the replay, {}", error))?;
+
+            if let Some(path) = inputs {
+                branch.record(
+                    &golden::read_inputs(&path)
+                        .map_err(|error| format!("couldn't read the inputs, {}", error))?,
+                );
+            }
+
+            let output = output.unwrap_or(replay);
+
+            branch
+                .save(&output)
+                .map_err(|error| format!("couldn't save the replay, {}", error))?;
+            println!("Saved the replay to {}", output.display());
+        }
+
+        Command::Disasm {
+            program,
+            load_address,
+            source,
+        } => {
+            let program = fs::read(&program)
+                .map_err(|error| format!("couldn't read the program, {}", error))?;
+            let load_address = load_address.map_or(backend::MEMORY_PADDING, usize::from);
+
+            let data: Vec<std::ops::Range<usize>> = ui::sprites::find(&program, load_address)
+                .iter()
+                .map(|sprite| sprite.address..sprite.address + sprite.size())
+                .collect();
+
+            let mut memory = vec![0; load_address];
+            memory.extend(&program);
+
+            let lines =
+                disassembler::disassemble(
+                    &memory,
+                    load_address..memory.len(),
+                    |address| match data.iter().any(|range| range.contains(&address)) {
+                        true => disassembler::Region::Data,
+                        false => disassembler::Region::Code,
+                    },
+                );
+
+            match source {
+                true => print!("{}", disassembler::source(&lines)),
+                false => {
+                    for line in lines {
+                        let bytes: String = line
+                            .bytes
+                            .iter()
+                            .map(|byte| format!("{:02X}", byte))
+                            .collect();
+
+                        println!("0x{:03X}  {:<4}  {}", line.address, bytes, line.text);
+                    }
+                }
+            }
+        }
+
+        Command::RecordGolden {
+            programs,
+            seed,
+            ticks,
+        } => {
+            for path in programs {
+                let program = loader::program(&path, &backend::Quirks::default())
+                    .map_err(|error| format!("couldn't load {}, {}", path.display(), error))?;
+                let inputs = golden::load_inputs(&path).map_err(|error| {
+                    format!("couldn't read the inputs of {}, {}", path.display(), error)
+                })?;
+                let golden_path = golden::golden_path(&path);
+
+                golden::Golden::record(&program, inputs, seed, ticks)
+                    .and_then(|golden| golden.save(&golden_path))
+                    .map_err(|error| format!("couldn't record {}, {}", path.display(), error))?;
+                println!("Saved the recording to {}", golden_path.display());
+            }
+        }
+
+        Command::Run {
+            program,
+            dump_display,
+            json,
+            #[cfg(feature = "serde")]
+            load_state,
+            max_frames,
+            #[cfg(feature = "serde")]
+            record_replay,
+            #[cfg(feature = "serde")]
+            replay,
+            report: report_path,
+            #[cfg(feature = "serde")]
+            save_state,
+            seed,
+            speed,
+            stall_seconds,
+            timeout,
+        } => {
+            let contents = loader::program(&program, &backend::Quirks::default())
+                .map_err(|error| format!("couldn't load the program, {}", error))?;
+
+            let mut options = headless::Options {
+                inputs: Vec::new(),
+                instructions_per_tick: speed,
+                max_frames,
+                seed,
+                stall_seconds,
+                #[cfg(feature = "serde")]
+                state: load_state
+                    .map(|path| save_state::SaveState::load(&path))
+                    .transpose()
+                    .map_err(|error| format!("couldn't load the state, {}", error))?,
+                timeout: timeout.map(time::Duration::from_secs),
+            };
+
+            #[cfg(feature = "serde")]
+            let replay = replay
+                .map(|path| replay::Replay::load(&path))
+                .transpose()
+                .map_err(|error| format!("couldn't load the replay, {}", error))?;
+
+            #[cfg(feature = "serde")]
+            if let Some(replay) = &replay {
+                options.inputs = replay.relative_inputs();
+                options.instructions_per_tick = replay.instructions_per_tick;
+                options.seed = replay.seed;
+                options.state = replay.state.clone();
+            }
+
+            #[cfg(feature = "serde")]
+            let replayed = replay.is_some();
+            #[cfg(not(feature = "serde"))]
+            let replayed = false;
+
+            if !replayed {
+                options.inputs = golden::load_inputs(&program)
+                    .map_err(|error| format!("couldn't read the inputs, {}", error))?;
+            }
+
+            // a recorded run from the start of the program needs a seed to be played again the
+            // same way
+            #[cfg(feature = "serde")]
+            if record_replay.is_some() && options.state.is_none() && options.seed.is_none() {
+                options.seed = Some(rand::random());
+            }
+
+            let report = headless::run(&contents, &options)
+                .map_err(|error| format!("{}: {}", program.display(), error))?;
+
+            #[cfg(feature = "serde")]
+            if let Some(path) = record_replay {
+                let mut recording = replay::Replay::new(
+                    options.instructions_per_tick,
+                    options.seed,
+                    options.state.clone(),
+                );
+                recording.record(&options.inputs);
+                recording
+                    .save(&path)
+                    .map_err(|error| format!("couldn't save the replay, {}", error))?;
+            }
+
+            if let Some(path) = dump_display {
+                report
+                    .display
+                    .to_image()
+                    .save(&path)
+                    .map_err(|error| format!("couldn't save the display, {}", error))?;
+            }
+
+            #[cfg(feature = "serde")]
+            if let Some(path) = save_state {
+                save_state::SaveState {
+                    backend: report.state.clone(),
+                    display: report.display.clone(),
+                }
+                .save(&path)
+                .map_err(|error| format!("couldn't save the state, {}", error))?;
+            }
+
+            if let Some(path) = report_path {
+                fs::write(&path, report.to_json())
+                    .map_err(|error| format!("couldn't save the report, {}", error))?;
+            }
+
+            match json {
+                true => println!("{}", report.to_json()),
+                false => println!("{}: {}", program.display(), report),
+            }
+
+            // every run without a window ends with an error, an exit or a limit, a stuck program
+            // being one
+            process::exit(match report.outcome {
+                headless::Outcome::Error(error) => error.code() as i32,
+                headless::Outcome::Exited => 0,
+                _ => TIMEOUT_EXIT_CODE,
+            });
+        }
+
+        Command::Sprites {
+            program,
+            load_address,
+        } => {
+            let path = ui::sprites::export(
+                &program,
+                load_address.map_or(backend::MEMORY_PADDING, usize::from),
+            )
+            .map_err(|error| format!("couldn't save the sprites, {}", error))?;
+            println!("Saved the sprites to {}", path.display());
+        }
+
+        Command::VerifyGolden { programs } => {
+            let mut diverged = 0;
+
+            for path in programs.iter() {
+                // the program is checked with the quirks it was recorded with
+                let verified = golden::Golden::load(&golden::golden_path(path))
+                    .and_then(|golden| golden.verify(&loader::program(path, &golden.quirks)?));
+
+                match verified {
+                    Ok(None) => println!("{}: ok", path.display()),
+                    Ok(Some(tick)) => {
+                        diverged += 1;
+                        println!("{}: diverged at tick {}", path.display(), tick);
+                    }
+                    Err(error) => {
+                        diverged += 1;
+                        println!("{}: {}", path.display(), error);
+                    }
+                }
+            }
+
+            if diverged > 0 {
+                return Err(format!(
+                    "{} of {} programs didn't match their recordings",
+                    diverged,
+                    programs.len()
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_word(s: &str) -> Result<u16, num::ParseIntError> {
+    u16::from_str_radix(s.trim_start_matches("0x"), 16)
+}
@@ -0,0 +1,158 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path;
+
+use crate::backend;
+
+const IPS_HEADER: &[u8] = b"PATCH";
+const IPS_FOOTER: &[u8] = b"EOF";
+
+/// Changes to the bytes of a program, read from an IPS file or from a text file with lines of an
+/// offset and the bytes to write at it in hexadecimal, e.g. `1A4: 12 04`
+pub struct Patch {
+    /// Positions of the records in the patch as told by `PatchError::Invalid`, along with the
+    /// offsets in the program file and the bytes to write at them
+    records: Vec<(usize, usize, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub enum PatchError {
+    /// The patch is malformed at the line of a text patch or the offset of an IPS one
+    Invalid(usize),
+    IO(io::Error),
+}
+
+impl Patch {
+    /// Writes the records of the patch to `program`, extending it when they're past its end, up to
+    /// the size of the largest memory
+    pub fn apply(&self, program: &mut Vec<u8>) -> Result<(), PatchError> {
+        for (position, offset, bytes) in &self.records {
+            let end = offset
+                .checked_add(bytes.len())
+                .filter(|end| *end <= backend::EXTENDED_MEMORY_SIZE)
+                .ok_or(PatchError::Invalid(*position))?;
+
+            if program.len() < end {
+                program.resize(end, 0);
+            }
+
+            program[*offset..end].copy_from_slice(bytes);
+        }
+
+        Ok(())
+    }
+
+    pub fn load(path: &path::Path) -> Result<Self, PatchError> {
+        let contents = fs::read(path)?;
+
+        match contents.starts_with(IPS_HEADER) {
+            true => Self::parse_ips(&contents),
+            false => Self::parse_text(&String::from_utf8_lossy(&contents)),
+        }
+    }
+
+    fn parse_ips(contents: &[u8]) -> Result<Self, PatchError> {
+        let mut records = Vec::new();
+        let mut position = IPS_HEADER.len();
+
+        // reads `n` bytes as a big endian number
+        let read = |position: &mut usize, n: usize| {
+            let bytes = contents
+                .get(*position..*position + n)
+                .ok_or(PatchError::Invalid(*position))?;
+            *position += n;
+
+            Ok::<usize, PatchError>(
+                bytes
+                    .iter()
+                    .fold(0, |number, byte| number << u8::BITS | *byte as usize),
+            )
+        };
+
+        while contents.get(position..position + IPS_FOOTER.len()) != Some(IPS_FOOTER) {
+            let start = position;
+            let offset = read(&mut position, 3)?;
+            let size = read(&mut position, 2)?;
+
+            // a record without a size repeats a byte
+            let bytes = match size {
+                0 => {
+                    let count = read(&mut position, 2)?;
+                    vec![read(&mut position, 1)? as u8; count]
+                }
+                _ => {
+                    let bytes = contents
+                        .get(position..position + size)
+                        .ok_or(PatchError::Invalid(position))?;
+                    position += size;
+
+                    bytes.to_vec()
+                }
+            };
+
+            records.push((start, offset, bytes));
+        }
+
+        Ok(Self { records })
+    }
+
+    fn parse_text(contents: &str) -> Result<Self, PatchError> {
+        let mut records = Vec::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || PatchError::Invalid(number + 1);
+
+            let (offset, bytes) = line.split_once(':').ok_or_else(invalid)?;
+            let offset = usize::from_str_radix(offset.trim().trim_start_matches("0x"), 16)
+                .map_err(|_| invalid())?;
+            let digits: String = bytes.split_whitespace().collect();
+
+            if digits.is_empty() || !digits.len().is_multiple_of(2) || !digits.is_ascii() {
+                return Err(invalid());
+            }
+
+            let bytes = (0..digits.len())
+                .step_by(2)
+                .map(|index| u8::from_str_radix(&digits[index..index + 2], 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|_| invalid())?;
+
+            // no program is that large, the offset would only grow it to be rejected later
+            if offset
+                .checked_add(bytes.len())
+                .is_none_or(|end| end > backend::EXTENDED_MEMORY_SIZE)
+            {
+                return Err(invalid());
+            }
+
+            records.push((number + 1, offset, bytes));
+        }
+
+        Ok(Self { records })
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(position) => write!(f, "invalid patch at {}", position),
+            Self::IO(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for PatchError {}
+
+impl From<io::Error> for PatchError {
+    fn from(error: io::Error) -> Self {
+        Self::IO(error)
+    }
+}
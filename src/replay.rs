@@ -0,0 +1,253 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::num;
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::golden;
+use crate::save_state::{SaveState, SaveStateError};
+
+/// The keys held along a run from the start of the program or from a save state, for the run to be
+/// played again and branched from any of its states
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Replay {
+    /// Keys held from a tick on, the ticks counted from the load of the program
+    pub inputs: Vec<golden::Input>,
+    pub instructions_per_tick: num::NonZeroU16,
+    /// Seed of the random numbers of a run from the start of the program, a state carries its own
+    pub seed: Option<u64>,
+    /// State the run starts from, with the version of its format for later releases to load it
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "embedded")]
+    pub state: Option<SaveState>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The state to branch from is at the tick, before the start of the replay
+    BeforeStart(u64),
+    IO(io::Error),
+    Json(serde_json::Error),
+    SaveState(SaveStateError),
+}
+
+impl Replay {
+    pub fn new(
+        instructions_per_tick: num::NonZeroU16,
+        seed: Option<u64>,
+        state: Option<SaveState>,
+    ) -> Self {
+        Self {
+            inputs: Vec::new(),
+            instructions_per_tick,
+            seed,
+            state,
+        }
+    }
+
+    pub fn load(path: &path::Path) -> Result<Self, ReplayError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &path::Path) -> Result<(), ReplayError> {
+        Ok(fs::write(path, serde_json::to_vec(self)?)?)
+    }
+
+    /// Returns a replay starting from `state`, a state reached along this one, with the keys held
+    /// when it was saved and without the inputs after it, for a new run to be recorded from there
+    pub fn branch(&self, state: SaveState) -> Result<Self, ReplayError> {
+        let tick = state.backend.frames();
+
+        if tick < self.start() {
+            return Err(ReplayError::BeforeStart(tick));
+        }
+
+        let keys = self
+            .inputs
+            .iter()
+            .take_while(|input| input.tick < tick)
+            .last()
+            .map_or(0, |input| input.keys);
+
+        Ok(Self {
+            inputs: vec![golden::Input { keys, tick }],
+            instructions_per_tick: self.instructions_per_tick,
+            seed: self.seed,
+            state: Some(state),
+        })
+    }
+
+    /// Records `inputs` of a run from the start of the replay, in place of the ones from the first
+    /// of them on
+    pub fn record(&mut self, inputs: &[golden::Input]) {
+        let start = self.start();
+
+        if let Some(first) = inputs.first() {
+            self.inputs.retain(|input| input.tick < start + first.tick);
+        }
+
+        self.inputs.extend(inputs.iter().map(|input| golden::Input {
+            keys: input.keys,
+            tick: start + input.tick,
+        }));
+    }
+
+    /// Returns the inputs with their ticks counted from the start of the replay, the way a run
+    /// from it counts them
+    pub fn relative_inputs(&self) -> Vec<golden::Input> {
+        let start = self.start();
+
+        self.inputs
+            .iter()
+            .filter(|input| input.tick >= start)
+            .map(|input| golden::Input {
+                keys: input.keys,
+                tick: input.tick - start,
+            })
+            .collect()
+    }
+
+    /// Returns the tick the replay starts at, the one of its state or the load of the program
+    pub fn start(&self) -> u64 {
+        self.state
+            .as_ref()
+            .map_or(0, |state| state.backend.frames())
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BeforeStart(tick) => write!(
+                f,
+                "the state is at tick {}, before the start of the replay",
+                tick
+            ),
+            Self::IO(error) => write!(f, "{}", error),
+            Self::Json(error) => write!(f, "invalid replay, {}", error),
+            Self::SaveState(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        Self::IO(error)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl From<SaveStateError> for ReplayError {
+    fn from(error: SaveStateError) -> Self {
+        Self::SaveState(error)
+    }
+}
+
+/// Serializes the state of a replay the way save state files are, with the version of their format
+mod embedded {
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::save_state::SaveState;
+
+    pub fn serialize<S: Serializer>(
+        state: &Option<SaveState>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        state
+            .as_ref()
+            .map(SaveState::to_value)
+            .transpose()
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SaveState>, D::Error> {
+        Option::<serde_json::Value>::deserialize(deserializer)?
+            .map(SaveState::from_value)
+            .transpose()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{self, interfaces};
+
+    fn input(tick: u64, keys: u16) -> golden::Input {
+        golden::Input { keys, tick }
+    }
+
+    /// Returns a state saved `frames` ticks after the load of the program
+    fn state(frames: u64) -> SaveState {
+        let mut backend = serde_json::to_value(backend::Backend::new()).unwrap();
+        backend["frames"] = frames.into();
+
+        SaveState {
+            backend: serde_json::from_value(backend).unwrap(),
+            display: interfaces::DisplayBuffer::new(false),
+        }
+    }
+
+    fn replay(inputs: Vec<golden::Input>) -> Replay {
+        Replay {
+            inputs,
+            ..Replay::new(num::NonZeroU16::MIN, Some(7), None)
+        }
+    }
+
+    #[test]
+    fn branching_keeps_the_held_keys_and_drops_the_later_inputs() {
+        let replay = replay(vec![input(0, 0x1), input(10, 0x2), input(20, 0x4)]);
+        let branch = replay.branch(state(15)).unwrap();
+
+        assert_eq!(branch.start(), 15);
+        assert_eq!(branch.inputs, [input(15, 0x2)]);
+        assert_eq!(branch.relative_inputs(), [input(0, 0x2)]);
+        assert!(matches!(
+            branch.branch(state(5)),
+            Err(ReplayError::BeforeStart(5))
+        ));
+    }
+
+    #[test]
+    fn recording_continues_from_the_start() {
+        let mut branch = replay(vec![input(0, 0x1), input(10, 0x2)])
+            .branch(state(5))
+            .unwrap();
+        branch.record(&[input(3, 0x8), input(4, 0)]);
+
+        assert_eq!(branch.inputs, [input(5, 0x1), input(8, 0x8), input(9, 0)]);
+
+        branch.record(&[input(0, 0x10)]);
+
+        assert_eq!(branch.inputs, [input(5, 0x10)]);
+    }
+
+    #[test]
+    fn the_state_is_embedded_with_its_version() {
+        let mut state = state(5);
+        state.backend.registers.general[0x3] = 0x42;
+
+        let replay = replay(Vec::new()).branch(state).unwrap();
+        let value = serde_json::to_value(&replay).unwrap();
+
+        assert_eq!(value["state"]["version"], crate::save_state::VERSION);
+
+        let replay: Replay = serde_json::from_value(value).unwrap();
+
+        assert_eq!(replay.start(), 5);
+        assert_eq!(replay.state.unwrap().backend.registers.general[0x3], 0x42);
+    }
+}
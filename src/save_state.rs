@@ -0,0 +1,230 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{self, interfaces};
+
+/// Version of the save states written by this release, to be bumped along with a new migration
+/// whenever the serialized state changes
+pub const VERSION: u32 = 1;
+
+/// Migrations of the save states of every version before the current one, the first one turns a
+/// state of version 1 into one of version 2 and so on
+const MIGRATIONS: [Migration; VERSION as usize - 1] = [];
+
+/// Turns the fields of a state into the ones of the next version
+type Migration = fn(&mut serde_json::Map<String, serde_json::Value>);
+
+/// The state of a program and its display, saved to a file that later releases still load
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SaveState {
+    pub backend: backend::Backend,
+    pub display: interfaces::DisplayBuffer,
+}
+
+#[derive(Debug)]
+pub enum SaveStateError {
+    IO(io::Error),
+    Json(serde_json::Error),
+    /// The file isn't a save state, or doesn't tell its version
+    NotAState,
+    /// The state was saved by a later release, with the version
+    Unsupported(u32),
+}
+
+impl SaveState {
+    /// Reads the state from `value`, migrating it from the version it was saved with, for the states
+    /// embedded in other files
+    #[inline]
+    pub fn from_value(value: serde_json::Value) -> Result<Self, SaveStateError> {
+        Self::from_value_with(value, &MIGRATIONS)
+    }
+
+    /// Does what `from_value` does with `migrations` in place of the ones of the release, the
+    /// version after the last of them being the current one
+    fn from_value_with(
+        mut value: serde_json::Value,
+        migrations: &[Migration],
+    ) -> Result<Self, SaveStateError> {
+        let object = value.as_object_mut().ok_or(SaveStateError::NotAState)?;
+        let version = object
+            .remove("version")
+            .and_then(|version| version.as_u64())
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version > 0)
+            .ok_or(SaveStateError::NotAState)?;
+
+        if version as usize > migrations.len() + 1 {
+            return Err(SaveStateError::Unsupported(version));
+        }
+
+        for migrate in &migrations[version as usize - 1..] {
+            migrate(object);
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Loads the state at `path`, migrating it from the version it was saved with
+    #[inline]
+    pub fn load(path: &path::Path) -> Result<Self, SaveStateError> {
+        Self::load_with(path, &MIGRATIONS)
+    }
+
+    /// Does what `load` does with `migrations` in place of the ones of the release
+    fn load_with(path: &path::Path, migrations: &[Migration]) -> Result<Self, SaveStateError> {
+        Self::from_value_with(serde_json::from_slice(&fs::read(path)?)?, migrations)
+    }
+
+    /// Saves the state at `path`, along with the current version
+    pub fn save(&self, path: &path::Path) -> Result<(), SaveStateError> {
+        Ok(fs::write(path, serde_json::to_vec(&self.to_value()?)?)?)
+    }
+
+    /// Returns the state as JSON along with the current version, for `from_value` to read it back
+    pub fn to_value(&self) -> Result<serde_json::Value, SaveStateError> {
+        let mut value = serde_json::to_value(self)?;
+
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".to_owned(), VERSION.into());
+        }
+
+        Ok(value)
+    }
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IO(error) => write!(f, "{}", error),
+            Self::Json(error) => write!(f, "invalid save state, {}", error),
+            Self::NotAState => write!(f, "not a save state"),
+            Self::Unsupported(version) => write!(
+                f,
+                "the state was saved with version {} of the format, newer than the supported {}",
+                version, VERSION
+            ),
+        }
+    }
+}
+
+impl error::Error for SaveStateError {}
+
+impl From<io::Error> for SaveStateError {
+    fn from(error: io::Error) -> Self {
+        Self::IO(error)
+    }
+}
+
+impl From<serde_json::Error> for SaveStateError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Migrations of a format whose version 1 named the display `display_buffer`, and whose
+    /// version 2 named the backend `state`
+    const TEST_MIGRATIONS: [Migration; 2] = [
+        |object| {
+            let display = object.remove("display_buffer").unwrap();
+            object.insert("display".to_owned(), display);
+        },
+        |object| {
+            let backend = object.remove("state").unwrap();
+            object.insert("backend".to_owned(), backend);
+        },
+    ];
+
+    /// Returns a state that isn't a new one, for what's loaded to be told apart from defaults
+    fn state() -> SaveState {
+        let mut state = SaveState {
+            backend: backend::Backend::new(),
+            display: interfaces::DisplayBuffer::new(false),
+        };
+        state.backend.registers.general[0x3] = 0x42;
+        state.display.buffer[5].set(7, true);
+
+        state
+    }
+
+    /// Writes `value` as JSON to a file of its own named after `name` and returns its path
+    fn write(name: &str, value: &serde_json::Value) -> path::PathBuf {
+        let path = std::env::temp_dir().join(format!("rc-8-save-state-{}.json", name));
+        fs::write(&path, serde_json::to_vec(value).unwrap()).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn migrates_a_state_of_the_first_version() {
+        let expected = state();
+        let value = serde_json::json!({
+            "version": 1,
+            "state": serde_json::to_value(&expected.backend).unwrap(),
+            "display_buffer": serde_json::to_value(&expected.display).unwrap(),
+        });
+
+        let state = SaveState::load_with(&write("first", &value), &TEST_MIGRATIONS).unwrap();
+
+        assert_eq!(
+            state.backend.registers.general,
+            expected.backend.registers.general
+        );
+        assert_eq!(state.display.buffer, expected.display.buffer);
+    }
+
+    #[test]
+    fn migrates_a_state_from_its_own_version_only() {
+        let expected = state();
+        // the migration to version 2 would fail to find `display_buffer`
+        let value = serde_json::json!({
+            "version": 2,
+            "state": serde_json::to_value(&expected.backend).unwrap(),
+            "display": serde_json::to_value(&expected.display).unwrap(),
+        });
+
+        let state = SaveState::load_with(&write("second", &value), &TEST_MIGRATIONS).unwrap();
+
+        assert_eq!(
+            state.backend.registers.general,
+            expected.backend.registers.general
+        );
+        assert_eq!(state.display.buffer, expected.display.buffer);
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_state() {
+        for (name, value) in [
+            ("array", serde_json::json!([1, 2, 3])),
+            (
+                "unversioned",
+                serde_json::json!({ "backend": {}, "display": {} }),
+            ),
+            ("version-0", serde_json::json!({ "version": 0 })),
+            ("version-text", serde_json::json!({ "version": "1" })),
+        ] {
+            assert!(matches!(
+                SaveState::load(&write(name, &value)),
+                Err(SaveStateError::NotAState)
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_a_state_of_a_later_version() {
+        let value = serde_json::json!({ "version": VERSION + 1 });
+
+        assert!(matches!(
+            SaveState::load(&write("later", &value)),
+            Err(SaveStateError::Unsupported(version)) if version == VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path;
+use std::time;
+
+use sha2::{Digest, Sha256};
+
+use crate::config;
+use crate::frontend;
+
+/// What's known about a fatal error when it happens, to be written to a zip file if the user wants
+/// to report it
+pub struct CrashReport {
+    config: String,
+    display: egui::ColorImage,
+    error: String,
+    /// File name, size and SHA-256 hash of the program, if it could be read
+    program: Option<String>,
+}
+
+impl CrashReport {
+    pub fn new(
+        error: &frontend::FrontendError,
+        config: &config::EmulatorConfig,
+        display: egui::ColorImage,
+        program: Option<&path::Path>,
+    ) -> Self {
+        let program = program.and_then(|path| {
+            let contents = fs::read(path).ok()?;
+
+            Some(format!(
+                "{}\n{} bytes\nSHA-256 {:x}\n",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                contents.len(),
+                Sha256::digest(&contents)
+            ))
+        });
+
+        Self {
+            config: toml::to_string(config)
+                .unwrap_or_else(|error| format!("couldn't write the configuration, {}", error)),
+            display,
+            error: format!("{}\n", error),
+            program,
+        }
+    }
+
+    /// Writes the report as a zip file in the documents directory and returns its path
+    pub fn save(&self) -> Result<path::PathBuf, String> {
+        let directory = match dirs::document_dir() {
+            Some(directory) => directory,
+            None => env::current_dir().map_err(|error| format!("{}", error))?,
+        };
+
+        let path = directory.join(format!(
+            "{}-crash-{}.zip",
+            env!("CARGO_PKG_NAME"),
+            time::SystemTime::now()
+                .duration_since(time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        ));
+
+        let file = fs::File::create(&path).map_err(|error| format!("{}", error))?;
+        let mut zip = zip::ZipWriter::new(io::BufWriter::new(file));
+
+        let mut display = Vec::new();
+        super::screenshot::write(&self.display, &mut display)?;
+
+        let mut entries = vec![
+            ("error.txt", self.error.as_bytes()),
+            ("config.toml", self.config.as_bytes()),
+            ("display.png", &display),
+        ];
+
+        if let Some(program) = self.program.as_ref() {
+            entries.push(("program.txt", program.as_bytes()));
+        }
+
+        for (name, contents) in entries {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .map_err(|error| format!("{}", error))?;
+            zip.write_all(contents)
+                .map_err(|error| format!("{}", error))?;
+        }
+
+        zip.finish().map_err(|error| format!("{}", error))?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,1989 @@
+use std::collections;
+use std::fs;
+use std::io;
+use std::mem;
+use std::ops;
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend;
+use crate::config;
+use crate::disassembler;
+use crate::frontend;
+use crate::graph;
+use crate::i18n;
+
+use super::integrity;
+
+/// Suffix of the file the control-flow graph is exported to in the DOT language, next to the program
+const CONTROL_FLOW_FILE_SUFFIX: &str = ".dot";
+/// Suffix of the file the disassembly is exported to, next to the program
+const DISASSEMBLY_FILE_SUFFIX: &str = ".asm";
+/// Height of a lane of the event track in points
+const EVENT_LANE_HEIGHT: f32 = 6.0;
+/// The kinds of events on the event track, a lane each
+const EVENT_LANES: [(frontend::TickEvents, &str); 4] = [
+    (frontend::TickEvents::DRAW, "event-draw"),
+    (frontend::TickEvents::BEEP, "event-beep"),
+    (frontend::TickEvents::KEY_READ, "event-key-read"),
+    (frontend::TickEvents::ERROR, "event-error"),
+];
+/// Height of the event track in points, with a lane for each kind of event and one for the
+/// snapshots
+pub const EVENT_TRACK_HEIGHT: f32 = EVENT_LANE_HEIGHT * (EVENT_LANES.len() + 1) as f32;
+const MEMORY_ROW_SIZE: usize = 16;
+/// Number of matches of the memory search listed, the rest are only counted
+const SEARCH_MATCHES_SHOWN: usize = 64;
+const OCTO_STATE_FILE_SUFFIX: &str = ".octo.json";
+const SESSION_FILE_SUFFIX: &str = ".session.toml";
+const TIMELINE_CELL_SIZE: egui::Vec2 = egui::vec2(4.0, 6.0);
+/// Rate the timers count down at, which the ticks are executed at
+const TIMER_FREQUENCY: f64 = 60.0;
+const TIMER_PLOT_HEIGHT: f32 = 120.0;
+
+/// Reads a timer off a tick of the timeline
+type Timer = fn(&frontend::TimelineFrame) -> u8;
+
+/// Shows the state of the program between steps in debug mode, highlighting what the last step changed
+pub struct Debugger {
+    /// Address entered to add breakpoints, watches and labels at
+    address: usize,
+    /// The last collisions of sprites, oldest first
+    collisions: Vec<frontend::CollisionEvent>,
+    current: Option<frontend::Snapshot>,
+    /// Whether the session has changed since it was last saved
+    dirty: bool,
+    disassembly_search: DisassemblySearch,
+    /// State imported from Octo, until the program is moved to it
+    imported: Option<frontend::Snapshot>,
+    /// What the program being debugged was read as
+    integrity: Option<integrity::Integrity>,
+    /// Name entered to add a label with
+    label: String,
+    /// Coordinates of the pixel clicked on the display, to list the instructions that drew over it
+    pixel: Option<(usize, usize)>,
+    /// Shadow copy of the state before the last step
+    previous: Option<frontend::Snapshot>,
+    /// Path of the program being debugged
+    program: Option<path::PathBuf>,
+    /// Address of the line the disassembly is yet to be scrolled to
+    scroll_to: Option<usize>,
+    search: MemorySearch,
+    /// Address of the byte the selection was started at and of the one it was extended to, in
+    /// either order
+    selection: Option<(usize, usize)>,
+    session: Session,
+    /// Ticks the snapshots kept by the frontend were taken before, oldest first
+    snapshots: Vec<u64>,
+    /// The last message of the frontend thread, e.g. why the program was suspended
+    status: String,
+    /// The keys held and the timers during the last ticks, oldest first
+    timeline: Vec<frontend::TimelineFrame>,
+}
+
+/// Steps through the lines of the disassembly matching what was entered, see
+/// `disassembler::matches`
+#[derive(Default)]
+struct DisassemblySearch {
+    /// Position of the line last stepped to among the matching ones, `None` before the first step
+    current: Option<usize>,
+    query: String,
+}
+
+/// Finds the address of a value by narrowing down the addresses with each search, e.g. to the ones
+/// that increased since the last one
+#[derive(Default)]
+struct MemorySearch {
+    /// Addresses that matched every search so far, `None` before the first one
+    matches: Option<Vec<usize>>,
+    /// The memory at the last search, to compare the current one against
+    memory: Vec<u8>,
+    /// Value entered to search for
+    value: u8,
+}
+
+#[derive(Clone, Copy)]
+enum SearchFilter {
+    Changed,
+    Decreased,
+    Equal(u8),
+    Increased,
+    Unchanged,
+}
+
+/// Debugging aids for a program, saved next to it so that they're restored when it's opened again
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Session {
+    breakpoints: collections::BTreeSet<usize>,
+    /// Addresses marked as code, whatever the program did with them
+    code: collections::BTreeSet<usize>,
+    /// Addresses marked as data, whatever the program did with them
+    data: collections::BTreeSet<usize>,
+    /// Values the registers are pinned to, keyed by the name of the register
+    frozen: collections::BTreeMap<String, usize>,
+    labels: collections::BTreeMap<String, usize>,
+    layout: Layout,
+    watches: Vec<Watch>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+struct Layout {
+    breakpoints: bool,
+    call_graph: bool,
+    collisions: bool,
+    control_flow: bool,
+    /// Whether the display is in a window of its own, leaving the main window to the debugger
+    detached_display: bool,
+    disassembly: bool,
+    /// Whether the pixels turned off by collisions in the last tick are highlighted on the display
+    flash_collisions: bool,
+    labels: bool,
+    /// Whether the display is magnified around the pointer
+    magnifier: bool,
+    memory: bool,
+    program: bool,
+    references: bool,
+    registers: bool,
+    stack: bool,
+    statistics: bool,
+    timeline: bool,
+    timers: bool,
+    watches: bool,
+    width: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Watch {
+    address: usize,
+    /// Stop continuing to the next breakpoint when the watched byte is written
+    #[serde(default)]
+    break_on_write: bool,
+}
+
+impl Debugger {
+    #[inline]
+    pub fn breakpoints(&self) -> collections::BTreeSet<usize> {
+        self.session.breakpoints.clone()
+    }
+
+    /// Returns the registers pinned to a value
+    pub fn frozen(&self) -> backend::FrozenRegisters {
+        let mut frozen = backend::FrozenRegisters {
+            address: self.session.frozen.get("I").copied(),
+            ..Default::default()
+        };
+
+        for (register, value) in frozen.general.iter_mut().enumerate() {
+            *value = self
+                .session
+                .frozen
+                .get(&format!("V{:X}", register))
+                .map(|value| *value as u8);
+        }
+
+        frozen
+    }
+
+    /// Returns the coordinates of the pixels the last step flipped
+    pub fn changed_pixels(&self) -> Vec<(usize, usize)> {
+        let (previous, current) = match (self.previous.as_ref(), self.current.as_ref()) {
+            (Some(previous), Some(current)) => (previous, current),
+            _ => return Vec::new(),
+        };
+
+        let (previous, current) = (
+            &previous.display_buffer().buffer,
+            &current.display_buffer().buffer,
+        );
+
+        (0..backend::DISPLAY_BUFFER_HEIGHT)
+            .flat_map(|y| (0..backend::DISPLAY_BUFFER_WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| previous[*y][*x] != current[*y][*x])
+            .collect()
+    }
+
+    /// Returns the pixels turned off by collisions in the last tick, when they're to be flashed
+    pub fn flashed_pixels(&self) -> Vec<(usize, usize)> {
+        let last = match self.timeline.last() {
+            Some(last) if self.session.layout.flash_collisions => last.frame,
+            _ => return Vec::new(),
+        };
+
+        self.collisions
+            .iter()
+            .filter(|event| event.frame == last)
+            .flat_map(|event| event.collision.pixels.iter().copied())
+            .collect()
+    }
+
+    /// Lists the instructions that drew over `pixel`
+    #[inline]
+    pub fn inspect_pixel(&mut self, pixel: (usize, usize)) {
+        self.pixel = Some(pixel);
+    }
+
+    #[inline]
+    pub fn display_detached(&self) -> bool {
+        self.session.layout.detached_display
+    }
+
+    /// Returns the display of the current state when the magnifier is on
+    pub fn magnified(&self) -> Option<&backend::interfaces::DisplayBuffer> {
+        self.current
+            .as_ref()
+            .filter(|_| self.session.layout.magnifier)
+            .map(frontend::Snapshot::display_buffer)
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.collisions.clear();
+        self.current = None;
+        self.previous = None;
+        self.status.clear();
+        self.timeline.clear();
+    }
+
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            address: backend::MEMORY_PADDING,
+            collisions: Vec::new(),
+            current: None,
+            dirty: false,
+            disassembly_search: DisassemblySearch::default(),
+            imported: None,
+            integrity: None,
+            label: String::new(),
+            pixel: None,
+            previous: None,
+            program: None,
+            scroll_to: None,
+            search: MemorySearch::default(),
+            selection: None,
+            session: Session::default(),
+            snapshots: Vec::new(),
+            status: String::new(),
+            timeline: Vec::new(),
+        }
+    }
+
+    /// Writes the current state next to the program in the format of Octo's test harness
+    fn export(&mut self) {
+        let (current, path) = match (self.current.as_ref(), self.sidecar(OCTO_STATE_FILE_SUFFIX)) {
+            (Some(current), Some(path)) => (current, path),
+            _ => return,
+        };
+
+        self.status = match fs::write(&path, current.to_octo_json()) {
+            Ok(()) => format!("Exported the state to {}", path.display()),
+            Err(error) => format!("couldn't export the state, {}", error),
+        };
+    }
+
+    /// Reads the state in the format of Octo's test harness next to the program, for the program to
+    /// be moved to
+    fn import(&mut self) {
+        let (current, path) = match (self.current.as_ref(), self.sidecar(OCTO_STATE_FILE_SUFFIX)) {
+            (Some(current), Some(path)) => (current, path),
+            _ => return,
+        };
+
+        let imported = fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|json| {
+                frontend::Snapshot::from_octo_json(&json, current)
+                    .map_err(|error| error.to_string())
+            });
+
+        self.status = match imported {
+            Ok(snapshot) => {
+                self.imported = Some(snapshot);
+                format!("Imported the state from {}", path.display())
+            }
+            Err(error) => format!("couldn't import the state, {}", error),
+        };
+    }
+
+    /// Writes `dot` next to the program, the control-flow graph of the whole program
+    fn export_control_flow(&mut self, dot: String) {
+        let Some(path) = self.sidecar(CONTROL_FLOW_FILE_SUFFIX) else {
+            return;
+        };
+
+        self.status = match fs::write(&path, dot) {
+            Ok(()) => format!("Exported the control-flow graph to {}", path.display()),
+            Err(error) => format!("couldn't export the control-flow graph, {}", error),
+        };
+    }
+
+    /// Writes `source` next to the program, the disassembly of the whole program
+    fn export_disassembly(&mut self, source: String) {
+        let Some(path) = self.sidecar(DISASSEMBLY_FILE_SUFFIX) else {
+            return;
+        };
+
+        self.status = match fs::write(&path, source) {
+            Ok(()) => format!("Exported the disassembly to {}", path.display()),
+            Err(error) => format!("couldn't export the disassembly, {}", error),
+        };
+    }
+
+    /// Saves the session of the previous program and loads the one of `program`, which was read as
+    /// `integrity`
+    pub fn open(&mut self, program: &path::Path, integrity: integrity::Integrity) {
+        self.save();
+        self.integrity = Some(integrity);
+        self.pixel = None;
+        self.selection = None;
+        self.program = Some(program.to_owned());
+        self.search = MemorySearch::default();
+
+        self.session =
+            Session::load(&self.sidecar(SESSION_FILE_SUFFIX).unwrap()).unwrap_or_else(|error| {
+                eprintln!("couldn't load the debugger session, {}", error);
+                Session::default()
+            });
+    }
+
+    /// Records the state after a step, keeping the one before it to compare against
+    #[inline]
+    pub fn push(&mut self, snapshot: frontend::Snapshot) {
+        self.previous = self.current.replace(snapshot);
+    }
+
+    /// Saves the session if it has changed
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        if let Some(path) = self.sidecar(SESSION_FILE_SUFFIX) {
+            if let Err(error) = self.session.save(&path) {
+                eprintln!("couldn't save the debugger session, {}", error);
+            }
+        }
+
+        self.dirty = false;
+    }
+
+    /// Extends the selection from the byte it was started at to `bytes`, or starts it at them
+    fn select(&mut self, bytes: ops::RangeInclusive<usize>) {
+        self.selection = Some(match self.selection {
+            Some((start, _)) if *bytes.start() < start => (start, *bytes.start()),
+            Some((start, _)) => (start, *bytes.end()),
+            None => (*bytes.start(), *bytes.end()),
+        });
+    }
+
+    /// Returns the addresses of the selected bytes
+    fn selected(&self) -> Option<ops::RangeInclusive<usize>> {
+        self.selection
+            .map(|(start, end)| start.min(end)..=start.max(end))
+    }
+
+    /// Returns the path of a file kept next to the program, named after it
+    fn sidecar(&self, suffix: &str) -> Option<path::PathBuf> {
+        self.program.as_ref().map(|program| {
+            let mut path = program.as_os_str().to_owned();
+            path.push(suffix);
+
+            path::PathBuf::from(path)
+        })
+    }
+
+    /// Takes the state imported from Octo, which the program is to be moved to
+    #[inline]
+    pub fn take_imported(&mut self) -> Option<frontend::Snapshot> {
+        self.imported.take()
+    }
+
+    #[inline]
+    pub fn set_status(&mut self, status: String) {
+        self.status = status;
+    }
+
+    #[inline]
+    pub fn set_collisions(&mut self, collisions: Vec<frontend::CollisionEvent>) {
+        self.collisions = collisions;
+    }
+
+    #[inline]
+    pub fn set_timeline(&mut self, timeline: Vec<frontend::TimelineFrame>, snapshots: Vec<u64>) {
+        self.snapshots = snapshots;
+        self.timeline = timeline;
+    }
+
+    #[inline]
+    pub fn set_width(&mut self, width: f32) {
+        if (self.session.layout.width - width).abs() >= 1.0 {
+            self.session.layout.width = width;
+            self.dirty = true;
+        }
+    }
+
+    /// Shows the debugger and returns whether the breakpoints, the watchpoints or the frozen
+    /// registers were changed
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        localizer: &i18n::Localizer,
+        highlight: egui::Color32,
+    ) -> bool {
+        let mut breakpoints_changed = false;
+
+        let current = match self.current.as_ref() {
+            Some(current) => current.backend(),
+            None => {
+                ui.label(localizer.get("debugger-step-hint"));
+                return false;
+            }
+        };
+        let previous = self.previous.as_ref().map(frontend::Snapshot::backend);
+
+        if !self.status.is_empty() {
+            ui.colored_label(highlight, &self.status);
+        }
+
+        let mut export = false;
+        let mut import = false;
+        let mut export_control_flow = None;
+        let mut export_disassembly = None;
+        // bytes clicked with shift held, to extend the selection to
+        let mut selected = None;
+        let selection = self.selected();
+
+        let changed = |f: &dyn Fn(&backend::Backend) -> usize| match previous {
+            Some(previous) => f(previous) != f(current),
+            None => false,
+        };
+        let value = |ui: &mut egui::Ui, text: String, changed: bool| {
+            ui.label(egui::RichText::new(text).monospace().color(match changed {
+                true => highlight,
+                false => egui::Color32::LIGHT_GRAY,
+            }))
+        };
+
+        let changed_registers = (0..backend::REGISTER_COUNT)
+            .filter(|register| changed(&|backend| backend.registers.general[*register] as usize))
+            .count();
+        let changed_bytes: Vec<bool> = (0..current.memory.len())
+            .map(|address| {
+                changed(&|backend| {
+                    backend.memory.get(address).copied().unwrap_or_default() as usize
+                })
+            })
+            .collect();
+        // addresses beyond the first 4K take four hexadecimal digits
+        let digits = match current.memory.len() > backend::MEMORY_SIZE {
+            true => 4,
+            false => 3,
+        };
+
+        ui.label(localizer.format(
+            "debugger-changes",
+            &[
+                ("registers", &changed_registers),
+                (
+                    "bytes",
+                    &changed_bytes.iter().filter(|changed| **changed).count(),
+                ),
+                ("pixels", &self.changed_pixels().len()),
+            ],
+        ));
+
+        if ui.button(localizer.get("debugger-export-octo")).clicked() {
+            export = true;
+        }
+
+        if ui.button(localizer.get("debugger-import-octo")).clicked() {
+            import = true;
+        }
+
+        if ui
+            .checkbox(
+                &mut self.session.layout.magnifier,
+                localizer.get("debugger-magnifier"),
+            )
+            .changed()
+        {
+            self.dirty = true;
+        }
+
+        if ui
+            .checkbox(
+                &mut self.session.layout.detached_display,
+                localizer.get("debugger-detach-display"),
+            )
+            .changed()
+        {
+            self.dirty = true;
+        }
+
+        if let Some((x, y)) = self.pixel {
+            let display = self.current.as_ref().unwrap().display_buffer();
+            let draws = display.draws.get(&(x, y));
+
+            ui.horizontal(|ui| {
+                ui.label(localizer.format(
+                    match display.buffer[y][x] {
+                        true => "debugger-magnifier-on",
+                        false => "debugger-magnifier-off",
+                    },
+                    &[("x", &x), ("y", &y)],
+                ));
+
+                if ui.small_button("×").clicked() {
+                    self.pixel = None;
+                }
+            });
+
+            match draws.filter(|draws| !draws.is_empty()) {
+                // the latest draw first
+                Some(draws) => {
+                    for draw in draws.iter().rev() {
+                        let text = format!("PC {:03X}, I {:03X}", draw.index, draw.address);
+
+                        // picking a draw enters its address, e.g. to add a breakpoint at it
+                        if ui
+                            .add(egui::Button::new(egui::RichText::new(text).monospace()).small())
+                            .clicked()
+                        {
+                            self.address = draw.index;
+                        }
+                    }
+                }
+                None => {
+                    ui.label(localizer.get("debugger-pixel-undrawn"));
+                }
+            }
+        }
+
+        egui::ScrollArea::vertical()
+            .id_source("debugger")
+            .show(ui, |ui| {
+                let session = &mut self.session;
+
+                let registers = egui::CollapsingHeader::new(localizer.get("debugger-registers"))
+                    .default_open(session.layout.registers)
+                    .show(ui, |ui| {
+                        egui::Grid::new("registers").show(ui, |ui| {
+                            for (name, text, changed) in [
+                                (
+                                    "PC",
+                                    format!("{:03X}", current.index()),
+                                    changed(&backend::Backend::index),
+                                ),
+                                (
+                                    "I",
+                                    format!("{:03X}", current.registers.address),
+                                    changed(&|backend| backend.registers.address),
+                                ),
+                                (
+                                    "DT",
+                                    format!("{:02X}", current.timers.delay),
+                                    changed(&|backend| backend.timers.delay as usize),
+                                ),
+                                (
+                                    "ST",
+                                    format!("{:02X}", current.timers.sound),
+                                    changed(&|backend| backend.timers.sound as usize),
+                                ),
+                                (
+                                    "SP",
+                                    format!("{:X}", current.stack.len()),
+                                    changed(&|backend| backend.stack.len()),
+                                ),
+                            ] {
+                                ui.label(name);
+                                value(ui, text, changed);
+
+                                if name == "I" {
+                                    breakpoints_changed |= session.freeze(
+                                        ui,
+                                        localizer,
+                                        name,
+                                        current.registers.address,
+                                        current.memory.len() - 1,
+                                    );
+                                }
+
+                                ui.end_row();
+                            }
+
+                            for register in 0..backend::REGISTER_COUNT {
+                                let name = format!("V{:X}", register);
+
+                                ui.label(&name);
+                                value(
+                                    ui,
+                                    format!("{:02X}", current.registers.general[register]),
+                                    changed(&|backend| {
+                                        backend.registers.general[register] as usize
+                                    }),
+                                );
+                                breakpoints_changed |= session.freeze(
+                                    ui,
+                                    localizer,
+                                    &name,
+                                    current.registers.general[register] as usize,
+                                    u8::MAX as usize,
+                                );
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                let stack = egui::CollapsingHeader::new(localizer.get("debugger-stack"))
+                    .default_open(session.layout.stack)
+                    .show(ui, |ui| {
+                        if current.stack.is_empty() {
+                            ui.label(localizer.get("debugger-stack-empty"));
+                        }
+
+                        // the innermost call first, the subroutine it entered is read off the
+                        // call instruction right before the return address
+                        for (depth, address) in current.stack.iter().enumerate().rev() {
+                            let address = *address as usize;
+                            let call = address
+                                .checked_sub(mem::size_of::<backend::Instruction>())
+                                .and_then(|call| current.memory.get(call..call + 2))
+                                .map(|bytes| backend::Instruction::new([bytes[0], bytes[1]]))
+                                .filter(|instruction| instruction.operator_code() == 0x2);
+                            let text = localizer.format(
+                                "debugger-stack-frame",
+                                &[
+                                    ("depth", &depth),
+                                    (
+                                        "subroutine",
+                                        &match call {
+                                            Some(call) => session.name(call.operand_nnn()),
+                                            None => "???".to_owned(),
+                                        },
+                                    ),
+                                    ("address", &session.name(address)),
+                                ],
+                            );
+
+                            if ui
+                                .add(
+                                    egui::Button::new(egui::RichText::new(text).monospace())
+                                        .small(),
+                                )
+                                .clicked()
+                            {
+                                self.address = address;
+                                self.scroll_to = Some(address);
+                            }
+                        }
+                    });
+
+                let statistics = egui::CollapsingHeader::new(localizer.get("debugger-statistics"))
+                    .default_open(session.layout.statistics)
+                    .show(ui, |ui| {
+                        let statistics = &current.statistics;
+
+                        egui::Grid::new("statistics").show(ui, |ui| {
+                            for (id, count) in [
+                                ("debugger-statistics-total", statistics.total()),
+                                ("debugger-statistics-draws", statistics.draws),
+                                ("debugger-statistics-sounds", statistics.sounds),
+                                ("debugger-statistics-key-reads", statistics.key_reads),
+                            ] {
+                                ui.label(localizer.get(id));
+                                ui.monospace(count.to_string());
+                                ui.end_row();
+                            }
+                        });
+
+                        // a histogram of the instructions by their operator code
+                        let most = statistics.families.iter().copied().max().unwrap_or(0);
+
+                        egui::Grid::new("families").show(ui, |ui| {
+                            for (family, count) in statistics.families.iter().enumerate() {
+                                ui.monospace(format!("{:X}___", family));
+                                ui.add(
+                                    egui::ProgressBar::new(match most {
+                                        0 => 0.0,
+                                        most => *count as f32 / most as f32,
+                                    })
+                                    .text(count.to_string()),
+                                );
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                let memory = egui::CollapsingHeader::new(localizer.get("debugger-memory"))
+                    .default_open(session.layout.memory)
+                    .show(ui, |ui| {
+                        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+
+                        egui::ScrollArea::vertical()
+                            .id_source("memory")
+                            .max_height(row_height * MEMORY_ROW_SIZE as f32)
+                            .show_rows(
+                                ui,
+                                row_height,
+                                current.memory.len() / MEMORY_ROW_SIZE,
+                                |ui, rows| {
+                                    for row in rows {
+                                        ui.horizontal(|ui| {
+                                            ui.spacing_mut().item_spacing.x = 4.0;
+
+                                            let start = row * MEMORY_ROW_SIZE;
+
+                                            ui.label(
+                                                egui::RichText::new(format!(
+                                                    "{:0width$X}",
+                                                    start,
+                                                    width = digits
+                                                ))
+                                                .monospace(),
+                                            );
+
+                                            for (address, changed) in changed_bytes
+                                                .iter()
+                                                .enumerate()
+                                                .skip(start)
+                                                .take(MEMORY_ROW_SIZE)
+                                            {
+                                                let mut text = egui::RichText::new(format!(
+                                                    "{:02X}",
+                                                    current.memory[address]
+                                                ))
+                                                .monospace()
+                                                .color(match changed {
+                                                    true => highlight,
+                                                    false => egui::Color32::LIGHT_GRAY,
+                                                });
+
+                                                if session.breakpoints.contains(&address) {
+                                                    text = text.underline();
+                                                }
+
+                                                if selection
+                                                    .as_ref()
+                                                    .is_some_and(|bytes| bytes.contains(&address))
+                                                {
+                                                    text = text.background_color(
+                                                        ui.visuals().selection.bg_fill,
+                                                    );
+                                                }
+
+                                                let response = ui.add(
+                                                    egui::Label::new(text)
+                                                        .sense(egui::Sense::click()),
+                                                );
+
+                                                // clicking a byte toggles a breakpoint at it, with
+                                                // shift held it selects up to it instead
+                                                if response.clicked() && ui.input().modifiers.shift
+                                                {
+                                                    selected = Some(address..=address);
+                                                } else if response.clicked() {
+                                                    if !session.breakpoints.remove(&address) {
+                                                        session.breakpoints.insert(address);
+                                                    }
+
+                                                    breakpoints_changed = true;
+                                                }
+
+                                                response.on_hover_text(session.name(address));
+                                            }
+                                        });
+                                    }
+                                },
+                            );
+
+                        ui.horizontal(|ui| match selection.as_ref() {
+                            Some(bytes) => {
+                                if ui
+                                    .button(localizer.get("debugger-copy-selection"))
+                                    .clicked()
+                                {
+                                    ui.output().copied_text =
+                                        hex_dump(&current.memory, bytes.clone(), digits);
+                                }
+
+                                if ui
+                                    .button(localizer.get("debugger-clear-selection"))
+                                    .clicked()
+                                {
+                                    self.selection = None;
+                                }
+                            }
+                            None => {
+                                ui.label(localizer.get("debugger-selection-hint"));
+                            }
+                        });
+
+                        ui.separator();
+
+                        let search = &mut self.search;
+
+                        ui.horizontal_wrapped(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut search.value).hexadecimal(2, false, true),
+                            );
+
+                            if ui.button(localizer.get("debugger-search-equal")).clicked() {
+                                search.narrow(&current.memory, SearchFilter::Equal(search.value));
+                            }
+
+                            // the other searches compare against the memory at the last one
+                            ui.add_enabled_ui(!search.memory.is_empty(), |ui| {
+                                for (filter, id) in [
+                                    (SearchFilter::Changed, "debugger-search-changed"),
+                                    (SearchFilter::Unchanged, "debugger-search-unchanged"),
+                                    (SearchFilter::Increased, "debugger-search-increased"),
+                                    (SearchFilter::Decreased, "debugger-search-decreased"),
+                                ] {
+                                    if ui.button(localizer.get(id)).clicked() {
+                                        search.narrow(&current.memory, filter);
+                                    }
+                                }
+                            });
+
+                            if ui.button(localizer.get("debugger-search-new")).clicked() {
+                                search.matches = None;
+                                search.memory = current.memory.clone();
+                            }
+                        });
+
+                        if let Some(matches) = search.matches.as_ref() {
+                            ui.label(
+                                localizer.format(
+                                    "debugger-search-matches",
+                                    &[("count", &matches.len())],
+                                ),
+                            );
+
+                            for address in matches.iter().take(SEARCH_MATCHES_SHOWN) {
+                                ui.horizontal(|ui| {
+                                    // a found address is followed from then on as a watch
+                                    if ui.small_button(localizer.get("debugger-watch")).clicked() {
+                                        session.watches.push(Watch {
+                                            address: *address,
+                                            break_on_write: false,
+                                        });
+                                        self.dirty = true;
+                                    }
+
+                                    ui.monospace(format!(
+                                        "{} {:02X}",
+                                        session.name(*address),
+                                        current.memory[*address]
+                                    ));
+                                });
+                            }
+                        }
+                    });
+
+                let lines = disassembler::disassemble(
+                    &current.memory,
+                    current.quirks.load_address..current.memory.len(),
+                    |address| session.region(address, current.coverage()),
+                );
+                let references = disassembler::references(&lines);
+                let describe = |references: &[disassembler::Reference], session: &Session| {
+                    references
+                        .iter()
+                        .map(|reference| {
+                            localizer.reference(reference.kind, &session.name(reference.address))
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+
+                let disassembly =
+                    egui::CollapsingHeader::new(localizer.get("debugger-disassembly"))
+                        .default_open(session.layout.disassembly)
+                        .show(ui, |ui| {
+                            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                            let mut marked = None;
+
+                            let search = &mut self.disassembly_search;
+                            let found: Vec<usize> = (0..lines.len())
+                                .filter(|index| {
+                                    disassembler::matches(&lines[*index], &search.query)
+                                })
+                                .collect();
+
+                            // the lines found change along with the memory
+                            search.current =
+                                search.current.filter(|current| *current < found.len());
+
+                            ui.horizontal(|ui| {
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut search.query)
+                                        .hint_text(localizer.get("debugger-disassembly-search"))
+                                        .desired_width(120.0),
+                                );
+                                let entered = response.lost_focus()
+                                    && ui.input().key_pressed(egui::Key::Enter);
+
+                                if response.changed() {
+                                    search.current = None;
+                                }
+
+                                ui.add_enabled_ui(!found.is_empty(), |ui| {
+                                    let count = found.len();
+                                    let stepped = match (
+                                        ui.small_button("◀").clicked(),
+                                        ui.small_button("▶").clicked() || entered,
+                                    ) {
+                                        (true, _) => {
+                                            Some(search.current.map_or(count - 1, |current| {
+                                                (current + count - 1) % count
+                                            }))
+                                        }
+                                        (_, true) => Some(
+                                            search
+                                                .current
+                                                .map_or(0, |current| (current + 1) % count),
+                                        ),
+                                        _ => None,
+                                    };
+
+                                    // the address of a line stepped to is entered, e.g. to add a
+                                    // breakpoint at it
+                                    if let Some(stepped) = stepped {
+                                        search.current = Some(stepped);
+                                        self.address = lines[found[stepped]].address;
+                                        self.scroll_to = Some(self.address);
+                                    }
+                                });
+
+                                if !search.query.trim().is_empty() {
+                                    ui.label(match (found.is_empty(), search.current) {
+                                        (true, _) => {
+                                            localizer.get("debugger-disassembly-search-none")
+                                        }
+                                        (false, Some(current)) => localizer.format(
+                                            "debugger-disassembly-search-current",
+                                            &[("current", &(current + 1)), ("count", &found.len())],
+                                        ),
+                                        (false, None) => localizer.format(
+                                            "debugger-disassembly-search-matches",
+                                            &[("count", &found.len())],
+                                        ),
+                                    });
+                                }
+                            });
+
+                            let mut scroll_area = egui::ScrollArea::vertical()
+                                .id_source("disassembly")
+                                .max_height(row_height * MEMORY_ROW_SIZE as f32);
+
+                            let stepped = search.current.map(|current| found[current]);
+
+                            if let Some(address) = self.scroll_to.take() {
+                                let index = lines.partition_point(|line| line.address < address);
+
+                                scroll_area = scroll_area.vertical_scroll_offset(
+                                    (row_height + ui.spacing().item_spacing.y) * index as f32,
+                                );
+                            }
+
+                            scroll_area.show_rows(ui, row_height, lines.len(), |ui, rows| {
+                                for (index, line) in rows.map(|index| (index, &lines[index])) {
+                                    ui.horizontal(|ui| {
+                                        // clicking the region of a line marks it as the other one
+                                        let (marker, other) = match line.region {
+                                            disassembler::Region::Code => {
+                                                ("C", disassembler::Region::Data)
+                                            }
+                                            disassembler::Region::Data => {
+                                                ("D", disassembler::Region::Code)
+                                            }
+                                        };
+
+                                        if ui.small_button(marker).clicked() {
+                                            marked = Some((line.address, other));
+                                        }
+
+                                        let mut text = egui::RichText::new(listing(
+                                            line,
+                                            &session.name(line.address),
+                                        ))
+                                        .monospace();
+
+                                        if line.address == current.index() {
+                                            text = text.color(highlight);
+                                        }
+
+                                        // the lines found are underlined, the one stepped to is
+                                        // emphasized as well
+                                        if found.binary_search(&index).is_ok() {
+                                            text = text.underline();
+                                        }
+
+                                        if stepped == Some(index) {
+                                            text = text.strong();
+                                        }
+
+                                        if selection
+                                            .as_ref()
+                                            .is_some_and(|bytes| bytes.contains(&line.address))
+                                        {
+                                            text = text
+                                                .background_color(ui.visuals().selection.bg_fill);
+                                        }
+
+                                        let response = ui.add(
+                                            egui::Label::new(text).sense(egui::Sense::click()),
+                                        );
+
+                                        if response.clicked() && ui.input().modifiers.shift {
+                                            selected = Some(
+                                                line.address..=line.address + line.bytes.len() - 1,
+                                            );
+                                        }
+
+                                        // what refers to a line is told when hovering it
+                                        if let Some(references) = references.get(&line.address) {
+                                            response.on_hover_text(describe(references, session));
+                                        }
+
+                                        // data is often sprites, which can be told apart at a glance
+                                        if line.region == disassembler::Region::Data {
+                                            ui.monospace(
+                                                format!("{:08b}", line.bytes[0])
+                                                    .replace('0', "·")
+                                                    .replace('1', "█"),
+                                            );
+                                        }
+                                    });
+                                }
+                            });
+
+                            if let Some((address, region)) = marked {
+                                session.mark(address, region);
+                                self.dirty = true;
+                            }
+
+                            ui.horizontal(|ui| {
+                                if let Some(bytes) = selection.as_ref() {
+                                    if ui
+                                        .button(localizer.get("debugger-copy-selection"))
+                                        .clicked()
+                                    {
+                                        let selected: Vec<_> = lines
+                                            .iter()
+                                            .filter(|line| bytes.contains(&line.address))
+                                            .map(|line| listing(line, &session.name(line.address)))
+                                            .collect();
+
+                                        ui.output().copied_text = selected.join("\n");
+                                    }
+                                }
+
+                                // the zeros the memory is padded with after the program are left out
+                                if ui
+                                    .button(localizer.get("debugger-export-disassembly"))
+                                    .clicked()
+                                {
+                                    let end = current
+                                        .memory
+                                        .iter()
+                                        .rposition(|byte| *byte != 0)
+                                        .map_or(0, |last| last + 1);
+                                    let count = lines.partition_point(|line| line.address < end);
+
+                                    export_disassembly =
+                                        Some(disassembler::source(&lines[..count]));
+                                }
+                            });
+                        });
+
+                let blocks = graph::control_flow(&lines);
+
+                let control_flow =
+                    egui::CollapsingHeader::new(localizer.get("debugger-control-flow"))
+                        .default_open(session.layout.control_flow)
+                        .show(ui, |ui| {
+                            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+
+                            if ui
+                                .button(localizer.get("debugger-export-control-flow"))
+                                .clicked()
+                            {
+                                export_control_flow =
+                                    Some(graph::dot(&blocks, |address| session.name(address)));
+                            }
+
+                            egui::ScrollArea::vertical()
+                                .id_source("control_flow")
+                                .max_height(row_height * MEMORY_ROW_SIZE as f32)
+                                .show(ui, |ui| {
+                                    for block in blocks.iter() {
+                                        ui.horizontal_wrapped(|ui| {
+                                            let breakpoint =
+                                                session.breakpoints.contains(&block.start);
+
+                                            // the dot toggles a breakpoint at the start of the
+                                            // block, the block itself is followed in the
+                                            // disassembly when picked
+                                            if ui
+                                                .small_button(match breakpoint {
+                                                    true => "●",
+                                                    false => "○",
+                                                })
+                                                .clicked()
+                                            {
+                                                if !session.breakpoints.remove(&block.start) {
+                                                    session.breakpoints.insert(block.start);
+                                                }
+
+                                                breakpoints_changed = true;
+                                            }
+
+                                            let mut followed = ui
+                                                .add(
+                                                    egui::Button::new(
+                                                        egui::RichText::new(format!(
+                                                            "{}…{:03X}",
+                                                            session.name(block.start),
+                                                            block.end
+                                                        ))
+                                                        .monospace(),
+                                                    )
+                                                    .small(),
+                                                )
+                                                .clicked()
+                                                .then_some(block.start);
+
+                                            for edge in block.successors.iter() {
+                                                let text = localizer.format(
+                                                    "debugger-control-flow-edge",
+                                                    &[
+                                                        ("kind", &localizer.edge(edge.kind)),
+                                                        ("address", &session.name(edge.target)),
+                                                    ],
+                                                );
+
+                                                if ui.small_button(text).clicked() {
+                                                    followed = Some(edge.target);
+                                                }
+                                            }
+
+                                            if let Some(address) = followed {
+                                                self.address = address;
+                                                self.scroll_to = Some(address);
+                                            }
+                                        });
+                                    }
+                                });
+                        });
+
+                ui.horizontal(|ui| {
+                    ui.label(localizer.get("debugger-address"));
+                    ui.add(
+                        egui::DragValue::new(&mut self.address)
+                            .clamp_range(0..=current.memory.len() - 1)
+                            .hexadecimal(digits, false, true),
+                    );
+                });
+
+                let references = egui::CollapsingHeader::new(localizer.get("debugger-references"))
+                    .default_open(session.layout.references)
+                    .show(ui, |ui| {
+                        ui.label(localizer.format(
+                            "debugger-references-to",
+                            &[("address", &session.name(self.address))],
+                        ));
+
+                        match references.get(&self.address) {
+                            // picking a reference enters its address, to follow it back
+                            Some(references) => {
+                                for reference in references {
+                                    let text = localizer.reference(
+                                        reference.kind,
+                                        &session.name(reference.address),
+                                    );
+
+                                    if ui
+                                        .add(
+                                            egui::Button::new(
+                                                egui::RichText::new(text).monospace(),
+                                            )
+                                            .small(),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.address = reference.address;
+                                    }
+                                }
+                            }
+                            None => {
+                                ui.label(localizer.get("debugger-references-none"));
+                            }
+                        }
+                    });
+
+                let call_graph = egui::CollapsingHeader::new(localizer.get("debugger-call-graph"))
+                    .default_open(session.layout.call_graph)
+                    .show(ui, |ui| {
+                        let subroutines = graph::call_graph(&blocks);
+                        let by_address: collections::BTreeMap<usize, &graph::Subroutine> =
+                            subroutines
+                                .iter()
+                                .map(|subroutine| (subroutine.address, subroutine))
+                                .collect();
+                        let mut followed = None;
+
+                        egui::ScrollArea::vertical()
+                            .id_source("call_graph")
+                            .max_height(
+                                ui.text_style_height(&egui::TextStyle::Monospace)
+                                    * MEMORY_ROW_SIZE as f32,
+                            )
+                            .show(ui, |ui| {
+                                if let Some(entry) = subroutines.first() {
+                                    call_tree(
+                                        ui,
+                                        &by_address,
+                                        entry.address,
+                                        &mut Vec::new(),
+                                        &|address| session.name(address),
+                                        &mut followed,
+                                    );
+                                }
+                            });
+
+                        ui.separator();
+
+                        match by_address.get(&self.address) {
+                            Some(subroutine) => {
+                                ui.label(localizer.format(
+                                    "debugger-subroutine",
+                                    &[("address", &session.name(self.address))],
+                                ));
+
+                                if subroutine.callers.is_empty() {
+                                    ui.label(localizer.get("debugger-subroutine-entry"));
+                                }
+
+                                for caller in subroutine.callers.iter() {
+                                    let text = localizer.reference(
+                                        disassembler::ReferenceKind::Call,
+                                        &session.name(*caller),
+                                    );
+
+                                    if ui
+                                        .add(
+                                            egui::Button::new(
+                                                egui::RichText::new(text).monospace(),
+                                            )
+                                            .small(),
+                                        )
+                                        .clicked()
+                                    {
+                                        followed = Some(*caller);
+                                    }
+                                }
+
+                                ui.horizontal(|ui| {
+                                    ui.text_edit_singleline(&mut self.label);
+
+                                    if ui.button(localizer.get("debugger-rename")).clicked()
+                                        && !self.label.trim().is_empty()
+                                    {
+                                        session.rename(self.address, self.label.trim());
+                                        self.label.clear();
+                                        self.dirty = true;
+                                    }
+                                });
+                            }
+                            None => {
+                                ui.label(localizer.format(
+                                    "debugger-subroutine-none",
+                                    &[("address", &session.name(self.address))],
+                                ));
+                            }
+                        }
+
+                        if let Some(address) = followed {
+                            self.address = address;
+                            self.scroll_to = Some(address);
+                        }
+                    });
+
+                let breakpoints =
+                    egui::CollapsingHeader::new(localizer.get("debugger-breakpoints"))
+                        .default_open(session.layout.breakpoints)
+                        .show(ui, |ui| {
+                            let mut removed = None;
+
+                            for address in session.breakpoints.iter() {
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("×").clicked() {
+                                        removed = Some(*address);
+                                    }
+
+                                    ui.monospace(session.name(*address));
+                                });
+                            }
+
+                            if let Some(address) = removed {
+                                session.breakpoints.remove(&address);
+                                breakpoints_changed = true;
+                            }
+
+                            if ui.button(localizer.get("debugger-add")).clicked() {
+                                breakpoints_changed |= session.breakpoints.insert(self.address);
+                            }
+                        });
+
+                let watches = egui::CollapsingHeader::new(localizer.get("debugger-watches"))
+                    .default_open(session.layout.watches)
+                    .show(ui, |ui| {
+                        let mut removed = None;
+
+                        egui::Grid::new("watches").show(ui, |ui| {
+                            for (index, watch) in session.watches.iter_mut().enumerate() {
+                                if ui.small_button("×").clicked() {
+                                    removed = Some(index);
+                                }
+
+                                ui.monospace(Session::label_name(&session.labels, watch.address));
+                                ui.monospace(match current.memory.get(watch.address) {
+                                    Some(value) => format!("{:02X}", value),
+                                    None => "--".to_owned(),
+                                });
+
+                                breakpoints_changed |= ui
+                                    .checkbox(
+                                        &mut watch.break_on_write,
+                                        localizer.get("debugger-break-on-write"),
+                                    )
+                                    .changed();
+                                ui.end_row();
+                            }
+                        });
+
+                        if let Some(index) = removed {
+                            breakpoints_changed |= session.watches.remove(index).break_on_write;
+                            self.dirty = true;
+                        }
+
+                        if ui.button(localizer.get("debugger-add")).clicked() {
+                            session.watches.push(Watch {
+                                address: self.address,
+                                break_on_write: false,
+                            });
+                            self.dirty = true;
+                        }
+                    });
+
+                // a piano roll of the keypad, a column per tick and a row per key
+                let timeline =
+                    egui::CollapsingHeader::new(localizer.get("debugger-input-timeline"))
+                        .default_open(session.layout.timeline)
+                        .show(ui, |ui| {
+                            egui::ScrollArea::horizontal()
+                                .id_source("timeline")
+                                .stick_to_right(true)
+                                .show(ui, |ui| {
+                                    let (response, painter) = ui.allocate_painter(
+                                        egui::vec2(
+                                            TIMELINE_CELL_SIZE.x * self.timeline.len() as f32,
+                                            TIMELINE_CELL_SIZE.y * backend::KEY_COUNT as f32,
+                                        ),
+                                        egui::Sense::hover(),
+                                    );
+                                    let origin = response.rect.min;
+
+                                    painter.rect_filled(
+                                        response.rect,
+                                        0.0,
+                                        ui.visuals().extreme_bg_color,
+                                    );
+
+                                    for (column, frame) in self.timeline.iter().enumerate() {
+                                        for key in (0..backend::KEY_COUNT)
+                                            .filter(|key| frame.keys & 1 << key != 0)
+                                        {
+                                            painter.rect_filled(
+                                                egui::Rect::from_min_size(
+                                                    origin
+                                                        + TIMELINE_CELL_SIZE
+                                                            * egui::vec2(column as f32, key as f32),
+                                                    TIMELINE_CELL_SIZE,
+                                                ),
+                                                0.0,
+                                                highlight,
+                                            );
+                                        }
+                                    }
+
+                                    let hovered = response.hover_pos().and_then(|position| {
+                                        self.timeline.get(
+                                            ((position.x - origin.x) / TIMELINE_CELL_SIZE.x)
+                                                as usize,
+                                        )
+                                    });
+
+                                    if let Some(frame) = hovered {
+                                        let keys: String = (0..backend::KEY_COUNT)
+                                            .filter(|key| frame.keys & 1 << key != 0)
+                                            .map(|key| format!("{:X}", key))
+                                            .collect();
+
+                                        response.on_hover_text(localizer.format(
+                                            "debugger-timeline-frame",
+                                            &[
+                                                ("frame", &frame.frame),
+                                                ("address", &session.name(frame.index)),
+                                                ("keys", &keys),
+                                            ],
+                                        ));
+                                    }
+                                });
+                        });
+
+                // the timers over the last ticks, with how often the program reloads them to check
+                // the cadence of its timing loops
+                let timers = egui::CollapsingHeader::new(localizer.get("debugger-timers"))
+                    .default_open(session.layout.timers)
+                    .show(ui, |ui| {
+                        let series: [(String, Timer); 2] = [
+                            (localizer.get("debugger-timer-delay"), |frame| frame.delay),
+                            (localizer.get("debugger-timer-sound"), |frame| frame.sound),
+                        ];
+
+                        for (name, timer) in &series {
+                            ui.label(match reload_period(&self.timeline, *timer) {
+                                Some(period) => localizer.format(
+                                    "debugger-timer-period",
+                                    &[
+                                        ("timer", name),
+                                        ("ticks", &period),
+                                        (
+                                            "frequency",
+                                            &format!("{:.2}", TIMER_FREQUENCY / period as f64),
+                                        ),
+                                    ],
+                                ),
+                                None => localizer
+                                    .format("debugger-timer-unreloaded", &[("timer", name)]),
+                            });
+                        }
+
+                        egui::plot::Plot::new("timers")
+                            .height(TIMER_PLOT_HEIGHT)
+                            .include_y(0.0)
+                            .legend(egui::plot::Legend::default())
+                            .show(ui, |plot| {
+                                for (name, timer) in &series {
+                                    plot.line(
+                                        egui::plot::Line::new(
+                                            self.timeline
+                                                .iter()
+                                                .map(|frame| {
+                                                    [frame.frame as f64, timer(frame) as f64]
+                                                })
+                                                .collect::<egui::plot::PlotPoints>(),
+                                        )
+                                        .name(name),
+                                    );
+                                }
+                            });
+                    });
+
+                let collisions = egui::CollapsingHeader::new(localizer.get("debugger-collisions"))
+                    .default_open(session.layout.collisions)
+                    .show(ui, |ui| {
+                        if ui
+                            .checkbox(
+                                &mut session.layout.flash_collisions,
+                                localizer.get("debugger-flash-collisions"),
+                            )
+                            .changed()
+                        {
+                            self.dirty = true;
+                        }
+
+                        if self.collisions.is_empty() {
+                            ui.label(localizer.get("debugger-collisions-none"));
+                        }
+
+                        egui::ScrollArea::vertical()
+                            .id_source("collisions")
+                            .max_height(160.0)
+                            .stick_to_bottom(true)
+                            .show(ui, |ui| {
+                                for event in &self.collisions {
+                                    let text = localizer.format(
+                                        "debugger-collision",
+                                        &[
+                                            ("frame", &event.frame),
+                                            ("address", &session.name(event.collision.index)),
+                                            ("x", &event.collision.coordinates.0),
+                                            ("y", &event.collision.coordinates.1),
+                                            ("pixels", &event.collision.pixels.len()),
+                                        ],
+                                    );
+
+                                    // picking a collision enters the address of the instruction
+                                    if ui
+                                        .add(
+                                            egui::Button::new(
+                                                egui::RichText::new(text).monospace(),
+                                            )
+                                            .small(),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.address = event.collision.index;
+                                    }
+                                }
+                            });
+                    });
+
+                let labels = egui::CollapsingHeader::new(localizer.get("debugger-labels"))
+                    .default_open(session.layout.labels)
+                    .show(ui, |ui| {
+                        let mut removed = None;
+
+                        for (name, address) in session.labels.iter() {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("×").clicked() {
+                                    removed = Some(name.clone());
+                                }
+
+                                ui.monospace(format!("{:03X} {}", address, name));
+                            });
+                        }
+
+                        if let Some(name) = removed {
+                            session.labels.remove(&name);
+                            self.dirty = true;
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.label);
+
+                            if ui.button(localizer.get("debugger-add")).clicked()
+                                && !self.label.trim().is_empty()
+                            {
+                                session
+                                    .labels
+                                    .insert(self.label.trim().to_owned(), self.address);
+                                self.label.clear();
+                                self.dirty = true;
+                            }
+                        });
+                    });
+
+                let program = egui::CollapsingHeader::new(localizer.get("debugger-program"))
+                    .default_open(session.layout.program)
+                    .show(ui, |ui| {
+                        if let Some(integrity) = self.integrity.as_ref() {
+                            integrity.show(ui, localizer);
+                        }
+                    });
+
+                for (open, response) in [
+                    (&mut session.layout.registers, registers.openness),
+                    (&mut session.layout.stack, stack.openness),
+                    (&mut session.layout.statistics, statistics.openness),
+                    (&mut session.layout.memory, memory.openness),
+                    (&mut session.layout.disassembly, disassembly.openness),
+                    (&mut session.layout.control_flow, control_flow.openness),
+                    (&mut session.layout.references, references.openness),
+                    (&mut session.layout.call_graph, call_graph.openness),
+                    (&mut session.layout.breakpoints, breakpoints.openness),
+                    (&mut session.layout.watches, watches.openness),
+                    (&mut session.layout.timeline, timeline.openness),
+                    (&mut session.layout.timers, timers.openness),
+                    (&mut session.layout.collisions, collisions.openness),
+                    (&mut session.layout.labels, labels.openness),
+                    (&mut session.layout.program, program.openness),
+                ] {
+                    if *open != (response > 0.5) {
+                        *open = response > 0.5;
+                        self.dirty = true;
+                    }
+                }
+            });
+
+        if export {
+            self.export();
+        }
+
+        if import {
+            self.import();
+        }
+
+        if let Some(dot) = export_control_flow {
+            self.export_control_flow(dot);
+        }
+
+        if let Some(source) = export_disassembly {
+            self.export_disassembly(source);
+        }
+
+        if let Some(bytes) = selected {
+            self.select(bytes);
+        }
+
+        self.dirty |= breakpoints_changed;
+
+        breakpoints_changed
+    }
+
+    /// Returns the addresses of the watches that stop continuing to the next breakpoint when written
+    /// Shows the events of the last ticks as a track, with a lane for each kind of event and one
+    /// marking the snapshots, and returns the tick picked on it to rewind to the snapshot nearest
+    /// to
+    pub fn show_events(
+        &self,
+        ui: &mut egui::Ui,
+        localizer: &i18n::Localizer,
+        highlight: egui::Color32,
+    ) -> Option<u64> {
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(ui.available_width(), EVENT_TRACK_HEIGHT),
+            egui::Sense::click_and_drag(),
+        );
+        let rect = response.rect;
+
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        let first = self.timeline.first()?.frame;
+        let column = rect.width() / self.timeline.len() as f32;
+        let x = |frame: u64| rect.min.x + (frame - first) as f32 * column;
+
+        for frame in self.timeline.iter() {
+            for (lane, (event, _)) in EVENT_LANES.iter().enumerate() {
+                if frame.events.contains(*event) {
+                    painter.rect_filled(
+                        egui::Rect::from_min_size(
+                            egui::pos2(
+                                x(frame.frame),
+                                rect.min.y + EVENT_LANE_HEIGHT * lane as f32,
+                            ),
+                            egui::vec2(column.max(1.0), EVENT_LANE_HEIGHT),
+                        ),
+                        0.0,
+                        match *event == frontend::TickEvents::ERROR {
+                            true => ui.visuals().error_fg_color,
+                            false => highlight,
+                        },
+                    );
+                }
+            }
+        }
+
+        let snapshots = egui::Rect::from_min_max(
+            egui::pos2(rect.min.x, rect.max.y - EVENT_LANE_HEIGHT),
+            rect.max,
+        );
+
+        for frame in self.snapshots.iter().filter(|frame| **frame >= first) {
+            painter.vline(
+                x(*frame),
+                snapshots.y_range(),
+                egui::Stroke::new(1.0, ui.visuals().weak_text_color()),
+            );
+        }
+
+        let pointed = response
+            .hover_pos()
+            .or(response.interact_pointer_pos())
+            .and_then(|position| {
+                self.timeline
+                    .get(((position.x - rect.min.x) / column) as usize)
+            })?;
+
+        // the snapshot the program would be rewound to is marked while the track is scrubbed
+        if let Some(nearest) = self
+            .snapshots
+            .iter()
+            .filter(|frame| **frame >= first)
+            .min_by_key(|frame| frame.abs_diff(pointed.frame))
+        {
+            painter.vline(
+                x(*nearest),
+                rect.y_range(),
+                egui::Stroke::new(1.0, highlight),
+            );
+        }
+
+        let events: Vec<String> = EVENT_LANES
+            .iter()
+            .filter(|(event, _)| pointed.events.contains(*event))
+            .map(|(_, id)| localizer.get(id))
+            .collect();
+        let picked = response.clicked() || response.drag_released();
+
+        response.on_hover_text(localizer.format(
+            "debugger-event-frame",
+            &[
+                ("frame", &pointed.frame),
+                ("address", &self.session.name(pointed.index)),
+                (
+                    "events",
+                    &match events.is_empty() {
+                        true => localizer.get("event-none"),
+                        false => events.join(", "),
+                    },
+                ),
+            ],
+        ));
+
+        picked.then_some(pointed.frame)
+    }
+
+    pub fn watchpoints(&self) -> Vec<usize> {
+        self.session
+            .watches
+            .iter()
+            .filter(|watch| watch.break_on_write)
+            .map(|watch| watch.address)
+            .collect()
+    }
+
+    #[inline]
+    pub fn width(&self) -> f32 {
+        self.session.layout.width
+    }
+}
+
+impl MemorySearch {
+    /// Keeps the matches for which `filter` holds in `memory`, starting from every address
+    fn narrow(&mut self, memory: &[u8], filter: SearchFilter) {
+        let previous = &self.memory;
+        let holds = |address: &usize| {
+            let (before, now) = (previous.get(*address).copied(), memory[*address]);
+
+            match filter {
+                SearchFilter::Changed => before != Some(now),
+                SearchFilter::Decreased => before.is_some_and(|before| now < before),
+                SearchFilter::Equal(value) => now == value,
+                SearchFilter::Increased => before.is_some_and(|before| now > before),
+                SearchFilter::Unchanged => before == Some(now),
+            }
+        };
+
+        let matches = match self.matches.take() {
+            Some(matches) => matches.into_iter().filter(holds).collect(),
+            None => (0..memory.len()).filter(holds).collect(),
+        };
+
+        self.matches = Some(matches);
+        self.memory = memory.to_vec();
+    }
+}
+
+impl Session {
+    /// Shows the controls pinning `register` to a value up to `max`, starting from its current
+    /// `value`, and returns whether they were changed
+    fn freeze(
+        &mut self,
+        ui: &mut egui::Ui,
+        localizer: &i18n::Localizer,
+        register: &str,
+        value: usize,
+        max: usize,
+    ) -> bool {
+        let mut frozen = self.frozen.contains_key(register);
+        let mut changed = ui
+            .checkbox(&mut frozen, "")
+            .on_hover_text(localizer.get("debugger-freeze"))
+            .changed();
+
+        match frozen {
+            true => {
+                let frozen = self.frozen.entry(register.to_owned()).or_insert(value);
+
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(frozen)
+                            .clamp_range(0..=max)
+                            .hexadecimal(2, false, true),
+                    )
+                    .changed();
+            }
+            false => {
+                self.frozen.remove(register);
+            }
+        }
+
+        changed
+    }
+
+    fn label_name(labels: &collections::BTreeMap<String, usize>, address: usize) -> String {
+        match labels.iter().find(|(_, label)| **label == address) {
+            Some((name, _)) => format!("{:03X} {}", address, name),
+            None => format!("{:03X}", address),
+        }
+    }
+
+    /// Loads the session at `path`, a missing file is treated as an empty session
+    fn load(path: &path::Path) -> Result<Self, config::ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Marks the byte at `address` and the one after it as `region`, so that a line of code
+    /// becomes two lines of data and a line of data becomes an instruction
+    fn mark(&mut self, address: usize, region: disassembler::Region) {
+        for address in address..address + 2 {
+            let (marked, unmarked) = match region {
+                disassembler::Region::Code => (&mut self.code, &mut self.data),
+                disassembler::Region::Data => (&mut self.data, &mut self.code),
+            };
+
+            unmarked.remove(&address);
+            marked.insert(address);
+        }
+    }
+
+    #[inline]
+    fn name(&self, address: usize) -> String {
+        Self::label_name(&self.labels, address)
+    }
+
+    /// Names `address` `name`, replacing the labels it had
+    fn rename(&mut self, address: usize, name: &str) {
+        self.labels.retain(|_, label| *label != address);
+        self.labels.insert(name.to_owned(), address);
+    }
+
+    /// Returns the region of the byte at `address`, as marked or as inferred from `coverage`
+    fn region(&self, address: usize, coverage: &[backend::Coverage]) -> disassembler::Region {
+        if self.code.contains(&address) {
+            disassembler::Region::Code
+        } else if self.data.contains(&address) {
+            disassembler::Region::Data
+        } else {
+            disassembler::Region::infer(coverage.get(address).copied().unwrap_or_default())
+        }
+    }
+
+    fn save(&self, path: &path::Path) -> Result<(), config::ConfigError> {
+        Ok(fs::write(path, toml::to_string(self)?)?)
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            breakpoints: true,
+            call_graph: false,
+            collisions: false,
+            control_flow: false,
+            detached_display: false,
+            disassembly: false,
+            flash_collisions: false,
+            labels: false,
+            magnifier: false,
+            memory: true,
+            program: false,
+            references: false,
+            registers: true,
+            stack: false,
+            statistics: false,
+            timeline: false,
+            timers: false,
+            watches: true,
+            width: 280.0,
+        }
+    }
+}
+
+/// Shows the subroutine at `address` as a node of the call graph, with the subroutines it calls
+/// nested under it, down to the ones already on `path` from the entry of the program, which are
+/// shown without what they call to avoid following a recursion forever
+fn call_tree(
+    ui: &mut egui::Ui,
+    subroutines: &collections::BTreeMap<usize, &graph::Subroutine>,
+    address: usize,
+    path: &mut Vec<usize>,
+    name: &dyn Fn(usize) -> String,
+    followed: &mut Option<usize>,
+) {
+    let callees = match subroutines.get(&address) {
+        Some(subroutine) if !path.contains(&address) => subroutine.callees.as_slice(),
+        _ => &[],
+    };
+    let text = egui::RichText::new(name(address)).monospace();
+
+    if callees.is_empty() {
+        if ui.add(egui::Button::new(text).small()).clicked() {
+            *followed = Some(address);
+        }
+
+        return;
+    }
+
+    path.push(address);
+
+    let response = egui::CollapsingHeader::new(text)
+        .id_source(("call_tree", path.as_slice()))
+        .show(ui, |ui| {
+            for callee in callees {
+                call_tree(ui, subroutines, *callee, path, name, followed);
+            }
+        });
+
+    if response.header_response.double_clicked() {
+        *followed = Some(address);
+    }
+
+    path.pop();
+}
+
+/// Formats the bytes of `memory` at `addresses` in rows of 16 like the memory panel, each starting
+/// with the address of its first byte in `digits` hexadecimal digits
+fn hex_dump(memory: &[u8], addresses: ops::RangeInclusive<usize>, digits: usize) -> String {
+    let mut dump = String::new();
+
+    for address in addresses.take_while(|address| *address < memory.len()) {
+        if dump.is_empty() || address % MEMORY_ROW_SIZE == 0 {
+            if !dump.is_empty() {
+                dump.push('\n');
+            }
+
+            dump.push_str(&format!("{:0width$X}", address, width = digits));
+        }
+
+        dump.push_str(&format!(" {:02X}", memory[address]));
+    }
+
+    dump
+}
+
+/// Formats `line` like the disassembly panel, `name` being the name of its address
+fn listing(line: &disassembler::Line, name: &str) -> String {
+    let bytes: String = line
+        .bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+
+    format!("{} {:<4} {}", name, bytes, line.text)
+}
+
+/// Returns the ticks between the last two times the program set `timer` higher than it was, in
+/// `timeline`
+fn reload_period(timeline: &[frontend::TimelineFrame], timer: Timer) -> Option<u64> {
+    let mut reloads = timeline
+        .windows(2)
+        .rev()
+        .filter(|frames| timer(&frames[1]) > timer(&frames[0]))
+        .map(|frames| frames[1].frame);
+
+    let last = reloads.next()?;
+
+    Some(last - reloads.next()?)
+}
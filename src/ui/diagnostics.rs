@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+/// Address of the 8 bytes of the sprite the test pattern is drawn with
+const PATTERN_SPRITE: u16 = 0x250;
+/// Address of the subroutine filling the pattern sprite, right after it
+const FILL: u16 = PATTERN_SPRITE + 8;
+
+/// The diagnostics program up to its pattern sprite, it draws the test pattern, beeps, and then
+/// echoes the pressed key at the center of the display with a short beep
+const PROGRAM: [u16; 40] = [
+    0x00E0,        // clear the display
+    0x6E00,        // VE = 0, the row of the pattern sprite
+    0x6D00,        // VD = 0, the column of the pattern sprite
+    0x2000 | FILL, // fill the pattern sprite
+    0xA000 | PATTERN_SPRITE,
+    0xDDE8, // draw the pattern sprite at VD, VE
+    0x7D08,
+    0x3D40,
+    0x1206, // next column
+    0x7E08,
+    0x3E20,
+    0x1204, // next row
+    0x601E,
+    0xF018, // beep for half a second
+    0x6AFF, // VA = FF, the key shown, none yet
+    0x6C1E, // VC = 30, the column of the key shown
+    0x6D0D, // VD = 13, the row of the key shown
+    0x6B00, // VB = 0, the key checked
+    0xEBA1,
+    0x1230, // found a pressed key
+    0x7B01,
+    0x3B10,
+    0x1224, // check the next key
+    0x6BFF, // VB = FF, no key is pressed
+    0x5BA0,
+    0x1236, // the pressed key changed
+    0x1222, // check the keys again
+    0x3AFF,
+    0x224A, // erase the key shown
+    0x8AB0,
+    0x3AFF,
+    0x2242, // show the pressed key
+    0x1222, // check the keys again
+    0x224A, // show the key in VA and beep shortly
+    0x6006,
+    0xF018,
+    0x00EE,
+    0xFA29, // draw the key in VA
+    0xDCD5,
+    0x00EE,
+];
+
+/// What the diagnostics program fills the display with
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestPattern {
+    #[default]
+    Checkerboard,
+    /// Random pixels, for spotting pixels stuck on or off
+    Noise,
+}
+
+impl TestPattern {
+    pub const ALL: [Self; 2] = [Self::Checkerboard, Self::Noise];
+}
+
+/// Returns a program that checks the display, the sound and the keys without a program of the user
+pub fn program(pattern: TestPattern) -> Vec<u8> {
+    // subroutine that fills the pattern sprite, for the noise to be different in every sprite
+    let fill: &[u16] = match pattern {
+        TestPattern::Checkerboard => &[0x00EE],
+        TestPattern::Noise => &[
+            0xC0FF,
+            0xC1FF,
+            0xC2FF,
+            0xC3FF,
+            0xC4FF,
+            0xC5FF,
+            0xC6FF,
+            0xC7FF,
+            0xA000 | PATTERN_SPRITE,
+            0xF755,
+            0x00EE,
+        ],
+    };
+
+    let mut program: Vec<u8> = PROGRAM.iter().flat_map(|word| word.to_be_bytes()).collect();
+    program.extend([0xAA, 0x55].repeat(4));
+    program.extend(fill.iter().flat_map(|word| word.to_be_bytes()));
+
+    program
+}
@@ -1,33 +1,14 @@
-use std::fs;
-use std::io;
 use std::path;
 
 pub struct FilePicker {
     dialog: egui_file::FileDialog,
+    folder_dialog: egui_file::FileDialog,
 }
 
 impl FilePicker {
     pub fn is_open(&self) -> bool {
         self.dialog.state() == egui_file::State::Open
-    }
-
-    pub fn load(path: Option<&path::PathBuf>) -> Result<Option<Vec<u8>>, String> {
-        path.and_then(|path| {
-            Some(fs::read(path).map_err(|error| match error.kind() {
-                io::ErrorKind::NotFound => {
-                    format!(
-                        "file '{}' does not exists",
-                        path.file_name()
-                            .and_then(|file_name| file_name.to_str())
-                            .unwrap()
-                    )
-                }
-                _ => {
-                    format!("{}", error)
-                }
-            }))
-        })
-        .transpose()
+            || self.folder_dialog.state() == egui_file::State::Open
     }
 
     pub fn new() -> Self {
@@ -36,6 +17,10 @@ impl FilePicker {
                 .resizable(false)
                 .show_new_folder(false)
                 .show_rename(false),
+            folder_dialog: egui_file::FileDialog::select_folder(None)
+                .resizable(false)
+                .show_new_folder(false)
+                .show_rename(false),
         }
     }
 
@@ -43,9 +28,15 @@ impl FilePicker {
         self.dialog.open();
     }
 
+    pub fn open_folder(&mut self) {
+        self.folder_dialog.open();
+    }
+
     pub fn show(&mut self, ctx: &egui::Context) -> Option<path::PathBuf> {
-        if self.dialog.show(ctx).selected() {
-            return self.dialog.path();
+        for dialog in [&mut self.dialog, &mut self.folder_dialog] {
+            if dialog.show(ctx).selected() {
+                return dialog.path();
+            }
         }
 
         None
@@ -0,0 +1,38 @@
+use sha1::{Digest, Sha1};
+
+use crate::i18n;
+
+/// The size and the checksums of a program as it was read from its file, for it to be told apart
+/// from other versions of it
+#[derive(Clone)]
+pub struct Integrity {
+    pub crc32: u32,
+    pub sha1: String,
+    pub size: usize,
+}
+
+impl Integrity {
+    pub fn new(contents: &[u8]) -> Self {
+        Self {
+            crc32: crc32fast::hash(contents),
+            sha1: format!("{:x}", Sha1::digest(contents)),
+            size: contents.len(),
+        }
+    }
+
+    /// Shows the size and the checksums, one per row
+    pub fn show(&self, ui: &mut egui::Ui, localizer: &i18n::Localizer) {
+        for (id, value) in [
+            (
+                "integrity-size",
+                localizer.format("integrity-bytes", &[("bytes", &self.size)]),
+            ),
+            ("integrity-sha1", self.sha1.clone()),
+            ("integrity-crc32", format!("{:08x}", self.crc32)),
+        ] {
+            super::menu_item(ui, localizer.get(id), |ui| {
+                ui.add(egui::Label::new(egui::RichText::new(value).monospace()).wrap(false))
+            });
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::fs;
+use std::path;
+use std::sync::{self, atomic};
+use std::thread;
+use std::time;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config;
+
+const CATALOG_FILE_NAME: &str = "library.json";
+/// Extensions of the files taken for programs
+const EXTENSIONS: [&str; 4] = ["ch8", "c8", "sc8", "xo8"];
+/// Time between scans of the library directories, for the programs added to them to show up
+const SCAN_INTERVAL: time::Duration = time::Duration::from_secs(30);
+
+/// A program found in the library directories
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
+pub struct Entry {
+    pub author: Option<String>,
+    /// Modification time of the file in seconds since the Unix epoch, for telling whether it has to
+    /// be hashed again
+    modified: u64,
+    pub path: path::PathBuf,
+    pub sha256: String,
+    size: u64,
+    /// Title from the metadata of the program, if it has any
+    pub title: Option<String>,
+}
+
+/// Catalog of the programs in the library directories, kept up to date by a thread scanning them
+/// and saved between runs so that unchanged files aren't hashed again
+pub struct Library {
+    directories: sync::Arc<sync::Mutex<Vec<path::PathBuf>>>,
+    entries: sync::Arc<sync::Mutex<Vec<Entry>>>,
+    stopped: sync::Arc<atomic::AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Entry {
+    /// Returns the title of the program, or its file name if it has none
+    pub fn name(&self) -> String {
+        match self.title.as_ref() {
+            Some(title) => title.clone(),
+            None => self
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        }
+    }
+}
+
+impl Library {
+    fn catalog_path() -> Option<path::PathBuf> {
+        dirs::data_dir().map(|path| path.join(env!("CARGO_PKG_NAME")).join(CATALOG_FILE_NAME))
+    }
+
+    pub fn new(directories: Vec<path::PathBuf>) -> Self {
+        let catalog: Vec<Entry> = Self::catalog_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        let directories = sync::Arc::new(sync::Mutex::new(directories));
+        let entries = sync::Arc::new(sync::Mutex::new(catalog));
+        let stopped = sync::Arc::new(atomic::AtomicBool::new(false));
+
+        let thread = {
+            let (directories, entries, stopped) =
+                (directories.clone(), entries.clone(), stopped.clone());
+
+            thread::spawn(move || {
+                while !stopped.load(atomic::Ordering::Relaxed) {
+                    let directories = directories.lock().unwrap().clone();
+                    let previous = entries.lock().unwrap().clone();
+                    let scanned = scan(&directories, &previous);
+
+                    if scanned != previous {
+                        save(&scanned);
+                        *entries.lock().unwrap() = scanned;
+                    }
+
+                    thread::park_timeout(SCAN_INTERVAL);
+                }
+            })
+        };
+
+        Self {
+            directories,
+            entries,
+            stopped,
+            thread: Some(thread),
+        }
+    }
+
+    /// Returns the programs whose title, author or file name contain `query`, ignoring case
+    pub fn search(&self, query: &str) -> Vec<Entry> {
+        let query = query.to_lowercase();
+
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|entry| {
+                [
+                    entry.title.as_deref(),
+                    entry.author.as_deref(),
+                    entry
+                        .path
+                        .file_name()
+                        .and_then(|file_name| file_name.to_str()),
+                ]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&query))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Sets the directories to scan and scans them right away
+    pub fn set_directories(&self, directories: Vec<path::PathBuf>) {
+        *self.directories.lock().unwrap() = directories;
+
+        if let Some(thread) = self.thread.as_ref() {
+            thread.thread().unpark();
+        }
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        self.stopped.store(true, atomic::Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Returns the entries of the programs in `directories` and the ones below them, reusing the
+/// `previous` entries of the files that didn't change
+fn scan(directories: &[path::PathBuf], previous: &[Entry]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut pending: Vec<path::PathBuf> = directories.to_vec();
+
+    while let Some(directory) = pending.pop() {
+        let read = match fs::read_dir(&directory) {
+            Ok(read) => read,
+            Err(_) => continue,
+        };
+
+        for path in read.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+
+            let extension = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(str::to_lowercase);
+
+            if !EXTENSIONS
+                .iter()
+                .any(|known| extension.as_deref() == Some(known))
+            {
+                continue;
+            }
+
+            let (size, modified) = match fs::metadata(&path) {
+                Ok(metadata) => (
+                    metadata.len(),
+                    metadata
+                        .modified()
+                        .ok()
+                        .and_then(|modified| modified.duration_since(time::UNIX_EPOCH).ok())
+                        .map_or(0, |modified| modified.as_secs()),
+                ),
+                Err(_) => continue,
+            };
+
+            let unchanged = previous.iter().find(|entry| {
+                entry.path == path && entry.size == size && entry.modified == modified
+            });
+
+            if let Some(entry) = unchanged {
+                entries.push(entry.clone());
+                continue;
+            }
+
+            let contents = match fs::read(&path) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            };
+            let metadata = config::Metadata::load(&path).ok().flatten();
+
+            entries.push(Entry {
+                author: metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.author.clone()),
+                modified,
+                sha256: format!("{:x}", Sha256::digest(&contents)),
+                size,
+                title: metadata.and_then(|metadata| metadata.title),
+                path,
+            });
+        }
+    }
+
+    entries.sort_by_key(Entry::name);
+
+    entries
+}
+
+fn save(entries: &[Entry]) {
+    let path = match Library::catalog_path() {
+        Some(path) => path,
+        None => return,
+    };
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&path, serde_json::to_string(entries).unwrap()));
+
+    if let Err(error) = result {
+        eprintln!("couldn't save the library catalog, {}", error);
+    }
+}
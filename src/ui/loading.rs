@@ -0,0 +1,50 @@
+use std::path;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::backend;
+use crate::frontend;
+use crate::loader;
+
+/// The font and the program being read by a thread, for slow drives and network shares not to
+/// freeze the interface while they're read
+pub struct Loading {
+    receiver: mpsc::Receiver<Loaded>,
+    /// State to restore once the program is started, e.g. the one of a crash
+    pub restore: Option<frontend::Snapshot>,
+}
+
+/// What the files were read as
+pub struct Loaded {
+    /// `None` standing for the built-in font
+    pub font: Option<Result<Box<[u8; backend::FONT_SIZE]>, loader::LoadError>>,
+    /// The program along with its path, `None` when only the font was read
+    pub program: Option<(path::PathBuf, Result<Vec<u8>, loader::LoadError>)>,
+}
+
+impl Loading {
+    pub fn new(font: Option<path::PathBuf>, program: Option<path::PathBuf>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            // nobody is waiting for the files anymore when another loading replaced this one
+            let _ = sender.send(Loaded {
+                font: font.map(|path| loader::font(&path)),
+                program: program.map(|path| {
+                    let program = loader::read_program(&path);
+                    (path, program)
+                }),
+            });
+        });
+
+        Self {
+            receiver,
+            restore: None,
+        }
+    }
+
+    /// Returns what the files were read as, once they're read
+    pub fn poll(&self) -> Option<Loaded> {
+        self.receiver.try_recv().ok()
+    }
+}
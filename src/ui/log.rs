@@ -0,0 +1,73 @@
+use std::collections;
+
+/// Lines kept in the log, the oldest ones are dropped past it
+const LOG_LENGTH: usize = 1000;
+
+/// What the emulator and the running program reported, for the log panel
+#[derive(Default)]
+pub struct Log {
+    lines: collections::VecDeque<Line>,
+    /// Text the program printed to the console since its last complete line
+    partial: String,
+}
+
+pub struct Line {
+    pub source: Source,
+    pub text: String,
+}
+
+/// Where a line of the log comes from
+#[derive(Clone, Copy, PartialEq)]
+pub enum Source {
+    /// The console of the pseudo-peripherals of the program
+    Console,
+    Emulator,
+}
+
+impl Log {
+    #[inline]
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.partial.clear();
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.partial.is_empty()
+    }
+
+    /// Returns the lines of the log, oldest first
+    #[inline]
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.lines.iter()
+    }
+
+    /// Returns the text the program printed since its last complete line
+    #[inline]
+    pub fn partial(&self) -> &str {
+        &self.partial
+    }
+
+    /// Adds the bytes the program printed to the console, the ones that aren't printable ASCII
+    /// are shown as escapes, e.g. `\x1B`
+    pub fn print(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            match byte {
+                b'\n' => {
+                    let text = std::mem::take(&mut self.partial);
+                    self.push(Source::Console, text);
+                }
+                b' '..=b'~' => self.partial.push(*byte as char),
+                _ => self.partial.push_str(&format!("\\x{:02X}", byte)),
+            }
+        }
+    }
+
+    pub fn push(&mut self, source: Source, text: String) {
+        if self.lines.len() == LOG_LENGTH {
+            self.lines.pop_front();
+        }
+
+        self.lines.push_back(Line { source, text });
+    }
+}
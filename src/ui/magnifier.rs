@@ -0,0 +1,86 @@
+use crate::backend;
+use crate::frontend;
+use crate::i18n;
+
+/// Number of pixels shown on each side of the pixel under the pointer
+const RADIUS: usize = 4;
+/// Size of a magnified pixel in points
+const CELL_SIZE: f32 = 14.0;
+/// Distance between the pointer and the lens, for the lens not to cover the pixels it magnifies
+const OFFSET: egui::Vec2 = egui::vec2(24.0, 24.0);
+
+/// Shows a lens next to the pointer with the pixels of `display` around the one under it, when
+/// it's over the display drawn in `rect`
+pub fn show(
+    ctx: &egui::Context,
+    localizer: &i18n::Localizer,
+    rect: egui::Rect,
+    orientation: super::Orientation,
+    display: &backend::interfaces::DisplayBuffer,
+    colors: frontend::Colors,
+    highlight: egui::Color32,
+) {
+    let (pointer, hovered) = match ctx.pointer_hover_pos().and_then(|pointer| {
+        super::display_pixel(rect, orientation, pointer).map(|hovered| (pointer, hovered))
+    }) {
+        Some(hovered) => hovered,
+        None => return,
+    };
+
+    egui::Area::new("magnifier")
+        .fixed_pos(pointer + OFFSET)
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                let side = (RADIUS * 2 + 1) as f32 * CELL_SIZE;
+                let (lens, _) =
+                    ui.allocate_exact_size(egui::Vec2::splat(side), egui::Sense::hover());
+
+                for row in 0..=RADIUS * 2 {
+                    for column in 0..=RADIUS * 2 {
+                        // the pixels around the hovered one wrap around the edges like sprites do
+                        let (x, y) = (
+                            (hovered.0 + backend::DISPLAY_BUFFER_WIDTH + column - RADIUS)
+                                % backend::DISPLAY_BUFFER_WIDTH,
+                            (hovered.1 + backend::DISPLAY_BUFFER_HEIGHT + row - RADIUS)
+                                % backend::DISPLAY_BUFFER_HEIGHT,
+                        );
+                        let cell = egui::Rect::from_min_size(
+                            lens.min + egui::vec2(column as f32, row as f32) * CELL_SIZE,
+                            egui::Vec2::splat(CELL_SIZE),
+                        );
+
+                        ui.painter().rect(
+                            cell,
+                            0.0,
+                            match display.buffer[y][x] {
+                                true => colors.active,
+                                false => colors.inactive,
+                            },
+                            egui::Stroke::new(1.0, egui::Color32::from_gray(64)),
+                        );
+                    }
+                }
+
+                ui.painter().rect_stroke(
+                    egui::Rect::from_min_size(
+                        lens.min + egui::Vec2::splat(RADIUS as f32 * CELL_SIZE),
+                        egui::Vec2::splat(CELL_SIZE),
+                    ),
+                    0.0,
+                    egui::Stroke::new(2.0, highlight),
+                );
+
+                ui.label(
+                    egui::RichText::new(localizer.format(
+                        match display.buffer[hovered.1][hovered.0] {
+                            true => "debugger-magnifier-on",
+                            false => "debugger-magnifier-off",
+                        },
+                        &[("x", &hovered.0), ("y", &hovered.1)],
+                    ))
+                    .monospace(),
+                );
+            });
+        });
+}
@@ -1,24 +1,94 @@
-use std::fmt::Write;
+use std::collections;
 use std::path;
+use std::sync;
 use std::time;
 
 use egui::color_picker;
 
 use crate::backend;
+use crate::config;
+use crate::defaults;
 use crate::frontend;
+use crate::i18n;
+use crate::loader;
+use crate::patch;
 
+mod crash_report;
+mod debugger;
+mod diagnostics;
 mod file_picker;
+mod integrity;
+mod library;
+mod loading;
+mod log;
+mod magnifier;
+mod orientation;
+mod playlist;
+mod preview;
+mod resume;
+mod screenshot;
+pub mod sprites;
+mod stretch;
+mod theme;
 
+pub use diagnostics::TestPattern;
+pub use orientation::{Orientation, Rotation};
+pub use stretch::Stretch;
+pub use theme::Theme;
+
+/// Sizes of the audio buffer offered in frames, smaller ones for lower latency and larger ones for
+/// fewer dropouts
+const AUDIO_BUFFER_SIZES: [Option<u32>; 6] = [
+    None,
+    Some(256),
+    Some(512),
+    Some(1024),
+    Some(2048),
+    Some(4096),
+];
+/// Time between the checks of whether the audio device set is there, to fall back to the default
+/// one when it's gone and to go back to it when it's there again
+const AUDIO_DEVICE_CHECK_INTERVAL: time::Duration = time::Duration::from_secs(5);
+/// Color of the pixels turned off by a collision when they're flashed in debug mode, unlike any of
+/// the themes
+const COLLISION_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 0, 128);
+/// Number of divergences of a conformance run listed, the rest are only counted
+const CONFORMANCE_DIVERGENCES_SHOWN: usize = 256;
+/// Size the display window starts at when it's detached from the debugger
+const DETACHED_DISPLAY_SIZE: egui::Vec2 = egui::vec2(512.0, 256.0);
 const ERROR_DISPLAY_DURATION: time::Duration = time::Duration::from_secs(2);
+const HASH_OVERLAY_BACKGROUND_ALPHA: u8 = 192;
+const HASH_OVERLAY_FONT_SIZE: f32 = 12.0;
+/// Addresses programs are loaded at offered, along with the machines loading them there
+const LOAD_ADDRESSES: [(usize, &str); 2] =
+    [(backend::MEMORY_PADDING, "CHIP-8"), (0x600, "ETI-660")];
+/// Time between the checks for the files being read
+const LOADING_POLL_INTERVAL: time::Duration = time::Duration::from_millis(50);
+/// Number of programs of the library search listed, the rest are left out
+const LIBRARY_RESULTS_SHOWN: usize = 50;
+const LOG_SIZE: egui::Vec2 = egui::vec2(480.0, 240.0);
 const MENU_SPACING: f32 = 2.5;
-const PRIMARY_COLOR: egui::Color32 = egui::Color32::from_rgb(0x81, 0x5B, 0xA4);
-const SECONDARY_COLOR: egui::Color32 = egui::Color32::from_rgb(0x1C, 0x1C, 0x1C);
+/// Time the step hotkey has to be held for before it repeats
+const STEP_REPEAT_DELAY: time::Duration = time::Duration::from_millis(400);
+const UI_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+/// Width of the outline drawn around the display while the sound timer is active
+const VISUAL_BEEP_WIDTH: f32 = 8.0;
 
 pub struct App {
-    _stream: rodio::OutputStream,
+    /// `None` while no audio output could be opened, programs are played without sound then
+    audio: Option<frontend::audio::Output>,
+    /// The configuration the emulator was started with, command line options included
+    config: config::EmulatorConfig,
+    /// The configuration file as it was loaded
+    config_file: config::ConfigFile,
+    /// Where the settings changed while running are saved to on exit
+    config_path: Option<path::PathBuf>,
+    debugger: debugger::Debugger,
     display_texture: egui::TextureId,
     file_picker: file_picker::FilePicker,
     frontend: frontend::FrontendHandle,
+    library: library::Library,
+    localizer: i18n::Localizer,
     state: State,
 }
 
@@ -27,350 +97,2583 @@ struct Error {
     timestamp: time::Instant,
 }
 
+/// The tabs the settings of the menu are grouped in
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MenuTab {
+    #[default]
+    Program,
+    Display,
+    Audio,
+    Input,
+    Quirks,
+    Debug,
+}
+
+#[derive(Clone, Copy)]
 enum Selection {
+    BeepSound,
     Font,
+    LibraryDirectory,
+    Patch,
     Program,
 }
 
 struct State {
-    colors: frontend::Colors,
-    debug_mode: bool,
+    /// Whether the window was last set to be kept on top
+    always_on_top: bool,
+    audio_checked: time::Instant,
+    /// Names of the audio output devices, as of the last time they were listed
+    audio_devices: Vec<String>,
+    config: config::EmulatorConfig,
+    /// The results of the last conformance run, until they're closed
+    conformance: Option<backend::conformance::Report>,
+    /// The report of the last fatal error, until it's saved or another program is started
+    crash_report: Option<crash_report::CrashReport>,
+    /// The program that stopped with the last fatal error and its state at that moment, until
+    /// another program is started
+    crash_snapshot: Option<(path::PathBuf, frontend::Snapshot)>,
     error: Error,
-    fade_effect: bool,
+    /// Address of the loop the program was paused in for being halted there, while it's paused
+    halted: Option<usize>,
+    /// The last program started and what it was read as
+    integrity: Option<(path::PathBuf, integrity::Integrity)>,
+    /// What was running when the emulator was last closed, until something else is started
+    last_session: Option<resume::LastSession>,
+    log: log::Log,
+    /// Whether the log panel is open
+    log_shown: bool,
+    /// The configuration the running program was started with
+    running_config: config::EmulatorConfig,
     menu_raised: bool,
+    menu_tab: MenuTab,
+    /// What the programs of the library are searched for
+    library_query: String,
+    /// The files being read for the program to be started, or for the font to be replaced when
+    /// no program is read, until they're read
+    loading: Option<loading::Loading>,
+    /// The program the metadata was looked up for, and its metadata if it has any
+    metadata: (Option<path::PathBuf>, Option<config::Metadata>),
     font_path: Option<path::PathBuf>,
-    program_path: Option<path::PathBuf>,
+    playlist: playlist::Playlist,
+    program_started: time::Instant,
+    /// The action waiting for a key press to be bound to it
+    rebinding: Option<config::Action>,
     selection: Selection,
+    snapshot: Option<frontend::Snapshot>,
+    /// When the next step is due while the step hotkey is held in debug mode
+    step_repeat: Option<time::Instant>,
+    /// The program and the load address the sprite sheet was made for, and the sheet if sprites
+    /// were found in it
+    sprites: (Option<(path::PathBuf, usize)>, Option<egui::TextureHandle>),
+}
+
+impl MenuTab {
+    pub const ALL: [Self; 6] = [
+        Self::Program,
+        Self::Display,
+        Self::Audio,
+        Self::Input,
+        Self::Quirks,
+        Self::Debug,
+    ];
 }
 
 impl App {
     fn handle_input(&mut self, ctx: &egui::Context) {
-        if self.frontend.started() {
+        if !self.frontend.started() {
+            return;
+        }
+
+        let hotkeys = &self.state.running_config.hotkeys;
+        let step_held;
+        let actions: Vec<config::Action> = {
             let mut input = ctx.input_mut();
 
-            if input.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
-                if !self.state.menu_raised {
-                    if !self.frontend.suspended() {
-                        self.frontend.suspend();
+            let actions = config::Action::ALL
+                .into_iter()
+                .filter(|action| {
+                    *action != config::Action::Turbo
+                        && input.consume_shortcut(&hotkeys.get(*action).shortcut())
+                })
+                .collect();
+
+            self.frontend.set_turbo(
+                !self.state.menu_raised && input.key_down(hotkeys.get(config::Action::Turbo).key),
+            );
+
+            step_held = input.key_down(hotkeys.get(config::Action::Step).key);
+
+            // releases still go through while the menu is raised, so that no key is left held
+            for event in input.events.iter() {
+                if let egui::Event::Key {
+                    key: physical_key,
+                    pressed,
+                    ..
+                } = *event
+                {
+                    if pressed && self.state.menu_raised {
+                        continue;
                     }
 
-                    self.state.menu_raised = true;
-                    return;
+                    for (key, binding) in self.state.running_config.keymap.iter() {
+                        if binding == physical_key {
+                            self.frontend.send_key(key, pressed);
+                        }
+                    }
                 }
+            }
+
+            actions
+        };
+
+        // holding the step hotkey keeps stepping after a delay, like a key repeat, at most once a frame
+        // so that the steps don't get ahead of the messages reporting them
+        let rate = self.state.running_config.step_repeat_rate;
 
-                if !self.state.debug_mode {
+        match self
+            .state
+            .step_repeat
+            .filter(|_| step_held && rate > 0 && !self.state.menu_raised)
+        {
+            Some(due) => {
+                let now = time::Instant::now();
+
+                if now >= due && self.frontend.suspended() {
                     self.frontend.resume();
+                    self.state.step_repeat = Some(now + time::Duration::from_secs(1) / rate as u32);
                 }
 
-                self.state.menu_raised = false;
+                ctx.request_repaint_after(due.saturating_duration_since(now));
             }
+            None => self.state.step_repeat = None,
+        }
 
-            if !self.state.debug_mode || input.consume_key(egui::Modifiers::NONE, egui::Key::Enter)
-            {
-                if self.state.debug_mode {
-                    self.frontend.resume();
+        // the input is unlocked by now, the frontend thread may need it to request a repaint before stopping
+        for action in actions {
+            if self.state.menu_raised && action != config::Action::Pause {
+                continue;
+            }
+
+            match action {
+                config::Action::LoadState => {
+                    if let Some(snapshot) = self.state.snapshot.as_ref() {
+                        self.frontend
+                            .with_stopped(|frontend| frontend.restore(snapshot));
+                        self.debugger.clear();
+                        self.inspect();
+                    }
                 }
 
-                if let Some(message) = self.frontend.message() {
-                    match message {
-                        Ok(message) => {
-                            eprintln!("{}", message);
+                // continuing again pauses the program where it is
+                config::Action::Continue if self.state.running_config.debug_mode => {
+                    match (self.frontend.continuing(), self.frontend.suspended()) {
+                        (true, false) => {
+                            self.frontend.set_continuing(false);
+                            self.frontend.suspend();
+                            self.inspect();
+                            self.debugger
+                                .set_status(self.localizer.get("debugger-paused"));
                         }
-                        Err(error) => {
-                            if error.is_fatal() {
-                                self.state.error.message.clear();
-                                let _ = write!(self.state.error.message, "fatal error, {}", error);
-                                return self.frontend.stop().reset();
-                            }
+                        (false, true) => {
+                            self.frontend.set_continuing(true);
+                            self.frontend.resume();
+                        }
+                        _ => {}
+                    }
+                }
 
-                            eprintln!("{}", error);
+                config::Action::Continue => {}
+
+                config::Action::Log => self.state.log_shown = !self.state.log_shown,
+
+                config::Action::NextProgram | config::Action::PreviousProgram => {
+                    return self.switch_program(action == config::Action::NextProgram);
+                }
+
+                config::Action::Pause => {
+                    if !self.state.menu_raised {
+                        self.frontend.set_continuing(false);
+
+                        if !self.frontend.suspended() {
+                            self.frontend.suspend();
                         }
+
+                        self.state.menu_raised = true;
+                        return;
+                    }
+
+                    if !self.state.running_config.debug_mode {
+                        self.frontend.resume();
+                    }
+
+                    self.state.menu_raised = false;
+                }
+
+                config::Action::Reset => {
+                    self.frontend.with_stopped(frontend::Frontend::reset);
+                    self.debugger.clear();
+                    self.inspect();
+                }
+
+                config::Action::SaveState => {
+                    self.state.snapshot =
+                        Some(self.frontend.with_stopped(|frontend| frontend.snapshot()));
+                }
+
+                config::Action::Screenshot => {
+                    match screenshot::save(
+                        &self.frontend.with_stopped(|frontend| frontend.screenshot()),
+                    ) {
+                        Ok(path) => eprintln!("Saved screenshot to {}", path.display()),
+                        Err(error) => eprintln!("couldn't save the screenshot, {}", error),
                     }
                 }
+
+                config::Action::Step => {
+                    if self.state.running_config.debug_mode && self.frontend.suspended() {
+                        self.frontend.resume();
+                        self.state.step_repeat = Some(time::Instant::now() + STEP_REPEAT_DELAY);
+                    }
+                }
+
+                config::Action::Turbo => unreachable!(),
             }
         }
-    }
 
-    fn menu(&mut self, ctx: &egui::Context) {
-        if let Some(path) = self.file_picker.show(ctx) {
-            match self.state.selection {
-                Selection::Font => self.state.font_path.insert(path),
-                Selection::Program => self.state.program_path.insert(path),
-            };
-        }
+        let console = self.frontend.take_console();
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.add_enabled_ui(
-                !self.frontend.started() && !self.file_picker.is_open(),
-                |ui| {
-                    ui.add_visible_ui(
-                        !self.state.error.message.is_empty()
-                            && self.state.error.timestamp.elapsed() < ERROR_DISPLAY_DURATION,
-                        |ui| {
-                            ui.vertical_centered_justified(|ui| {
-                                ui.colored_label(egui::Color32::RED, &self.state.error.message)
-                            });
+        if !console.is_empty() {
+            self.state.log.print(&console);
+        }
 
-                            ctx.request_repaint_after(ERROR_DISPLAY_DURATION);
-                        },
-                    );
+        // the messages that weren't taken in time are gone, but they're not silently gone
+        let dropped = self.frontend.take_dropped_messages();
 
-                    ui.heading("Backend Parameters");
-                    ui.separator();
-
-                    for item_data in [
-                        ("Font", &mut self.state.font_path, Selection::Font),
-                        ("Program", &mut self.state.program_path, Selection::Program),
-                    ] {
-                        menu_item(ui, item_data.0, |ui| {
-                            if item_data.1.is_some()
-                                && ui
-                                    .add(
-                                        egui::Label::new(
-                                            egui::RichText::new("×").color(PRIMARY_COLOR),
-                                        )
-                                        .sense(egui::Sense::click()),
-                                    )
-                                    .clicked()
-                            {
-                                *item_data.1 = None;
-                            }
+        if dropped > 0 {
+            let message = self
+                .localizer
+                .format("log-dropped-messages", &[("messages", &dropped)]);
 
-                            let file_name = item_data
-                                .1
-                                .as_ref()
-                                .and_then(|path| path.file_name())
-                                .and_then(|file_name| file_name.to_str());
+            eprintln!("{}", message);
+            self.state.log.push(log::Source::Emulator, message);
+        }
 
-                            ui.colored_label(
-                                egui::Color32::LIGHT_GRAY,
-                                file_name.unwrap_or("None"),
-                            );
-                        });
-                        ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
-                            if ui
-                                .selectable_label(false, format!("📂 Load {}", item_data.0))
-                                .clicked()
-                            {
-                                self.state.error.message.clear();
-                                self.file_picker.open();
-                                self.state.selection = item_data.2;
-                            }
-                        });
+        // a burst of messages is taken at once instead of lagging behind a message per frame
+        let mut status = None;
 
-                        ui.add_space(MENU_SPACING);
+        for message in self.frontend.messages() {
+            match message {
+                Ok(message) => {
+                    eprintln!("{}", message);
+                    self.state.log.push(log::Source::Emulator, message.clone());
+                    status = Some(message);
+                }
+                Err(error) => {
+                    if self.state.running_config.json_errors {
+                        eprintln!("{}", error.to_json());
                     }
 
-                    ui.add_space(MENU_SPACING.powi(3) - MENU_SPACING);
+                    if let frontend::FrontendError::Halted(address) = error {
+                        self.state.halted = Some(address);
+                    }
 
-                    ui.heading("Frontend Parameters");
-                    ui.separator();
+                    self.state
+                        .log
+                        .push(log::Source::Emulator, self.localizer.frontend_error(&error));
 
-                    for item_data in [
-                        ("Active Color", &mut self.state.colors.active),
-                        ("Inactive Color", &mut self.state.colors.inactive),
-                    ] {
-                        menu_item(ui, item_data.0, |ui| {
-                            color_picker::color_edit_button_srgba(
-                                ui,
-                                item_data.1,
-                                color_picker::Alpha::Opaque,
-                            );
-                        });
+                    // a program that ended itself is back to the menu, without a crash report
+                    if error.is_exit() {
+                        self.frontend.stop().reset();
+                        self.state.error.message.clear();
+                        self.state.error.timestamp = time::Instant::now();
+                        self.state
+                            .error
+                            .message
+                            .push_str(&self.localizer.get("error-program-exited"));
 
-                        ui.add_space(MENU_SPACING);
+                        return;
                     }
 
-                    menu_item(ui, "Fade Effect", |ui| {
-                        ui.checkbox(&mut self.state.fade_effect, "");
-                    });
+                    if error.is_fatal() {
+                        self.state.error.message.clear();
+                        self.state.error.timestamp = time::Instant::now();
+                        self.state.error.message.push_str(&self.localizer.format(
+                            "error-fatal",
+                            &[("error", &self.localizer.frontend_error(&error))],
+                        ));
 
-                    ui.add_space(MENU_SPACING);
+                        let frontend = self.frontend.stop();
+                        let display = frontend.screenshot();
+                        let snapshot = frontend.snapshot();
+                        frontend.reset();
 
-                    if self.state.program_path.is_some() && !self.frontend.started() {
-                        ui.separator();
+                        self.state.crash_snapshot = self
+                            .state
+                            .playlist
+                            .current()
+                            .map(|program| (program.clone(), snapshot));
 
-                        ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
-                            if ui.button("▶ Start").clicked() {
-                                self.start();
-                            }
-                        });
-                    }
-                },
-            );
+                        self.state.crash_report = Some(crash_report::CrashReport::new(
+                            &error,
+                            &self.state.running_config,
+                            display,
+                            self.state.playlist.current().map(path::PathBuf::as_path),
+                        ));
 
-            if self.frontend.started() {
-                ui.separator();
+                        return;
+                    }
 
-                ui.vertical_centered_justified(|ui| {
-                    if ui.button("■ Stop").clicked() {
-                        self.frontend.stop().reset();
+                    if !self.state.running_config.json_errors {
+                        eprintln!("{}", error);
                     }
-                });
+                }
             }
-        });
+        }
+
+        // the state is inspected once for the whole burst, as of the last message
+        if let Some(status) = status {
+            self.inspect();
+            self.debugger.set_status(status);
+        }
     }
 
-    pub fn new(cc: &eframe::CreationContext, options: frontend::Options) -> Self {
-        let mut visuals = cc.egui_ctx.style().visuals.clone();
+    /// Returns the metadata of the current program, looking it up when the program changed
+    fn metadata(&mut self) -> Option<&config::Metadata> {
+        let current = self.state.playlist.current();
 
-        visuals.selection.bg_fill = PRIMARY_COLOR;
-        visuals.selection.stroke.color = egui::Color32::WHITE;
+        if self.state.metadata.0.as_ref() != current {
+            let metadata = match current.map(|program| config::Metadata::load(program)) {
+                Some(Ok(metadata)) => metadata,
+                Some(Err(error)) => {
+                    self.state.error.timestamp = time::Instant::now();
+                    self.state.error.message.clear();
+                    self.state.error.message.push_str(
+                        &self
+                            .localizer
+                            .format("error-metadata", &[("error", &error)]),
+                    );
 
-        visuals.widgets.hovered.bg_fill = PRIMARY_COLOR;
+                    None
+                }
+                None => None,
+            };
 
-        visuals.widgets.noninteractive.fg_stroke.color = egui::Color32::WHITE;
+            self.state.metadata = (current.cloned(), metadata);
+        }
 
-        visuals.window_fill = SECONDARY_COLOR;
-        cc.egui_ctx.set_visuals(visuals);
+        self.state.metadata.1.as_ref()
+    }
 
-        let (stream, handle) = rodio::OutputStream::try_default().unwrap();
+    /// Returns the sprite sheet of the current program, making it when the program changed
+    fn sprite_sheet(&mut self, ctx: &egui::Context) -> Option<&egui::TextureHandle> {
+        let load_address = self.state.config.quirks.load_address;
+        let current = self
+            .state
+            .playlist
+            .current()
+            .map(|program| (program.clone(), load_address));
 
-        let debug_mode = options.debug_mode;
-        let fade_effect = options.fade_effect;
-        let frontend = frontend::Frontend::new(&cc.egui_ctx, options, handle);
-        let state = State {
-            colors: frontend.colors,
-            debug_mode,
-            fade_effect,
-            error: Error {
-                message: String::with_capacity(128),
-                timestamp: time::Instant::now(),
-            },
-            menu_raised: false,
-            font_path: None,
-            program_path: None,
-            selection: Selection::Font,
-        };
+        if self.state.sprites.0 != current {
+            let sheet = current
+                .as_ref()
+                .and_then(|(program, _)| std::fs::read(program).ok())
+                .and_then(|program| {
+                    let sprites = sprites::find(&program, load_address);
 
-        Self {
-            _stream: stream,
-            display_texture: frontend.display_texture(),
-            file_picker: file_picker::FilePicker::new(),
-            frontend: frontend::FrontendHandle::new(frontend),
-            state,
+                    (!sprites.is_empty()).then(|| {
+                        ctx.load_texture(
+                            "sprites",
+                            sprites::sheet(&program, load_address, &sprites),
+                            egui::TextureOptions::NEAREST,
+                        )
+                    })
+                });
+
+            self.state.sprites = (current, sheet);
         }
+
+        self.state.sprites.1.as_ref()
     }
 
-    pub fn start(&mut self) {
-        self.state.error.message.clear();
+    /// Passes the state of the program to the debugger when in debug mode
+    fn inspect(&mut self) {
+        if self.state.running_config.debug_mode && self.frontend.started() {
+            let (snapshot, timeline, snapshots, collisions) =
+                self.frontend.with_stopped(|frontend| {
+                    (
+                        frontend.snapshot(),
+                        frontend.timeline().iter().copied().collect(),
+                        frontend.snapshot_frames(),
+                        frontend.collisions().iter().cloned().collect(),
+                    )
+                });
 
-        let boxed;
-        let frontend = self.frontend.get().unwrap();
+            self.debugger.push(snapshot);
+            self.debugger.set_collisions(collisions);
+            self.debugger.set_timeline(timeline, snapshots);
+        }
+    }
 
-        let font: Option<&[u8; backend::FONT_SIZE]> =
-            match file_picker::FilePicker::load(self.state.font_path.as_ref()) {
-                Ok(Some(font)) if font.len() == backend::FONT_SIZE => {
-                    boxed = font.into_boxed_slice(); // store the boxed slice so that it is not dropped immediately
+    /// Returns the font read, `None` standing for the built-in one, reporting why it can't be used
+    /// and forgetting it if so
+    fn check_font(
+        &mut self,
+        font: Option<Result<Box<[u8; backend::FONT_SIZE]>, loader::LoadError>>,
+    ) -> Result<Option<Box<[u8; backend::FONT_SIZE]>>, ()> {
+        match font.transpose() {
+            Ok(font) => Ok(font),
+            Err(error) => {
+                let error = self.localizer.load_error(&error);
 
-                    Some(boxed.as_ref().try_into().unwrap())
-                }
+                self.state.font_path = None;
+                self.push_error(&self.localizer.format("error-font", &[("error", &error)]));
 
-                Ok(Some(_)) => {
-                    self.state.font_path = None;
-                    self.state.error.timestamp = time::Instant::now();
-                    self.state
-                        .error
-                        .message
-                        .push_str("couldn't load the font, attempt to load invalid font");
+                Err(())
+            }
+        }
+    }
 
-                    return;
-                }
+    /// Shows `message` along with the errors already shown, each on its own line
+    fn push_error(&mut self, message: &str) {
+        if !self.state.error.message.is_empty() {
+            self.state.error.message.push('\n');
+        }
 
-                Ok(None) => None,
+        self.state.error.timestamp = time::Instant::now();
+        self.state.error.message.push_str(message);
+    }
 
-                Err(error) => {
-                    self.state.font_path = None;
-                    self.state.error.timestamp = time::Instant::now();
-                    let _ = write!(
-                        self.state.error.message,
-                        "couldn't load the font, {}",
-                        error
-                    );
-                    return;
-                }
-            };
-        let program = match file_picker::FilePicker::load(self.state.program_path.as_ref()) {
-            Ok(program) => program.unwrap(),
+    /// Shows the results of the last conformance run, if they're not closed
+    fn conformance(&mut self, ctx: &egui::Context) {
+        let mut open = self.state.conformance.is_some();
 
-            Err(error) => {
-                self.state.program_path = None;
-                self.state.error.timestamp = time::Instant::now();
-                let _ = write!(
-                    self.state.error.message,
-                    "couldn't load the program, {}",
-                    error
-                );
-                return;
-            }
+        if let Some(report) = self.state.conformance.as_ref() {
+            egui::Window::new(self.localizer.get("conformance"))
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.label(match report.divergences.len() {
+                        0 => self
+                            .localizer
+                            .format("conformance-passed", &[("cases", &report.cases)]),
+                        divergences => self.localizer.format(
+                            "conformance-failed",
+                            &[("cases", &report.cases), ("divergences", &divergences)],
+                        ),
+                    });
+
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for divergence in report
+                            .divergences
+                            .iter()
+                            .take(CONFORMANCE_DIVERGENCES_SHOWN)
+                        {
+                            ui.separator();
+                            self.divergence(ui, divergence);
+                        }
+                    });
+                });
+        }
+
+        if !open {
+            self.state.conformance = None;
+        }
+    }
+
+    /// Tells that the program was paused for being halted, with a way to keep it running anyway
+    fn halted(&mut self, ctx: &egui::Context) {
+        // the program may have been resumed otherwise since, e.g. from the pause menu
+        if !self.frontend.suspended() {
+            self.state.halted = None;
+        }
+
+        let Some(address) = self.state.halted else {
+            return;
         };
 
-        frontend.colors = self.state.colors;
-        frontend.options.debug_mode = self.state.debug_mode;
-        frontend.options.fade_effect = self.state.fade_effect;
+        egui::Window::new(self.localizer.get("halted"))
+            .anchor(
+                egui::Align2::CENTER_TOP,
+                egui::Vec2::splat(MENU_SPACING * 4.0),
+            )
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(self.localizer.format(
+                    "halted-message",
+                    &[("address", &format!("0x{:03x}", address))],
+                ));
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(self.localizer.get("halted-keep-running"))
+                        .clicked()
+                    {
+                        self.frontend.with_stopped(frontend::Frontend::ignore_halts);
+                        self.frontend.resume();
+                        self.state.halted = None;
+                    }
 
-        frontend.update_texture();
-        match frontend.backend.load(font, &program) {
-            Ok(()) => (),
+                    if ui.button(self.localizer.get("halted-restart")).clicked() {
+                        self.restart();
+                    }
+                });
+            });
+    }
+
+    /// Shows the log panel while it's open
+    fn log(&mut self, ctx: &egui::Context) {
+        let mut shown = self.state.log_shown;
+
+        egui::Window::new(self.localizer.get("log"))
+            .open(&mut shown)
+            .default_size(LOG_SIZE)
+            .show(ctx, |ui| {
+                if ui.button(self.localizer.get("log-clear")).clicked() {
+                    self.state.log.clear();
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        if self.state.log.is_empty() {
+                            ui.weak(self.localizer.get("log-empty"));
+                        }
+
+                        for line in self.state.log.lines() {
+                            match line.source {
+                                log::Source::Console => ui.monospace(&line.text),
+                                log::Source::Emulator => ui.weak(&line.text),
+                            };
+                        }
+
+                        if !self.state.log.partial().is_empty() {
+                            ui.monospace(self.state.log.partial());
+                        }
+                    });
+            });
+
+        self.state.log_shown = shown;
+    }
+
+    /// Lists what the core did differently from the reference in a case of a conformance run
+    fn divergence(&self, ui: &mut egui::Ui, divergence: &backend::conformance::Divergence) {
+        let case = &divergence.case;
+        let operands: String = [case.instruction.operand_x(), case.instruction.operand_y()]
+            .into_iter()
+            .collect::<collections::BTreeSet<usize>>()
+            .into_iter()
+            .map(|register| format!(" V{:X}={:02X}", register, case.registers[register]))
+            .collect();
+
+        ui.monospace(format!("{}{}", case.instruction, operands));
+
+        let actual = match divergence.actual.as_ref() {
+            Ok(actual) => actual,
             Err(error) => {
-                self.state.program_path = None;
-                self.state.error.timestamp = time::Instant::now();
-                let _ = write!(
-                    self.state.error.message,
-                    "couldn't load the program, {}",
-                    error
-                );
+                ui.label(self.localizer.backend_error(error));
                 return;
             }
         };
 
-        self.frontend.start();
-        self.state.menu_raised = false;
+        for (register, (actual, expected)) in actual
+            .registers
+            .iter()
+            .zip(divergence.expected.registers.iter())
+            .enumerate()
+            .filter(|(_, (actual, expected))| actual != expected)
+        {
+            ui.label(self.localizer.format(
+                "conformance-register",
+                &[
+                    ("register", &format!("V{:X}", register)),
+                    ("actual", &format!("{:02X}", actual)),
+                    ("expected", &format!("{:02X}", expected)),
+                ],
+            ));
+        }
+
+        for (offset, (actual, expected)) in actual
+            .memory
+            .iter()
+            .zip(divergence.expected.memory.iter())
+            .enumerate()
+            .filter(|(_, (actual, expected))| actual != expected)
+        {
+            ui.label(self.localizer.format(
+                "conformance-memory",
+                &[
+                    ("offset", &offset),
+                    ("actual", &actual),
+                    ("expected", &expected),
+                ],
+            ));
+        }
     }
-}
 
-impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        self.handle_input(ctx);
+    fn menu(&mut self, ctx: &egui::Context) {
+        let (font_path, builtin_font) = (self.state.font_path.clone(), self.state.config.font);
 
-        if !self.frontend.started() || self.state.menu_raised {
-            return self.menu(ctx);
+        if let Some(path) = self.file_picker.show(ctx) {
+            match self.state.selection {
+                Selection::BeepSound => self.state.config.beep_sound = Some(path),
+                Selection::Font => self.state.font_path = Some(path),
+                Selection::LibraryDirectory => {
+                    self.state.config.library_directories.push(path);
+                    self.library
+                        .set_directories(self.state.config.library_directories.clone());
+                }
+                Selection::Patch => {
+                    if let Some(program) = self.state.playlist.current().cloned() {
+                        let mut patches = self.config_file.patches(&program);
+                        patches.push(path);
+                        self.set_patches(&program, &patches);
+                    }
+                }
+                Selection::Program => {
+                    self.state.playlist.add(path);
+                    self.state.playlist.select(self.state.playlist.len() - 1);
+                }
+            }
         }
 
-        let window_size = frame.info().window_info.size;
-        let size;
-        let margin;
+        if let Some(action) = self.state.rebinding {
+            let hotkey = ctx.input().events.iter().find_map(|event| match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                } => Some(config::Hotkey::pressed(*modifiers, *key)),
+                _ => None,
+            });
 
-        if window_size[0] / window_size[1] <= backend::DISPLAY_BUFFER_ASPECT_RATIO
-            && window_size[0] > window_size[1]
-        {
-            size = window_size;
-            margin = egui::style::Margin::same(0.0);
-        } else {
-            size = egui::vec2(
-                window_size[0],
-                window_size[0] / backend::DISPLAY_BUFFER_ASPECT_RATIO,
-            );
-            margin = egui::style::Margin::symmetric(0.0, (window_size[1] - size[1]) / 2.0);
-        };
+            if let Some(hotkey) = hotkey {
+                self.state.config.hotkeys.set(action, hotkey);
+                self.state.rebinding = None;
+            }
+        }
 
-        egui::CentralPanel::default()
-            .frame(egui::Frame::central_panel(&ctx.style()).inner_margin(margin))
-            .show(ctx, |ui| {
-                ui.add(egui::Image::new(self.display_texture, size));
+        egui::TopBottomPanel::bottom("menu_actions").show(ctx, |ui| {
+            ui.add_space(MENU_SPACING);
+
+            if self.state.loading.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(self.localizer.get("menu-loading"));
+                });
+                ui.add_space(MENU_SPACING);
+            }
+
+            // nothing else is started while the files are read
+            let enabled = !self.file_picker.is_open() && self.state.loading.is_none();
+
+            ui.add_enabled_ui(enabled, |ui| {
+                if self.frontend.started() {
+                    ui.vertical_centered_justified(|ui| {
+                        if ui.button(self.localizer.get("menu-restart")).clicked() {
+                            self.restart();
+                        }
+
+                        if ui.button(self.localizer.get("menu-stop")).clicked() {
+                            self.frontend.stop().reset();
+                        }
+                    });
+
+                    return;
+                }
+
+                ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
+                    if self.state.playlist.is_empty()
+                        && self.state.last_session.is_some()
+                        && ui.button(self.localizer.get("menu-resume")).clicked()
+                    {
+                        self.resume();
+                    }
+
+                    // without a program, the setup can be checked with the diagnostics one
+                    match self.state.playlist.is_empty() {
+                        true => {
+                            if ui.button(self.localizer.get("menu-diagnostics")).clicked() {
+                                self.start_diagnostics();
+                            }
+                        }
+                        false => {
+                            if ui.button(self.localizer.get("menu-start")).clicked() {
+                                self.start();
+                            }
+                        }
+                    }
+                });
             });
-    }
-}
 
-pub fn menu_item(
-    ui: &mut egui::Ui,
-    text: impl Into<egui::WidgetText>,
-    add_contents: impl FnOnce(&mut egui::Ui),
-) {
-    ui.horizontal(|ui| {
-        ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), |ui| {
-            ui.label(text)
+            ui.add_space(MENU_SPACING);
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // the tabs can be looked through while most of the settings can't be changed
+            ui.horizontal(|ui| {
+                for tab in MenuTab::ALL {
+                    ui.selectable_value(
+                        &mut self.state.menu_tab,
+                        tab,
+                        self.localizer.menu_tab(tab),
+                    );
+                }
+            });
+
+            ui.separator();
+
+            ui.add_enabled_ui(
+                !self.frontend.started() && !self.file_picker.is_open(),
+                |ui| {
+                    ui.add_visible_ui(
+                        !self.state.error.message.is_empty()
+                            && self.state.error.timestamp.elapsed() < ERROR_DISPLAY_DURATION,
+                        |ui| {
+                            ui.vertical_centered_justified(|ui| {
+                                ui.colored_label(egui::Color32::RED, &self.state.error.message)
+                            });
+
+                            ctx.request_repaint_after(ERROR_DISPLAY_DURATION);
+                        },
+                    );
+
+                    let saved = self.state.crash_report.as_ref().and_then(|report| {
+                        let clicked = ui
+                            .vertical_centered_justified(|ui| {
+                                ui.button(self.localizer.get("menu-save-crash-report"))
+                                    .clicked()
+                            })
+                            .inner;
+
+                        clicked.then(|| report.save())
+                    });
+
+                    match saved {
+                        Some(Ok(path)) => {
+                            eprintln!("Saved the crash report to {}", path.display());
+                            self.state.crash_report = None;
+                        }
+                        Some(Err(error)) => eprintln!("couldn't save the crash report, {}", error),
+                        None => {}
+                    }
+
+                    // the state can only be loaded into the program that crashed
+                    let crashed = matches!(
+                        (self.state.crash_snapshot.as_ref(), self.state.playlist.current()),
+                        (Some((crashed, _)), Some(current)) if crashed == current
+                    );
+
+                    if crashed
+                        && ui
+                            .vertical_centered_justified(|ui| {
+                                ui.button(self.localizer.get("menu-load-crash-state"))
+                                    .clicked()
+                            })
+                            .inner
+                    {
+                        self.load_crash_state();
+                    }
+                },
+            );
+
+            ui.add_enabled_ui(!self.file_picker.is_open(), |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    // the display and program tabs have settings a running program takes on and the
+                    // debug tab has actions on it, they lock the others themselves
+                    ui.set_enabled(
+                        !self.frontend.started()
+                            || matches!(
+                                self.state.menu_tab,
+                                MenuTab::Debug | MenuTab::Display | MenuTab::Program
+                            ),
+                    );
+
+                    match self.state.menu_tab {
+                        MenuTab::Program => self.program_tab(ctx, ui),
+                        MenuTab::Display => self.display_tab(ctx, ui),
+                        MenuTab::Audio => self.audio_tab(ui),
+                        MenuTab::Input => self.input_tab(ui),
+                        MenuTab::Quirks => self.quirks_tab(ui),
+                        MenuTab::Debug => self.debug_tab(ui),
+                    }
+                });
+            });
+        });
+
+        // the font of a paused program is replaced as soon as another one is picked
+        if self.frontend.started()
+            && (self.state.font_path != font_path || self.state.config.font != builtin_font)
+        {
+            self.replace_font();
+        }
+
+        self.conformance(ctx);
+    }
+
+    /// Shows the sound settings in the menu
+    fn audio_tab(&mut self, ui: &mut egui::Ui) {
+        menu_item(ui, self.localizer.get("menu-audio-device"), |ui| {
+            ui.horizontal(|ui| {
+                if ui.small_button("⟳").clicked() {
+                    self.state.audio_devices = frontend::audio::output_devices();
+                }
+
+                egui::ComboBox::from_id_source("audio_device")
+                    .selected_text(match self.state.config.audio_device.as_ref() {
+                        Some(device) => device.clone(),
+                        None => self.localizer.get("menu-default"),
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut self.state.config.audio_device,
+                            None,
+                            self.localizer.get("menu-default"),
+                        );
+
+                        for device in self.state.audio_devices.iter() {
+                            ui.selectable_value(
+                                &mut self.state.config.audio_device,
+                                Some(device.clone()),
+                                device,
+                            );
+                        }
+                    });
+            })
+            .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-audio-buffer-size"), |ui| {
+            let frames = |size: Option<u32>| match size {
+                Some(size) => self.localizer.format("menu-frames", &[("frames", &size)]),
+                None => self.localizer.get("menu-default"),
+            };
+
+            egui::ComboBox::from_id_source("audio_buffer_size")
+                .selected_text(frames(self.state.config.audio_buffer_size))
+                .show_ui(ui, |ui| {
+                    for size in AUDIO_BUFFER_SIZES {
+                        ui.selectable_value(
+                            &mut self.state.config.audio_buffer_size,
+                            size,
+                            frames(size),
+                        );
+                    }
+                })
+                .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-beep-sound"), |ui| {
+            let label = self.localizer.get("menu-beep-sound");
+
+            ui.horizontal(|ui| {
+                if ui
+                    .small_button("📂")
+                    .on_hover_text(self.localizer.format("menu-load", &[("item", &label)]))
+                    .clicked()
+                {
+                    self.state.error.message.clear();
+                    self.file_picker.open();
+                    self.state.selection = Selection::BeepSound;
+                }
+
+                if self.state.config.beep_sound.is_some()
+                    && ui
+                        .small_button("×")
+                        .on_hover_text(self.localizer.format("menu-clear", &[("item", &label)]))
+                        .clicked()
+                {
+                    self.state.config.beep_sound = None;
+                }
+
+                ui.colored_label(
+                    egui::Color32::LIGHT_GRAY,
+                    match self.state.config.beep_sound.as_ref() {
+                        Some(path) => file_name(path).to_owned(),
+                        None => self.localizer.get("menu-built-in"),
+                    },
+                )
+            })
+            .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-pitched-beep"), |ui| {
+            ui.checkbox(&mut self.state.config.pitched_beep, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-audio-latency"), |ui| {
+            ui.label(
+                match self
+                    .audio
+                    .as_ref()
+                    .and_then(frontend::audio::Output::latency)
+                {
+                    Some(latency) => self.localizer.format(
+                        "menu-milliseconds",
+                        &[(
+                            "milliseconds",
+                            &format!("{:.1}", latency.as_secs_f64() * 1000.0),
+                        )],
+                    ),
+                    None => "-".to_owned(),
+                },
+            )
+        });
+
+        ui.add_space(MENU_SPACING);
+    }
+
+    /// Shows the settings of debug mode and the debugging tools in the menu
+    fn debug_tab(&mut self, ui: &mut egui::Ui) {
+        // saves the display as the program drew it, without the scaling and the effects of a
+        // screenshot
+        ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
+            let button = egui::Button::new(self.localizer.get("menu-dump-display"));
+
+            if ui.add_enabled(self.frontend.started(), button).clicked() {
+                match screenshot::save_display(
+                    &self
+                        .frontend
+                        .with_stopped(|frontend| frontend.display_image()),
+                ) {
+                    Ok(path) => eprintln!("Saved the display to {}", path.display()),
+                    Err(error) => eprintln!("couldn't save the display, {}", error),
+                }
+            }
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        ui.set_enabled(!self.frontend.started());
+
+        menu_item(ui, self.localizer.get("menu-log"), |ui| {
+            ui.checkbox(&mut self.state.log_shown, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(
+            ui,
+            self.localizer.get("menu-self-modification-warnings"),
+            |ui| ui.checkbox(&mut self.state.config.self_modification_warnings, ""),
+        );
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-stall-warning"), |ui| {
+            ui.add(
+                egui::DragValue::new(&mut self.state.config.stall_warning_seconds)
+                    .clamp_range(0..=600)
+                    .suffix(self.localizer.get("menu-seconds-suffix")),
+            )
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-step-repeat-rate"), |ui| {
+            ui.add(
+                egui::DragValue::new(&mut self.state.config.step_repeat_rate)
+                    .clamp_range(0..=60)
+                    .suffix(self.localizer.get("menu-per-second-suffix")),
+            )
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        // checks the arithmetic of the core against a reference, with the quirks programs are run
+        // with by default
+        ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
+            if ui.button(self.localizer.get("menu-conformance")).clicked() {
+                self.state.conformance = Some(backend::conformance::run(
+                    self.state.config.core,
+                    self.state.config.quirks,
+                ));
+            }
+        });
+    }
+
+    /// Shows the settings of the display and the user interface in the menu
+    fn display_tab(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let appearance = (self.state.config.colors, self.state.config.fade_effect);
+
+        menu_item(ui, self.localizer.get("menu-theme"), |ui| {
+            let theme = self.state.config.theme;
+
+            let response = egui::ComboBox::from_id_source("theme")
+                .selected_text(self.localizer.theme(theme))
+                .show_ui(ui, |ui| {
+                    for available in Theme::ALL {
+                        ui.selectable_value(
+                            &mut self.state.config.theme,
+                            available,
+                            self.localizer.theme(available),
+                        );
+                    }
+                })
+                .response;
+
+            // the display colors follow the theme, they can still be changed afterwards
+            if self.state.config.theme != theme {
+                self.state.config.theme.apply(ctx);
+                self.state.config.colors = self.state.config.theme.colors();
+            }
+
+            response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        for item_data in [
+            ("menu-active-color", &mut self.state.config.colors.active),
+            (
+                "menu-inactive-color",
+                &mut self.state.config.colors.inactive,
+            ),
+        ] {
+            menu_item(ui, self.localizer.get(item_data.0), |ui| {
+                color_picker::color_edit_button_srgba(ui, item_data.1, color_picker::Alpha::Opaque)
+            });
+
+            ui.add_space(MENU_SPACING);
+        }
+
+        menu_item(ui, self.localizer.get("menu-fade-effect"), |ui| {
+            ui.checkbox(&mut self.state.config.fade_effect, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-preview"), |ui| {
+            preview::show(
+                ui,
+                self.state.config.font.data(),
+                self.state.config.colors,
+                self.state.config.fade_effect,
+            )
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        // the colors and the fade effect are applied to a running program right away, from the
+        // pause menu
+        if self.frontend.started()
+            && (self.state.config.colors, self.state.config.fade_effect) != appearance
+        {
+            self.state.running_config.colors = self.state.config.colors;
+            self.state.running_config.fade_effect = self.state.config.fade_effect;
+            self.frontend
+                .set_appearance(self.state.config.colors, self.state.config.fade_effect);
+        }
+
+        ui.set_enabled(!self.frontend.started());
+
+        menu_item(ui, self.localizer.get("menu-rotation"), |ui| {
+            egui::ComboBox::from_id_source("rotation")
+                .selected_text(
+                    self.localizer
+                        .rotation(self.state.config.orientation.rotation),
+                )
+                .show_ui(ui, |ui| {
+                    for rotation in Rotation::ALL {
+                        ui.selectable_value(
+                            &mut self.state.config.orientation.rotation,
+                            rotation,
+                            self.localizer.rotation(rotation),
+                        );
+                    }
+                })
+                .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-mirrored"), |ui| {
+            ui.checkbox(&mut self.state.config.orientation.mirrored, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-scaling-filter"), |ui| {
+            egui::ComboBox::from_id_source("scaling_filter")
+                .selected_text(
+                    self.localizer
+                        .scaling_filter(self.state.config.scaling_filter),
+                )
+                .show_ui(ui, |ui| {
+                    for filter in frontend::ScalingFilter::ALL {
+                        ui.selectable_value(
+                            &mut self.state.config.scaling_filter,
+                            filter,
+                            self.localizer.scaling_filter(filter),
+                        );
+                    }
+                })
+                .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-stretch"), |ui| {
+            egui::ComboBox::from_id_source("stretch")
+                .selected_text(self.localizer.stretch(self.state.config.stretch))
+                .show_ui(ui, |ui| {
+                    for stretch in Stretch::ALL {
+                        ui.selectable_value(
+                            &mut self.state.config.stretch,
+                            stretch,
+                            self.localizer.stretch(stretch),
+                        );
+                    }
+                })
+                .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-visual-beep"), |ui| {
+            ui.checkbox(&mut self.state.config.visual_beep, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-controls-overlay"), |ui| {
+            ui.checkbox(&mut self.state.config.controls_overlay, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-hash-overlay"), |ui| {
+            ui.checkbox(&mut self.state.config.hash_overlay, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-language"), |ui| {
+            let language = self.state.config.language.clone();
+
+            let response = egui::ComboBox::from_id_source("language")
+                .selected_text(&language)
+                .show_ui(ui, |ui| {
+                    for available in i18n::Localizer::available_languages(
+                        self.state.config.locale_directory().as_deref(),
+                    ) {
+                        ui.selectable_value(
+                            &mut self.state.config.language,
+                            available.clone(),
+                            available,
+                        );
+                    }
+                })
+                .response;
+
+            if self.state.config.language != language {
+                match i18n::Localizer::new(
+                    &self.state.config.language,
+                    self.state.config.locale_directory().as_deref(),
+                ) {
+                    Ok(localizer) => self.localizer = localizer,
+                    Err(error) => {
+                        self.state.config.language = language;
+                        self.state.error.timestamp = time::Instant::now();
+                        self.state.error.message.clear();
+                        self.state.error.message.push_str(
+                            &self
+                                .localizer
+                                .format("error-language", &[("error", &error)]),
+                        );
+                    }
+                }
+            }
+
+            response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-test-pattern"), |ui| {
+            egui::ComboBox::from_id_source("test_pattern")
+                .selected_text(self.localizer.test_pattern(self.state.config.test_pattern))
+                .show_ui(ui, |ui| {
+                    for pattern in TestPattern::ALL {
+                        ui.selectable_value(
+                            &mut self.state.config.test_pattern,
+                            pattern,
+                            self.localizer.test_pattern(pattern),
+                        );
+                    }
+                })
+                .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-ui-scale"), |ui| {
+            ui.add(egui::Slider::new(
+                &mut self.state.config.ui_scale,
+                UI_SCALE_RANGE,
+            ))
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-always-on-top"), |ui| {
+            ui.checkbox(&mut self.state.config.always_on_top, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(
+            ui,
+            self.localizer.get("menu-remember-window-geometry"),
+            |ui| ui.checkbox(&mut self.state.config.remember_window_geometry, ""),
+        );
+    }
+
+    /// Shows the settings of the keypad and the hotkeys in the menu
+    fn input_tab(&mut self, ui: &mut egui::Ui) {
+        menu_item(ui, self.localizer.get("menu-key-priority"), |ui| {
+            egui::ComboBox::from_id_source("key_priority")
+                .selected_text(self.localizer.key_priority(self.state.config.key_priority))
+                .show_ui(ui, |ui| {
+                    for priority in backend::KeyPriority::ALL {
+                        ui.selectable_value(
+                            &mut self.state.config.key_priority,
+                            priority,
+                            self.localizer.key_priority(priority),
+                        );
+                    }
+                })
+                .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-input-latency"), |ui| {
+            ui.label(match self.frontend.input_latency() {
+                Some(latency) => self.localizer.format(
+                    "menu-milliseconds",
+                    &[(
+                        "milliseconds",
+                        &format!("{:.1}", latency.as_secs_f64() * 1000.0),
+                    )],
+                ),
+                None => "-".to_owned(),
+            })
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        ui.add_space(MENU_SPACING.powi(3) - MENU_SPACING);
+
+        ui.heading(self.localizer.get("menu-hotkeys"));
+        ui.separator();
+
+        for action in config::Action::ALL {
+            menu_item(ui, self.localizer.action(action), |ui| {
+                let rebinding = self.state.rebinding == Some(action);
+                let text = match rebinding {
+                    true => self.localizer.get("menu-press-key"),
+                    false => self.state.config.hotkeys.get(action).to_string(),
+                };
+
+                let response = ui.selectable_label(rebinding, text);
+
+                if response.clicked() {
+                    self.state.rebinding = Some(action);
+                }
+
+                response
+            });
+
+            ui.add_space(MENU_SPACING);
+        }
+    }
+
+    /// Shows the built-in fonts with their characters, to pick the one used without a font file,
+    /// followed by the big font
+    fn builtin_font(&mut self, ui: &mut egui::Ui) {
+        let colors = self.state.config.colors;
+
+        ui.add_enabled_ui(self.state.font_path.is_none(), |ui| {
+            menu_item(ui, self.localizer.get("menu-builtin-font"), |ui| {
+                let response = egui::ComboBox::from_id_source("builtin_font")
+                    .selected_text(self.localizer.font(self.state.config.font))
+                    .show_ui(ui, |ui| {
+                        for font in backend::Font::ALL {
+                            ui.horizontal(|ui| {
+                                preview::strip(ui, font.data(), preview::CHARACTER_WIDTH, colors);
+                                ui.selectable_value(
+                                    &mut self.state.config.font,
+                                    font,
+                                    self.localizer.font(font),
+                                );
+                            });
+                        }
+                    })
+                    .response;
+
+                // laid out right to left, the characters of the font picked end up before it
+                preview::strip(
+                    ui,
+                    self.state.config.font.data(),
+                    preview::CHARACTER_WIDTH,
+                    colors,
+                );
+
+                response
+            });
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        // `FX30` always points to the same big font, which isn't part of the font files
+        menu_item(ui, self.localizer.get("menu-big-font"), |ui| {
+            preview::strip(
+                ui,
+                &defaults::BIG_FONT,
+                preview::BIG_CHARACTER_WIDTH,
+                colors,
+            )
+        });
+    }
+
+    /// Shows the program, what it's loaded with and how fast it runs in the menu
+    fn program_tab(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        for selection in [Selection::Font, Selection::Program] {
+            let (label, path) = match selection {
+                Selection::Font => (
+                    self.localizer.get("menu-font"),
+                    self.state.font_path.as_ref(),
+                ),
+                Selection::Program => (
+                    self.localizer.get("menu-program"),
+                    self.state.playlist.current(),
+                ),
+                Selection::BeepSound | Selection::LibraryDirectory | Selection::Patch => {
+                    unreachable!()
+                }
+            };
+            let file_name = path.map(|path| file_name(path).to_owned());
+            let mut cleared = false;
+
+            menu_item(ui, &label, |ui| {
+                if file_name.is_some() {
+                    let clear = self.localizer.format("menu-clear", &[("item", &label)]);
+                    let response = ui
+                        .add(
+                            egui::Label::new(
+                                egui::RichText::new("×").color(self.state.config.theme.primary()),
+                            )
+                            .sense(egui::Sense::click()),
+                        )
+                        .on_hover_text(&clear);
+
+                    // the cross alone means nothing to a screen reader
+                    response.widget_info(|| {
+                        egui::WidgetInfo::labeled(egui::WidgetType::Button, &clear)
+                    });
+
+                    cleared = response.clicked();
+                }
+
+                ui.colored_label(
+                    egui::Color32::LIGHT_GRAY,
+                    file_name.unwrap_or_else(|| self.localizer.get("menu-none")),
+                )
+            });
+
+            if cleared {
+                match selection {
+                    Selection::Font => self.state.font_path = None,
+                    Selection::Program => self.state.playlist.remove(self.state.playlist.index()),
+                    Selection::BeepSound | Selection::LibraryDirectory | Selection::Patch => {
+                        unreachable!()
+                    }
+                }
+            }
+
+            ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
+                if ui
+                    .selectable_label(
+                        false,
+                        self.localizer.format("menu-load", &[("item", &label)]),
+                    )
+                    .clicked()
+                {
+                    self.state.error.message.clear();
+                    self.file_picker.open();
+                    self.state.selection = selection;
+                }
+            });
+
+            ui.add_space(MENU_SPACING);
+
+            if matches!(selection, Selection::Font) {
+                self.builtin_font(ui);
+                ui.add_space(MENU_SPACING);
+            }
+
+            // the font comes first, nothing after it can be changed while a program runs
+            if matches!(selection, Selection::Font) && self.frontend.started() {
+                ui.label(self.localizer.get("menu-font-replaced"));
+                ui.add_space(MENU_SPACING);
+                ui.set_enabled(false);
+            }
+        }
+
+        if let Some((_, integrity)) = self
+            .state
+            .integrity
+            .as_ref()
+            .filter(|(program, _)| self.state.playlist.current() == Some(program))
+        {
+            integrity.show(ui, &self.localizer);
+            ui.add_space(MENU_SPACING);
+        }
+
+        if let Some(program) = self.state.playlist.current().cloned() {
+            let mut patches = self.config_file.patches(&program);
+            let label = self.localizer.get("menu-patch");
+            let mut removed = None;
+
+            for (index, patch) in patches.iter().enumerate() {
+                menu_item(ui, &label, |ui| {
+                    let clear = self.localizer.format("menu-clear", &[("item", &label)]);
+                    let response = ui
+                        .add(
+                            egui::Label::new(
+                                egui::RichText::new("×").color(self.state.config.theme.primary()),
+                            )
+                            .sense(egui::Sense::click()),
+                        )
+                        .on_hover_text(&clear);
+
+                    response.widget_info(|| {
+                        egui::WidgetInfo::labeled(egui::WidgetType::Button, &clear)
+                    });
+
+                    if response.clicked() {
+                        removed = Some(index);
+                    }
+
+                    ui.colored_label(egui::Color32::LIGHT_GRAY, file_name(patch))
+                });
+            }
+
+            if let Some(index) = removed {
+                patches.remove(index);
+                self.set_patches(&program, &patches);
+            }
+
+            ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
+                if ui
+                    .selectable_label(
+                        false,
+                        self.localizer.format("menu-load", &[("item", &label)]),
+                    )
+                    .clicked()
+                {
+                    self.state.error.message.clear();
+                    self.file_picker.open();
+                    self.state.selection = Selection::Patch;
+                }
+            });
+
+            ui.add_space(MENU_SPACING);
+        }
+
+        if let Some(metadata) = self.metadata().cloned() {
+            if let Some(title) = &metadata.title {
+                ui.label(egui::RichText::new(title).strong());
+            }
+
+            if let Some(author) = &metadata.author {
+                ui.label(self.localizer.format("menu-author", &[("author", &author)]));
+            }
+
+            for (control, binding) in controls(&metadata, &self.state.config.keymap) {
+                menu_item(ui, control, |ui| {
+                    ui.colored_label(egui::Color32::LIGHT_GRAY, binding)
+                });
+            }
+
+            if metadata.quirks.is_some() || metadata.speed.is_some() {
+                ui.label(self.localizer.get("menu-recommended-settings"));
+            }
+
+            ui.add_space(MENU_SPACING);
+        }
+
+        if let Some(sheet) = self.sprite_sheet(ctx).cloned() {
+            let mut export = false;
+
+            egui::CollapsingHeader::new(self.localizer.get("menu-sprites")).show(ui, |ui| {
+                let size = sheet.size_vec2() / ctx.pixels_per_point();
+                let scale = (ui.available_width() / size.x).min(1.0);
+
+                ui.image(sheet.id(), size * scale);
+                export = ui
+                    .button(self.localizer.get("menu-export-sprites"))
+                    .clicked();
+            });
+
+            if export {
+                match sprites::export(
+                    self.state.playlist.current().unwrap(),
+                    self.state.config.quirks.load_address,
+                ) {
+                    Ok(path) => eprintln!("Saved the sprites to {}", path.display()),
+                    Err(error) => eprintln!("couldn't save the sprites, {}", error),
+                }
+            }
+
+            ui.add_space(MENU_SPACING);
+        }
+
+        let mut picked = None;
+
+        egui::CollapsingHeader::new(self.localizer.get("menu-library")).show(ui, |ui| {
+            let mut removed = None;
+
+            for (index, directory) in self.state.config.library_directories.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("×").clicked() {
+                        removed = Some(index);
+                    }
+
+                    ui.colored_label(egui::Color32::LIGHT_GRAY, directory.display().to_string());
+                });
+            }
+
+            if let Some(index) = removed {
+                self.state.config.library_directories.remove(index);
+                self.library
+                    .set_directories(self.state.config.library_directories.clone());
+            }
+
+            ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
+                if ui
+                    .selectable_label(
+                        false,
+                        self.localizer.format(
+                            "menu-load",
+                            &[("item", &self.localizer.get("menu-library-directory"))],
+                        ),
+                    )
+                    .clicked()
+                {
+                    self.state.error.message.clear();
+                    self.file_picker.open_folder();
+                    self.state.selection = Selection::LibraryDirectory;
+                }
+
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.state.library_query)
+                        .hint_text(self.localizer.get("menu-library-search")),
+                );
+
+                for entry in self
+                    .library
+                    .search(&self.state.library_query)
+                    .into_iter()
+                    .take(LIBRARY_RESULTS_SHOWN)
+                {
+                    if ui
+                        .selectable_label(false, entry.name())
+                        .on_hover_text(entry.path.display().to_string())
+                        .clicked()
+                    {
+                        picked = Some(entry.path);
+                    }
+                }
+            });
+        });
+
+        // a program picked from the library is queued like a loaded one
+        if let Some(path) = picked {
+            self.state.playlist.add(path);
+            self.state.playlist.select(self.state.playlist.len() - 1);
+        }
+
+        ui.add_space(MENU_SPACING);
+
+        if self.state.playlist.len() > 1 {
+            ui.label(self.localizer.get("menu-playlist"));
+
+            let mut selected = None;
+
+            ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
+                for (index, path) in self.state.playlist.iter().enumerate() {
+                    if ui
+                        .selectable_label(
+                            index == self.state.playlist.index(),
+                            format!("{}. {}", index + 1, file_name(path)),
+                        )
+                        .clicked()
+                    {
+                        selected = Some(index);
+                    }
+                }
+            });
+
+            if let Some(index) = selected {
+                self.state.playlist.select(index);
+            }
+
+            ui.add_space(MENU_SPACING);
+
+            menu_item(ui, self.localizer.get("menu-auto-advance"), |ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.state.config.auto_advance_minutes)
+                        .clamp_range(0..=600)
+                        .suffix(self.localizer.get("menu-minutes-suffix")),
+                )
+            });
+
+            ui.add_space(MENU_SPACING);
+        }
+
+        menu_item(ui, self.localizer.get("menu-instructions-per-tick"), |ui| {
+            let mut instructions_per_tick = self.state.config.instructions_per_tick.get();
+
+            let response =
+                ui.add(egui::DragValue::new(&mut instructions_per_tick).clamp_range(1..=1000));
+
+            if response.changed() {
+                if let Some(instructions_per_tick) =
+                    std::num::NonZeroU16::new(instructions_per_tick)
+                {
+                    self.state.config.instructions_per_tick = instructions_per_tick;
+                }
+            }
+
+            response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-core"), |ui| {
+            egui::ComboBox::from_id_source("core")
+                .selected_text(self.localizer.core(self.state.config.core))
+                .show_ui(ui, |ui| {
+                    for core in backend::CoreKind::ALL {
+                        ui.selectable_value(
+                            &mut self.state.config.core,
+                            core,
+                            self.localizer.core(core),
+                        );
+                    }
+                })
+                .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(
+            ui,
+            self.localizer.get("menu-throttle-in-background"),
+            |ui| ui.checkbox(&mut self.state.config.throttle_in_background, ""),
+        );
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-pause-on-halt"), |ui| {
+            ui.checkbox(&mut self.state.config.pause_on_halt, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+    }
+
+    /// Shows the quirks and the extensions programs are run with in the menu
+    fn quirks_tab(&mut self, ui: &mut egui::Ui) {
+        for item_data in [
+            (
+                "quirk-extended-memory",
+                &mut self.state.config.quirks.extended_memory,
+            ),
+            ("quirk-jump-vx", &mut self.state.config.quirks.jump_vx),
+            (
+                "quirk-key-checks-continue",
+                &mut self.state.config.quirks.key_checks_continue,
+            ),
+            (
+                "quirk-load-store-increment",
+                &mut self.state.config.quirks.load_store_increment,
+            ),
+            ("quirk-shift-vy", &mut self.state.config.quirks.shift_vy),
+            ("quirk-vf-reset", &mut self.state.config.quirks.vf_reset),
+            (
+                "quirk-wrap-sprites",
+                &mut self.state.config.quirks.wrap_sprites,
+            ),
+        ] {
+            menu_item(ui, self.localizer.get(item_data.0), |ui| {
+                ui.checkbox(item_data.1, "")
+            });
+
+            ui.add_space(MENU_SPACING);
+        }
+
+        menu_item(ui, self.localizer.get("quirk-load-address"), |ui| {
+            egui::ComboBox::from_id_source("load_address")
+                .selected_text(format!("0x{:03X}", self.state.config.quirks.load_address))
+                .show_ui(ui, |ui| {
+                    for (load_address, machine) in LOAD_ADDRESSES {
+                        ui.selectable_value(
+                            &mut self.state.config.quirks.load_address,
+                            load_address,
+                            format!("0x{:03X} ({})", load_address, machine),
+                        );
+                    }
+                })
+                .response
+        });
+
+        ui.add_space(MENU_SPACING);
+
+        menu_item(ui, self.localizer.get("menu-peripherals"), |ui| {
+            ui.checkbox(&mut self.state.config.peripherals, "")
+        });
+
+        ui.add_space(MENU_SPACING);
+    }
+
+    pub fn new(
+        cc: &eframe::CreationContext,
+        config_file: config::ConfigFile,
+        config: config::EmulatorConfig,
+        config_path: Option<path::PathBuf>,
+    ) -> Self {
+        config.theme.apply(&cc.egui_ctx);
+
+        let audio =
+            frontend::audio::Output::open(config.audio_device.as_deref(), config.audio_buffer_size)
+                .map_err(|error| eprintln!("couldn't open an audio device, {}", error))
+                .ok();
+
+        let localizer =
+            i18n::Localizer::new(&config.language, config.locale_directory().as_deref())
+                .unwrap_or_else(|error| {
+                    eprintln!("couldn't load the language, {}", error);
+                    i18n::Localizer::default()
+                });
+
+        let frontend = frontend::Frontend::new(
+            &cc.egui_ctx,
+            config.clone(),
+            audio
+                .as_ref()
+                .map_or_else(sync::Weak::new, frontend::audio::Output::mixer),
+        );
+        let library = library::Library::new(config.library_directories.clone());
+        let state = State {
+            always_on_top: config.always_on_top,
+            audio_checked: time::Instant::now(),
+            audio_devices: frontend::audio::output_devices(),
+            config: config.clone(),
+            conformance: None,
+            crash_report: None,
+            crash_snapshot: None,
+            error: Error {
+                message: String::with_capacity(128),
+                timestamp: time::Instant::now(),
+            },
+            halted: None,
+            integrity: None,
+            last_session: resume::LastSession::load().unwrap_or_else(|error| {
+                eprintln!("couldn't load the last session, {}", error);
+                None
+            }),
+            log: log::Log::default(),
+            log_shown: false,
+            running_config: config.clone(),
+            library_query: String::new(),
+            loading: None,
+            menu_raised: false,
+            menu_tab: MenuTab::default(),
+            metadata: (None, None),
+            font_path: None,
+            playlist: playlist::Playlist::new(),
+            program_started: time::Instant::now(),
+            rebinding: None,
+            selection: Selection::Font,
+            snapshot: None,
+            step_repeat: None,
+            sprites: (None, None),
+        };
+
+        Self {
+            audio,
+            config,
+            config_file,
+            config_path,
+            debugger: debugger::Debugger::new(),
+            display_texture: frontend.display_texture(),
+            file_picker: file_picker::FilePicker::new(),
+            frontend: frontend::FrontendHandle::new(frontend),
+            library,
+            localizer,
+            state,
+        }
+    }
+
+    fn auto_advance(&mut self, ctx: &egui::Context) {
+        let minutes = self.state.running_config.auto_advance_minutes;
+
+        if !self.frontend.started()
+            || self.state.menu_raised
+            || self.state.playlist.len() < 2
+            || minutes == 0
+        {
+            return;
+        }
+
+        let duration = time::Duration::from_secs(minutes as u64 * 60);
+        let elapsed = self.state.program_started.elapsed();
+
+        match elapsed >= duration {
+            true => self.switch_program(true),
+            false => ctx.request_repaint_after(duration - elapsed),
+        }
+    }
+
+    /// Opens the audio device set when it's there and not open yet, or the default one when the
+    /// open one is gone or none is open, and opens the device again when the buffer size changed
+    fn check_audio_device(&mut self) {
+        self.state.audio_checked = time::Instant::now();
+
+        let wanted = self.state.config.audio_device.as_ref();
+        let buffer_size = self.state.config.audio_buffer_size;
+
+        let open = self
+            .audio
+            .as_ref()
+            .map(|audio| (audio.device.as_ref(), audio.buffer_size));
+
+        // the devices aren't listed when the default one is both set and open
+        if wanted.is_none() && open == Some((None, buffer_size)) {
+            return;
+        }
+
+        let devices = frontend::audio::output_devices();
+        let device = wanted.filter(|wanted| devices.contains(wanted));
+
+        // without an audio output, one is opened as soon as a device shows up
+        if open == Some((device, buffer_size)) || open.is_none() && devices.is_empty() {
+            return;
+        }
+
+        let audio = match frontend::audio::Output::open(device.map(String::as_str), buffer_size) {
+            Ok(audio) => audio,
+            Err(error) => {
+                eprintln!("couldn't open the audio device, {}", error);
+                return;
+            }
+        };
+
+        if let Some(gone) = open
+            .and_then(|(device, _)| device)
+            .filter(|open| !devices.contains(open))
+        {
+            self.state.error.timestamp = time::Instant::now();
+            self.state.error.message.clear();
+            self.state.error.message.push_str(
+                &self
+                    .localizer
+                    .format("error-audio-device-gone", &[("device", gone)]),
+            );
+        }
+
+        let mixer = audio.mixer();
+
+        match self.frontend.started() {
+            true => self
+                .frontend
+                .with_stopped(|frontend| frontend.set_mixer(mixer)),
+            false => self.frontend.get().unwrap().set_mixer(mixer),
+        }
+
+        self.audio = Some(audio);
+    }
+
+    /// Reads the font and the program picked in the background, the program being started once
+    /// they're read
+    pub fn start(&mut self) {
+        self.state.loading = Some(loading::Loading::new(
+            self.state.font_path.clone(),
+            self.state.playlist.current().cloned(),
+        ));
+    }
+
+    /// Starts the program read, restoring `restore` once it's started
+    fn start_loaded(
+        &mut self,
+        font: Option<Result<Box<[u8; backend::FONT_SIZE]>, loader::LoadError>>,
+        program: Result<Vec<u8>, loader::LoadError>,
+        restore: Option<frontend::Snapshot>,
+    ) {
+        self.check_audio_device();
+        self.state.crash_report = None;
+        self.state.crash_snapshot = None;
+        self.state.halted = None;
+        self.state.last_session = None;
+        self.state.error.message.clear();
+
+        // the settings recommended by the program come before the ones the user set for it
+        let mut base_config = self.state.config.clone();
+
+        if let Some(metadata) = self.metadata() {
+            metadata.apply(&mut base_config);
+        }
+
+        // the program was read even if the font can't be used, for both errors to be shown at once
+        let font = self.check_font(font);
+        let frontend = self.frontend.get().unwrap();
+
+        let mut program = match program {
+            Ok(program) => program,
+            Err(error) => {
+                let error = self.localizer.load_error(&error);
+
+                self.state.playlist.remove(self.state.playlist.index());
+                self.push_error(&self.localizer.format("error-program", &[("error", &error)]));
+                return;
+            }
+        };
+
+        let Ok(font) = font else {
+            return;
+        };
+
+        // before the patches, to match the file
+        let integrity = integrity::Integrity::new(&program);
+
+        let config = match self
+            .config_file
+            .program_config(&base_config, self.state.playlist.current().unwrap())
+        {
+            Ok(config) => config,
+            Err(error) => {
+                self.state.error.timestamp = time::Instant::now();
+                self.state.error.message.push_str(
+                    &self
+                        .localizer
+                        .format("error-program-config", &[("error", &error)]),
+                );
+                return;
+            }
+        };
+
+        for path in config.patches.iter() {
+            if let Err(error) = patch::Patch::load(path).and_then(|patch| patch.apply(&mut program))
+            {
+                self.state.error.timestamp = time::Instant::now();
+                self.state.error.message.push_str(&self.localizer.format(
+                    "error-patch",
+                    &[("patch", &file_name(path)), ("error", &error)],
+                ));
+                return;
+            }
+        }
+
+        if let Err(error) = loader::check_program(&program, &config.quirks) {
+            let error = self.localizer.load_error(&error);
+
+            self.state.playlist.remove(self.state.playlist.index());
+            self.push_error(&self.localizer.format("error-program", &[("error", &error)]));
+            return;
+        }
+
+        let sound = match config.beep_sound.as_deref().map(loader::read).transpose() {
+            Ok(Some(sound)) => match frontend::Sound::from_bytes(sound) {
+                Ok(sound) => sound,
+                Err(error) => {
+                    self.state.config.beep_sound = None;
+                    self.state.error.timestamp = time::Instant::now();
+                    self.state.error.message.push_str(
+                        &self
+                            .localizer
+                            .format("error-beep-sound", &[("error", &error)]),
+                    );
+                    return;
+                }
+            },
+            Ok(None) => frontend::Sound::new().unwrap(),
+            Err(error) => {
+                let error = self.localizer.load_error(&error);
+
+                self.state.config.beep_sound = None;
+                self.state.error.timestamp = time::Instant::now();
+                self.state.error.message.push_str(
+                    &self
+                        .localizer
+                        .format("error-beep-sound", &[("error", &error)]),
+                );
+                return;
+            }
+        };
+
+        self.debugger
+            .open(self.state.playlist.current().unwrap(), integrity.clone());
+
+        frontend.set_config(config.clone());
+        frontend.set_sound(sound);
+        frontend.set_breakpoints(self.debugger.breakpoints(), self.debugger.watchpoints());
+        frontend.set_frozen(self.debugger.frozen());
+
+        frontend.update_texture();
+        match frontend.core.load(
+            Some(font.as_deref().unwrap_or(config.font.data())),
+            &program,
+        ) {
+            Ok(()) => (),
+            Err(error) => {
+                self.state.playlist.remove(self.state.playlist.index());
+                self.state.error.timestamp = time::Instant::now();
+                self.state.error.message.push_str(&self.localizer.format(
+                    "error-program",
+                    &[("error", &self.localizer.backend_error(&error))],
+                ));
+                return;
+            }
+        };
+
+        self.state.integrity = Some((self.state.playlist.current().unwrap().clone(), integrity));
+        self.frontend.set_continuing(false);
+        self.frontend.start();
+        self.state.menu_raised = false;
+        self.state.program_started = time::Instant::now();
+        self.state.snapshot = None;
+        self.debugger.clear();
+        self.state.running_config = config;
+
+        if let Some(snapshot) = restore {
+            self.frontend
+                .with_stopped(|frontend| frontend.restore(&snapshot));
+            self.state.snapshot = Some(snapshot);
+            self.inspect();
+        }
+    }
+
+    /// Starts the diagnostics program, with the test pattern of the configuration
+    fn start_diagnostics(&mut self) {
+        self.check_audio_device();
+        self.state.error.message.clear();
+
+        // there's nothing to debug in the diagnostics program
+        let mut config = self.state.config.clone();
+        config.debug_mode = false;
+
+        let frontend = self.frontend.get().unwrap();
+
+        frontend.set_config(config.clone());
+        frontend.set_breakpoints(Default::default(), Vec::new());
+        frontend.set_frozen(Default::default());
+
+        frontend.update_texture();
+        frontend
+            .core
+            .load(None, &diagnostics::program(config.test_pattern))
+            .expect("the diagnostics program is invalid");
+
+        self.frontend.set_continuing(false);
+        self.frontend.start();
+        self.state.menu_raised = false;
+        self.state.program_started = time::Instant::now();
+        self.state.snapshot = None;
+        self.debugger.clear();
+        self.state.running_config = config;
+    }
+
+    /// Sets the patches of `program` and saves them to the configuration file right away, since
+    /// they aren't part of the settings saved on exit
+    fn set_patches(&mut self, program: &path::Path, patches: &[path::PathBuf]) {
+        let result = self
+            .config_file
+            .set_patches(program, patches)
+            .and_then(|()| match self.config_path.as_ref() {
+                Some(path) => self.config_file.save(path),
+                None => Ok(()),
+            });
+
+        if let Err(error) = result {
+            eprintln!("couldn't save the configuration file, {}", error);
+        }
+    }
+
+    /// Starts the program that crashed and loads its state at the moment of the crash, which is also
+    /// kept as the saved state for it to be loaded again
+    fn load_crash_state(&mut self) {
+        let (_, snapshot) = match self.state.crash_snapshot.take() {
+            Some(crash) => crash,
+            None => return,
+        };
+
+        self.start();
+
+        if let Some(loading) = self.state.loading.as_mut() {
+            loading.restore = Some(snapshot);
+        }
+    }
+
+    /// Starts the program or replaces the font once the files being read are read, giving up the
+    /// program when another one was picked meanwhile
+    fn poll_loading(&mut self, ctx: &egui::Context) {
+        let Some(loaded) = self.state.loading.as_ref().map(loading::Loading::poll) else {
+            return;
+        };
+
+        let Some(loaded) = loaded else {
+            // nothing else repaints while the files are read with the program running
+            ctx.request_repaint_after(LOADING_POLL_INTERVAL);
+            return;
+        };
+
+        let restore = self
+            .state
+            .loading
+            .take()
+            .and_then(|loading| loading.restore);
+
+        match loaded.program {
+            Some((path, program)) if self.state.playlist.current() == Some(&path) => {
+                self.start_loaded(loaded.font, program, restore)
+            }
+            Some(_) => (),
+            None if self.frontend.started() => {
+                if let Ok(font) = self.check_font(loaded.font) {
+                    let font = font.as_deref().unwrap_or(self.state.config.font.data());
+
+                    self.frontend
+                        .with_stopped(|frontend| frontend.set_font(Some(font)));
+                    self.inspect();
+                }
+            }
+            None => (),
+        }
+    }
+
+    /// Reads the font picked in the background, to replace the one in the memory of the paused
+    /// program once it's read
+    fn replace_font(&mut self) {
+        self.state.loading = Some(loading::Loading::new(self.state.font_path.clone(), None));
+    }
+
+    /// Stops the program and starts it again as it's on disk now, along with its metadata and
+    /// sprites, for the changes made to it since to be seen
+    fn restart(&mut self) {
+        self.frontend.stop().reset();
+        self.state.metadata.0 = None;
+        self.state.sprites.0 = None;
+        self.start();
+    }
+
+    /// Loads the programs and the font of the last session and starts the program that was running
+    fn resume(&mut self) {
+        if let Some(last_session) = self.state.last_session.take() {
+            for path in last_session.playlist {
+                self.state.playlist.add(path);
+            }
+
+            self.state.playlist.select(last_session.index);
+            self.state.font_path = last_session.font;
+            self.start();
+        }
+    }
+
+    /// Rewinds the program to the snapshot nearest to the tick `frame` picked on the event track
+    fn rewind(&mut self, frame: u64) {
+        if let Some(frame) = self
+            .frontend
+            .with_stopped(|frontend| frontend.rewind(frame))
+        {
+            self.inspect();
+            self.debugger.set_status(
+                self.localizer
+                    .format("debugger-rewound", &[("frame", &frame)]),
+            );
+        }
+    }
+
+    /// Draws the display at `size` along with the overlays of debug mode, the visual beep and the hash
+    fn display(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, size: egui::Vec2) {
+        let orientation = self.state.running_config.orientation;
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+
+        orientation.paint(ui.painter(), self.display_texture, rect);
+
+        if let Some(pixel) = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked() && self.state.running_config.debug_mode)
+            .and_then(|position| display_pixel(rect, orientation, position))
+        {
+            self.debugger.inspect_pixel(pixel);
+        }
+
+        for pixel in self.debugger.changed_pixels() {
+            ui.painter().rect_stroke(
+                orientation.pixel_rect(rect, pixel),
+                0.0,
+                egui::Stroke::new(1.0, self.state.config.theme.primary()),
+            );
+        }
+
+        for pixel in self.debugger.flashed_pixels() {
+            ui.painter()
+                .rect_filled(orientation.pixel_rect(rect, pixel), 0.0, COLLISION_COLOR);
+        }
+
+        if let Some(display) = self
+            .debugger
+            .magnified()
+            .filter(|_| self.state.running_config.debug_mode)
+        {
+            magnifier::show(
+                ctx,
+                &self.localizer,
+                rect,
+                orientation,
+                display,
+                self.state.running_config.colors,
+                self.state.config.theme.primary(),
+            );
+        }
+
+        if self.state.running_config.visual_beep && self.frontend.beeping() {
+            ui.painter().rect_stroke(
+                rect.shrink(VISUAL_BEEP_WIDTH / 2.0),
+                0.0,
+                egui::Stroke::new(VISUAL_BEEP_WIDTH, self.state.config.theme.primary()),
+            );
+        }
+
+        if let Some((tick, hash)) = self
+            .frontend
+            .display_hash()
+            .filter(|_| self.state.running_config.hash_overlay)
+        {
+            let galley = ui.painter().layout_no_wrap(
+                format!("{:016x} @ {}", hash, tick),
+                egui::FontId::monospace(HASH_OVERLAY_FONT_SIZE),
+                self.state.config.theme.primary(),
+            );
+            let position = rect.right_bottom() - galley.size() - egui::Vec2::splat(MENU_SPACING);
+
+            ui.painter().rect_filled(
+                egui::Rect::from_min_size(position, galley.size()).expand(MENU_SPACING),
+                0.0,
+                egui::Color32::from_black_alpha(HASH_OVERLAY_BACKGROUND_ALPHA),
+            );
+            ui.painter().galley(position, galley);
+        }
+    }
+
+    /// Stops the running program and starts the next or the previous one in the playlist
+    fn switch_program(&mut self, forward: bool) {
+        if self.state.playlist.len() < 2 {
+            return;
+        }
+
+        self.frontend.stop().reset();
+
+        match forward {
+            true => self.state.playlist.next(),
+            false => self.state.playlist.previous(),
+        }
+
+        self.start();
+    }
+}
+
+impl eframe::App for App {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // the diagnostics program runs with an empty playlist, there's nothing to resume then
+        let result = match self.frontend.started() && !self.state.playlist.is_empty() {
+            true => resume::LastSession {
+                font: self.state.font_path.clone(),
+                index: self.state.playlist.index(),
+                playlist: self.state.playlist.iter().cloned().collect(),
+            }
+            .save(),
+            false => resume::LastSession::clear(),
+        };
+
+        if let Err(error) = result {
+            eprintln!("couldn't save the last session, {}", error);
+        }
+
+        // stop the frontend thread here rather than have it torn down with the process in the middle of a tick
+        if self.frontend.started() {
+            self.frontend.stop();
+        }
+
+        self.debugger.save();
+
+        if self.state.config == self.config {
+            return;
+        }
+
+        if let Some(path) = self.config_path.as_ref() {
+            // the command line options are left out of the file, only what was changed in the menu is saved
+            let result = self
+                .config_file
+                .apply_changes(&self.config, &self.state.config)
+                .and_then(|()| self.config_file.save(path));
+
+            if let Err(error) = result {
+                eprintln!("couldn't save the configuration file, {}", error);
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // changing the scale while the slider is being dragged would move the slider under the pointer
+        if !ctx.is_using_pointer() {
+            let pixels_per_point =
+                frame.info().native_pixels_per_point.unwrap_or(1.0) * self.state.config.ui_scale;
+
+            if (ctx.pixels_per_point() - pixels_per_point).abs() > f32::EPSILON {
+                ctx.set_pixels_per_point(pixels_per_point);
+            }
+        }
+
+        if self.state.always_on_top != self.state.config.always_on_top {
+            self.state.always_on_top = self.state.config.always_on_top;
+            frame.set_always_on_top(self.state.always_on_top);
+        }
+
+        // the geometry is kept up to date for it to be saved along with the configuration on exit
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.state.config.remember_window_geometry {
+            let window_info = &frame.info().window_info;
+
+            if !window_info.fullscreen {
+                let position = window_info.position;
+
+                self.state.config.window_geometry = Some(config::WindowGeometry {
+                    height: window_info.size.y.round(),
+                    width: window_info.size.x.round(),
+                    x: position.map(|position| position.x.round()),
+                    y: position.map(|position| position.y.round()),
+                });
+            }
+        }
+
+        if self.state.audio_checked.elapsed() >= AUDIO_DEVICE_CHECK_INTERVAL {
+            self.check_audio_device();
+        }
+
+        self.handle_input(ctx);
+        self.frontend.set_background(
+            self.state.running_config.throttle_in_background && !ctx.input().raw.has_focus,
+        );
+        self.frontend.update();
+        self.poll_loading(ctx);
+        self.auto_advance(ctx);
+        self.log(ctx);
+
+        if !self.frontend.started() || self.state.menu_raised {
+            return self.menu(ctx);
+        }
+
+        let detached = self.state.running_config.debug_mode && self.debugger.display_detached();
+
+        if self.state.running_config.debug_mode {
+            let panel = match detached {
+                true => egui::CentralPanel::default().show(ctx, |ui| {
+                    self.debugger
+                        .show(ui, &self.localizer, self.state.config.theme.primary())
+                }),
+                false => egui::SidePanel::right("debugger")
+                    .default_width(self.debugger.width())
+                    .show(ctx, |ui| {
+                        self.debugger
+                            .show(ui, &self.localizer, self.state.config.theme.primary())
+                    }),
+            };
+
+            if let Some(snapshot) = self.debugger.take_imported() {
+                self.frontend
+                    .with_stopped(|frontend| frontend.restore(&snapshot));
+                self.inspect();
+            }
+
+            if panel.inner {
+                let (breakpoints, watchpoints, frozen) = (
+                    self.debugger.breakpoints(),
+                    self.debugger.watchpoints(),
+                    self.debugger.frozen(),
+                );
+
+                self.frontend.with_stopped(|frontend| {
+                    frontend.set_breakpoints(breakpoints, watchpoints);
+                    frontend.set_frozen(frozen);
+                });
+            }
+
+            // the session is saved once resizing the panel or editing it is over
+            if !ctx.is_using_pointer() {
+                if !detached {
+                    self.debugger.set_width(panel.response.rect.width());
+                }
+
+                self.debugger.save();
+            }
+        }
+
+        // the tick picked on the event track under the display in debug mode
+        let mut rewound = None;
+
+        match detached {
+            // the debugger takes the whole window and the display is moved to one of its own
+            true => {
+                egui::Window::new(self.localizer.get("debugger-display-window"))
+                    .id(egui::Id::new("display"))
+                    .default_size(DETACHED_DISPLAY_SIZE)
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        let size = self.state.running_config.stretch.size(
+                            ui.available_size()
+                                - egui::vec2(
+                                    0.0,
+                                    debugger::EVENT_TRACK_HEIGHT + ui.spacing().item_spacing.y,
+                                ),
+                            self.state.running_config.orientation,
+                            ctx.pixels_per_point(),
+                        );
+                        self.display(ctx, ui, size);
+                        rewound = self.debugger.show_events(
+                            ui,
+                            &self.localizer,
+                            self.state.config.theme.primary(),
+                        );
+                    });
+            }
+            false => {
+                if self.state.running_config.debug_mode {
+                    rewound = egui::TopBottomPanel::bottom("events")
+                        .show(ctx, |ui| {
+                            self.debugger.show_events(
+                                ui,
+                                &self.localizer,
+                                self.state.config.theme.primary(),
+                            )
+                        })
+                        .inner;
+                }
+
+                let window_size = ctx.available_rect().size();
+                let size = self.state.running_config.stretch.size(
+                    window_size,
+                    self.state.running_config.orientation,
+                    ctx.pixels_per_point(),
+                );
+                let margin = egui::style::Margin::symmetric(
+                    (window_size[0] - size[0]) / 2.0,
+                    (window_size[1] - size[1]) / 2.0,
+                );
+
+                egui::CentralPanel::default()
+                    .frame(egui::Frame::central_panel(&ctx.style()).inner_margin(margin))
+                    .show(ctx, |ui| self.display(ctx, ui, size));
+            }
+        }
+
+        if let Some(frame) = rewound {
+            self.rewind(frame);
+        }
+
+        self.halted(ctx);
+
+        // the metadata looked up for the current program is the one of the running program
+        let metadata = self.state.metadata.1.as_ref();
+
+        if let Some(metadata) = metadata.filter(|metadata| {
+            self.state.running_config.controls_overlay && !metadata.controls.is_empty()
+        }) {
+            egui::Area::new("controls")
+                .anchor(
+                    egui::Align2::LEFT_TOP,
+                    egui::Vec2::splat(MENU_SPACING * 4.0),
+                )
+                .interactable(false)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        for (control, binding) in
+                            controls(metadata, &self.state.running_config.keymap)
+                        {
+                            menu_item(ui, control, |ui| ui.label(binding));
+                        }
+                    });
+                });
+        }
+    }
+}
+
+/// Returns what each key a program uses does, along with the physical key bound to it
+fn controls<'a>(
+    metadata: &'a config::Metadata,
+    keymap: &'a config::Keymap,
+) -> impl Iterator<Item = (&'a str, String)> + 'a {
+    metadata.controls.iter().map(|(key, control)| {
+        (
+            control.as_str(),
+            format!("{} ({:X})", keymap.get(*key).name(), key),
+        )
+    })
+}
+
+/// Returns the coordinates of the pixel at `position` of the display drawn in `rect` with
+/// `orientation`
+fn display_pixel(
+    rect: egui::Rect,
+    orientation: Orientation,
+    position: egui::Pos2,
+) -> Option<(usize, usize)> {
+    if !rect.contains(position) {
+        return None;
+    }
+
+    let point = orientation.to_display(rect, position);
+
+    Some((
+        ((point.x * backend::DISPLAY_BUFFER_WIDTH as f32) as usize)
+            .min(backend::DISPLAY_BUFFER_WIDTH - 1),
+        ((point.y * backend::DISPLAY_BUFFER_HEIGHT as f32) as usize)
+            .min(backend::DISPLAY_BUFFER_HEIGHT - 1),
+    ))
+}
+
+fn file_name(path: &path::Path) -> &str {
+    path.file_name()
+        .and_then(|file_name| file_name.to_str())
+        .unwrap_or_default()
+}
+
+pub fn menu_item(
+    ui: &mut egui::Ui,
+    text: impl Into<egui::WidgetText>,
+    add_contents: impl FnOnce(&mut egui::Ui) -> egui::Response,
+) {
+    ui.horizontal(|ui| {
+        let label = ui
+            .with_layout(egui::Layout::left_to_right(egui::Align::Min), |ui| {
+                ui.label(text)
+            })
+            .inner;
+
+        // the label is what assistive technology announces the control by
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+            add_contents(ui).labelled_by(label.id)
         });
-        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), add_contents);
     });
 }
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+use crate::backend;
+
+/// How the display is turned and flipped on the screen, for ports and homebrew made for portrait
+/// screens
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Orientation {
+    /// Flip the display horizontally before rotating it
+    pub mirrored: bool,
+    pub rotation: Rotation,
+}
+
+/// Clockwise rotation of the display
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Orientation {
+    /// Draws `texture` of the display in `rect`, oriented
+    pub fn paint(&self, painter: &egui::Painter, texture: egui::TextureId, rect: egui::Rect) {
+        let mut mesh = egui::Mesh::with_texture(texture);
+
+        for (corner, uv) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            mesh.vertices.push(egui::epaint::Vertex {
+                pos: self.to_screen(rect, egui::pos2(corner, uv)),
+                uv: egui::pos2(corner, uv),
+                color: egui::Color32::WHITE,
+            });
+        }
+
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(0, 2, 3);
+
+        painter.add(mesh);
+    }
+
+    /// Returns the rectangle the display pixel at `(x, y)` covers in the display drawn in `rect`
+    pub fn pixel_rect(&self, rect: egui::Rect, (x, y): (usize, usize)) -> egui::Rect {
+        let resolution = egui::vec2(
+            backend::DISPLAY_BUFFER_WIDTH as f32,
+            backend::DISPLAY_BUFFER_HEIGHT as f32,
+        );
+
+        egui::Rect::from_two_pos(
+            self.to_screen(
+                rect,
+                (egui::vec2(x as f32, y as f32) / resolution).to_pos2(),
+            ),
+            self.to_screen(
+                rect,
+                (egui::vec2(x as f32 + 1.0, y as f32 + 1.0) / resolution).to_pos2(),
+            ),
+        )
+    }
+
+    /// Returns the size `size` of the display takes on the screen, or the other way around
+    #[inline]
+    pub fn size(&self, size: egui::Vec2) -> egui::Vec2 {
+        match self.rotation {
+            Rotation::Quarter | Rotation::ThreeQuarters => egui::vec2(size.y, size.x),
+            Rotation::None | Rotation::Half => size,
+        }
+    }
+
+    /// Returns the point of the display, from (0, 0) at its top left corner to (1, 1) at its bottom
+    /// right one, at `position` in the display drawn in `rect`
+    pub fn to_display(&self, rect: egui::Rect, position: egui::Pos2) -> egui::Pos2 {
+        let (s, t) = (
+            (position.x - rect.min.x) / rect.width(),
+            (position.y - rect.min.y) / rect.height(),
+        );
+
+        let (u, v) = match self.rotation {
+            Rotation::None => (s, t),
+            Rotation::Quarter => (t, 1.0 - s),
+            Rotation::Half => (1.0 - s, 1.0 - t),
+            Rotation::ThreeQuarters => (1.0 - t, s),
+        };
+
+        match self.mirrored {
+            true => egui::pos2(1.0 - u, v),
+            false => egui::pos2(u, v),
+        }
+    }
+
+    /// Returns where `point` of the display, as returned by `to_display`, is in the display drawn
+    /// in `rect`
+    pub fn to_screen(&self, rect: egui::Rect, point: egui::Pos2) -> egui::Pos2 {
+        let u = match self.mirrored {
+            true => 1.0 - point.x,
+            false => point.x,
+        };
+        let v = point.y;
+
+        let (s, t) = match self.rotation {
+            Rotation::None => (u, v),
+            Rotation::Quarter => (1.0 - v, u),
+            Rotation::Half => (1.0 - u, 1.0 - v),
+            Rotation::ThreeQuarters => (v, 1.0 - u),
+        };
+
+        rect.min + egui::vec2(s, t) * rect.size()
+    }
+}
+
+impl Rotation {
+    pub const ALL: [Self; 4] = [Self::None, Self::Quarter, Self::Half, Self::ThreeQuarters];
+}
@@ -0,0 +1,76 @@
+use std::path;
+
+/// Programs queued to be run one after another
+pub struct Playlist {
+    index: usize,
+    paths: Vec<path::PathBuf>,
+}
+
+impl Playlist {
+    #[inline]
+    pub fn add(&mut self, path: path::PathBuf) {
+        self.paths.push(path);
+    }
+
+    #[inline]
+    pub fn current(&self) -> Option<&path::PathBuf> {
+        self.paths.get(self.index)
+    }
+
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &path::PathBuf> {
+        self.paths.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            index: 0,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Moves to the next program, wrapping around to the first one
+    pub fn next(&mut self) {
+        if !self.paths.is_empty() {
+            self.index = (self.index + 1) % self.paths.len();
+        }
+    }
+
+    /// Moves to the previous program, wrapping around to the last one
+    pub fn previous(&mut self) {
+        if !self.paths.is_empty() {
+            self.index = (self.index + self.paths.len() - 1) % self.paths.len();
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.paths.remove(index);
+
+        if index < self.index || self.index >= self.paths.len() {
+            self.index = self.index.saturating_sub(1);
+        }
+    }
+
+    #[inline]
+    pub fn select(&mut self, index: usize) {
+        if index < self.paths.len() {
+            self.index = index;
+        }
+    }
+}
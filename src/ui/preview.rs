@@ -0,0 +1,122 @@
+use std::time;
+
+use crate::backend;
+use crate::frontend;
+
+/// Size of a pixel of the preview in points
+const CELL_SIZE: f32 = 6.0;
+/// Width of a character in pixels, the rightmost columns of the sprites are left blank
+pub const CHARACTER_WIDTH: usize = 4;
+/// Width of a character of the big font in pixels
+pub const BIG_CHARACTER_WIDTH: usize = 8;
+/// Height of the strip of every character in points
+const STRIP_HEIGHT: f32 = 14.0;
+/// Number of pixels around and between the characters of the preview
+const MARGIN: usize = 1;
+/// Time each pair of characters stays on the preview
+const PERIOD: time::Duration = time::Duration::from_millis(750);
+
+/// Shows the characters of `font` two at a time with `colors`, the pixels turned off at each
+/// change fading out like on the display when `fade_effect` is set
+pub fn show(
+    ui: &mut egui::Ui,
+    font: &[u8; backend::FONT_SIZE],
+    colors: frontend::Colors,
+    fade_effect: bool,
+) -> egui::Response {
+    let size = egui::vec2(
+        (CHARACTER_WIDTH * 2 + MARGIN * 3) as f32,
+        (backend::CHARACTER_SIZE + MARGIN * 2) as f32,
+    ) * CELL_SIZE;
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+
+    let time = ui.input().time;
+    let step = (time / PERIOD.as_secs_f64()) as usize;
+    let elapsed = time::Duration::from_secs_f64(time % PERIOD.as_secs_f64());
+
+    ui.painter().rect_filled(rect, 0.0, colors.inactive);
+
+    for (index, character) in [step * 2 % 16, (step * 2 + 1) % 16].into_iter().enumerate() {
+        // the character shown at the same place before the last change
+        let previous = (character + 14) % 16;
+
+        for y in 0..backend::CHARACTER_SIZE {
+            for x in 0..CHARACTER_WIDTH {
+                let pixel = |character: usize| {
+                    font[character * backend::CHARACTER_SIZE + y] & (0x80 >> x) != 0
+                };
+
+                let color = match (pixel(character), pixel(previous)) {
+                    (true, _) => colors.active,
+                    (false, true) if fade_effect => match colors.fade(elapsed) {
+                        Some(color) => color,
+                        None => continue,
+                    },
+                    (false, _) => continue,
+                };
+
+                ui.painter().rect_filled(
+                    egui::Rect::from_min_size(
+                        rect.min
+                            + egui::vec2(
+                                (MARGIN + index * (CHARACTER_WIDTH + MARGIN) + x) as f32,
+                                (MARGIN + y) as f32,
+                            ) * CELL_SIZE,
+                        egui::Vec2::splat(CELL_SIZE),
+                    ),
+                    0.0,
+                    color,
+                );
+            }
+        }
+    }
+
+    // the preview only changes when the characters do, and while they fade
+    ui.ctx()
+        .request_repaint_after(match fade_effect && colors.fade(elapsed).is_some() {
+            true => time::Duration::ZERO,
+            false => PERIOD - elapsed,
+        });
+
+    response
+}
+
+/// Shows every character of `font` from 0 to F in a row with `colors`, the characters being
+/// `width` pixels wide, scaled for the strip to fit a list whatever their size
+pub fn strip(
+    ui: &mut egui::Ui,
+    font: &[u8],
+    width: usize,
+    colors: frontend::Colors,
+) -> egui::Response {
+    let height = font.len() / backend::KEY_COUNT;
+    let cell_size = STRIP_HEIGHT / (height + MARGIN * 2) as f32;
+    let size = egui::vec2(
+        (backend::KEY_COUNT * (width + MARGIN) + MARGIN) as f32,
+        (height + MARGIN * 2) as f32,
+    ) * cell_size;
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+
+    ui.painter().rect_filled(rect, 0.0, colors.inactive);
+
+    for (index, character) in font.chunks(height).enumerate() {
+        for (y, row) in character.iter().enumerate() {
+            for x in (0..width).filter(|x| row & (0x80 >> x) != 0) {
+                ui.painter().rect_filled(
+                    egui::Rect::from_min_size(
+                        rect.min
+                            + egui::vec2(
+                                (MARGIN + index * (width + MARGIN) + x) as f32,
+                                (MARGIN + y) as f32,
+                            ) * cell_size,
+                        egui::Vec2::splat(cell_size),
+                    ),
+                    0.0,
+                    colors.active,
+                );
+            }
+        }
+    }
+
+    response
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::io;
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+const RESUME_FILE_NAME: &str = "resume.toml";
+
+/// What was running when the emulator was last closed, to be started again in one click
+#[derive(Deserialize, Serialize)]
+pub struct LastSession {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<path::PathBuf>,
+    /// Index of the running program in the playlist
+    pub index: usize,
+    pub playlist: Vec<path::PathBuf>,
+}
+
+impl LastSession {
+    /// Forgets the last session, for when nothing was running at exit
+    pub fn clear() -> Result<(), config::ConfigError> {
+        match Self::path().map(fs::remove_file) {
+            Some(Err(error)) if error.kind() != io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Loads the last session, if there's one
+    pub fn load() -> Result<Option<Self>, config::ConfigError> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Some(toml::from_str(&contents)?)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn path() -> Option<path::PathBuf> {
+        dirs::data_dir().map(|path| path.join(env!("CARGO_PKG_NAME")).join(RESUME_FILE_NAME))
+    }
+
+    pub fn save(&self) -> Result<(), config::ConfigError> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Ok(fs::write(path, toml::to_string(self)?)?)
+    }
+}
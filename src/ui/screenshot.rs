@@ -0,0 +1,63 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path;
+use std::time;
+
+use crate::backend::interfaces;
+
+/// Saves `image` as a PNG file in the pictures directory and returns its path
+pub fn save(image: &egui::ColorImage) -> Result<path::PathBuf, String> {
+    let path = new_path("")?;
+    let file = fs::File::create(&path).map_err(|error| format!("{}", error))?;
+
+    write(image, io::BufWriter::new(file))?;
+
+    Ok(path)
+}
+
+/// Saves the pixels of the display as they are in a PNG file in the pictures directory and
+/// returns its path
+pub fn save_display(image: &interfaces::DisplayImage) -> Result<path::PathBuf, String> {
+    let path = new_path("-display")?;
+
+    image.save(&path).map_err(|error| format!("{}", error))?;
+
+    Ok(path)
+}
+
+/// Encodes `image` as PNG into `writer`
+pub fn write(image: &egui::ColorImage, writer: impl io::Write) -> Result<(), String> {
+    let mut encoder = png::Encoder::new(writer, image.size[0] as u32, image.size[1] as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let data: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+        .collect();
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|error| format!("{}", error))
+}
+
+/// Returns the path of a new PNG file in the pictures directory, named after the time with `suffix`
+fn new_path(suffix: &str) -> Result<path::PathBuf, String> {
+    let directory = match dirs::picture_dir() {
+        Some(directory) => directory,
+        None => env::current_dir().map_err(|error| format!("{}", error))?,
+    };
+
+    Ok(directory.join(format!(
+        "{}-{}{}.png",
+        env!("CARGO_PKG_NAME"),
+        time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis(),
+        suffix
+    )))
+}
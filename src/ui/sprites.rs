@@ -0,0 +1,157 @@
+use std::collections;
+use std::fs;
+use std::io;
+use std::path;
+
+use crate::backend;
+
+/// Number of sprites in a row of a sheet
+const SHEET_COLUMNS: usize = 8;
+/// Size of a cell of a sheet in sprite pixels, enough for a 16×16 sprite and a gap around it
+const SHEET_CELL_SIZE: usize = 18;
+/// Size of a sprite pixel in the pixels of a sheet
+const SHEET_SCALE: usize = 4;
+
+/// Likely sprite data in a program, found by where it's drawn from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    /// Address of the sprite in memory
+    pub address: usize,
+    /// Number of rows, each a byte wide, or two bytes for 16×16 sprites
+    pub height: usize,
+    pub wide: bool,
+}
+
+impl Sprite {
+    /// Returns the number of bytes of the sprite
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.height * self.width() / 8
+    }
+
+    #[inline]
+    fn width(&self) -> usize {
+        match self.wide {
+            true => 16,
+            false => 8,
+        }
+    }
+}
+
+/// Scans `program`, loaded at `load_address`, for the sprites drawn by a DXYN instruction after an
+/// ANNN one pointing I to them, a sprite drawn with different heights is kept with the tallest one
+pub fn find(program: &[u8], load_address: usize) -> Vec<Sprite> {
+    let mut sprites = collections::BTreeMap::new();
+    let mut index = None;
+
+    for bytes in program.chunks_exact(2) {
+        let instruction = backend::Instruction::new([bytes[0], bytes[1]]);
+
+        match (instruction.operator_code(), instruction.operand_nn()) {
+            (0xA, _) => index = Some(instruction.operand_nnn()),
+            // I no longer points where ANNN set it to
+            (0xF, 0x1E | 0x29 | 0x30 | 0x55 | 0x65) => index = None,
+            (0xD, _) => {
+                let address = match index {
+                    Some(address) => address,
+                    None => continue,
+                };
+
+                let sprite = match instruction.operand_n() {
+                    0 => Sprite {
+                        address,
+                        height: 16,
+                        wide: true,
+                    },
+                    height => Sprite {
+                        address,
+                        height: height as usize,
+                        wide: false,
+                    },
+                };
+
+                let end = address + sprite.size();
+
+                // sprites of the font or beyond the end of the program can't be extracted from it
+                if address < load_address || end > load_address + program.len() {
+                    continue;
+                }
+
+                sprites
+                    .entry(address)
+                    .and_modify(|found: &mut Sprite| {
+                        if sprite.height * sprite.width() > found.height * found.width() {
+                            *found = sprite;
+                        }
+                    })
+                    .or_insert(sprite);
+            }
+            _ => (),
+        }
+    }
+
+    sprites.into_values().collect()
+}
+
+/// Draws `sprites` of `program`, loaded at `load_address`, in rows of a sheet, in white over a
+/// transparent background
+pub fn sheet(program: &[u8], load_address: usize, sprites: &[Sprite]) -> egui::ColorImage {
+    let columns = sprites.len().clamp(1, SHEET_COLUMNS);
+    let rows = sprites.len().div_ceil(SHEET_COLUMNS);
+    let cell = SHEET_CELL_SIZE * SHEET_SCALE;
+    let mut image = egui::ColorImage::new(
+        [columns * cell, rows.max(1) * cell],
+        egui::Color32::TRANSPARENT,
+    );
+
+    for (n, sprite) in sprites.iter().enumerate() {
+        let bytes_per_row = sprite.width() / 8;
+        let data = &program[sprite.address - load_address..];
+        let origin = (
+            (n % SHEET_COLUMNS * SHEET_CELL_SIZE + 1) * SHEET_SCALE,
+            (n / SHEET_COLUMNS * SHEET_CELL_SIZE + 1) * SHEET_SCALE,
+        );
+
+        for row in 0..sprite.height {
+            for column in 0..sprite.width() {
+                let byte = data[row * bytes_per_row + column / 8];
+
+                if byte & (0x80 >> (column % 8)) == 0 {
+                    continue;
+                }
+
+                for y in 0..SHEET_SCALE {
+                    for x in 0..SHEET_SCALE {
+                        image[(
+                            origin.0 + column * SHEET_SCALE + x,
+                            origin.1 + row * SHEET_SCALE + y,
+                        )] = egui::Color32::WHITE;
+                    }
+                }
+            }
+        }
+    }
+
+    image
+}
+
+/// Saves a sheet of the sprites found in the program at `path`, loaded at `load_address`, next to
+/// it and returns the path of the sheet
+pub fn export(path: &path::Path, load_address: usize) -> Result<path::PathBuf, String> {
+    let program = fs::read(path).map_err(|error| format!("{}", error))?;
+    let sprites = find(&program, load_address);
+
+    if sprites.is_empty() {
+        return Err("no sprites were found in the program".to_owned());
+    }
+
+    let sheet_path = path.with_extension("sprites.png");
+    let file = fs::File::create(&sheet_path).map_err(|error| format!("{}", error))?;
+
+    super::screenshot::write(
+        &sheet(&program, load_address, &sprites),
+        io::BufWriter::new(file),
+    )?;
+
+    Ok(sheet_path)
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use super::Orientation;
+use crate::backend;
+
+/// How the display is sized in the space it's given
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stretch {
+    /// The largest size with square pixels
+    #[default]
+    Fit,
+    /// All of the space, whatever the shape of the pixels
+    Fill,
+    /// The largest 4:3 size, the shape of a CRT television, with the pixels stretched to it
+    FourThirds,
+}
+
+impl Stretch {
+    pub const ALL: [Self; 3] = [Self::Fit, Self::Fill, Self::FourThirds];
+
+    /// Returns the size of the display turned with `orientation` in `available`
+    ///
+    /// When fitted, the size is rounded down so that every pixel of the display covers the same
+    /// number of physical pixels.
+    pub fn size(
+        self,
+        available: egui::Vec2,
+        orientation: Orientation,
+        pixels_per_point: f32,
+    ) -> egui::Vec2 {
+        let resolution = orientation.size(egui::vec2(
+            backend::DISPLAY_BUFFER_WIDTH as f32,
+            backend::DISPLAY_BUFFER_HEIGHT as f32,
+        ));
+
+        match self {
+            Self::Fit => {
+                let scale = (available * pixels_per_point / resolution).min_elem();
+                let scale = match scale >= 1.0 {
+                    true => scale.floor(),
+                    false => scale,
+                };
+
+                resolution * scale / pixels_per_point
+            }
+            Self::Fill => available,
+            Self::FourThirds => {
+                // the television is turned along with the display
+                let ratio = orientation.size(egui::vec2(4.0, 3.0));
+
+                ratio * (available / ratio).min_elem()
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use crate::defaults;
+use crate::frontend;
+
+/// Palettes of the user interface, each with display colors to go with it
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Default,
+    /// White and yellow on black, for low vision
+    HighContrast,
+    /// Colors of the Okabe-Ito palette, which stay apart with the common color vision deficiencies
+    ColorblindSafe,
+}
+
+impl Theme {
+    pub const ALL: [Self; 3] = [Self::Default, Self::HighContrast, Self::ColorblindSafe];
+
+    /// Sets the visuals of `ctx` to the ones of the theme
+    pub fn apply(&self, ctx: &egui::Context) {
+        let mut visuals = egui::Visuals::dark();
+
+        visuals.selection.bg_fill = self.primary();
+        visuals.selection.stroke.color = egui::Color32::WHITE;
+
+        visuals.widgets.hovered.bg_fill = self.primary();
+
+        visuals.widgets.noninteractive.fg_stroke.color = egui::Color32::WHITE;
+
+        visuals.window_fill = self.secondary();
+
+        if *self == Self::HighContrast {
+            let stroke = egui::Stroke::new(1.0, egui::Color32::WHITE);
+
+            // text on the primary color is black, white on yellow can hardly be read
+            visuals.selection.stroke.color = egui::Color32::BLACK;
+            visuals.widgets.hovered.fg_stroke.color = egui::Color32::BLACK;
+            visuals.widgets.active.fg_stroke.color = egui::Color32::BLACK;
+            visuals.widgets.active.bg_fill = self.primary();
+
+            visuals.widgets.inactive.bg_fill = egui::Color32::BLACK;
+            visuals.widgets.inactive.bg_stroke = stroke;
+            visuals.widgets.inactive.fg_stroke.color = egui::Color32::WHITE;
+            visuals.widgets.noninteractive.bg_stroke = stroke;
+
+            visuals.extreme_bg_color = egui::Color32::BLACK;
+            visuals.panel_fill = egui::Color32::BLACK;
+            visuals.window_stroke = stroke;
+        }
+
+        ctx.set_visuals(visuals);
+    }
+
+    /// Returns the display colors that go with the theme
+    pub fn colors(&self) -> frontend::Colors {
+        match self {
+            Self::Default | Self::HighContrast => defaults::COLORS,
+            // yellow and blue are told apart with both red and green deficiencies
+            Self::ColorblindSafe => frontend::Colors {
+                active: egui::Color32::from_rgb(0xF0, 0xE4, 0x42),
+                inactive: egui::Color32::from_rgb(0x00, 0x72, 0xB2),
+            },
+        }
+    }
+
+    /// Returns the color of selections, highlights and hovered widgets
+    pub fn primary(&self) -> egui::Color32 {
+        match self {
+            Self::Default => egui::Color32::from_rgb(0x81, 0x5B, 0xA4),
+            Self::HighContrast => egui::Color32::from_rgb(0xFF, 0xFF, 0x00),
+            Self::ColorblindSafe => egui::Color32::from_rgb(0xE6, 0x9F, 0x00),
+        }
+    }
+
+    /// Returns the color of window backgrounds
+    pub fn secondary(&self) -> egui::Color32 {
+        match self {
+            Self::Default | Self::ColorblindSafe => egui::Color32::from_rgb(0x1C, 0x1C, 0x1C),
+            Self::HighContrast => egui::Color32::BLACK,
+        }
+    }
+}